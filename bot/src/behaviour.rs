@@ -0,0 +1,133 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tokio::{sync::mpsc, time};
+
+use makar_proxy::{protocol::VarInt, versions::v1_8_8::*};
+
+use crate::{client::Sender, stats::Stats};
+
+/// How long a chat message may take to come back before it's counted as
+/// lost.
+const ECHO_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Behaviour {
+    Idle,
+    Chat,
+    Walk,
+}
+
+impl FromStr for Behaviour {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "idle" => Ok(Self::Idle),
+            "chat" => Ok(Self::Chat),
+            "walk" => Ok(Self::Walk),
+            s => Err(format!("unknown behaviour {s}")),
+        }
+    }
+}
+
+pub struct Script {
+    pub behaviours: Vec<Behaviour>,
+    pub chat_interval: Duration,
+    pub duration: Duration,
+}
+
+struct Position {
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+fn message(name: &str, number: u32) -> String {
+    format!("{name} says hello #{number}")
+}
+
+pub async fn play(
+    name: &str,
+    mut rx: mpsc::Receiver<ClientBoundPacket>,
+    mut tx: Sender,
+    script: &Script,
+    stats: Arc<Stats>,
+) -> Result<(), Box<dyn Error>> {
+    tx.send(ProxyBoundPacket::ClientSettings {
+        locale: "en_US".to_string(),
+        view_distance: 8,
+        chat_mode: 0,
+        chat_colors: 1,
+        displayed_skin_parts: 0x7F,
+    })
+    .await?;
+
+    let deadline = time::sleep(script.duration);
+    tokio::pin!(deadline);
+    let mut chat = time::interval(script.chat_interval);
+    let mut walk = time::interval(Duration::from_millis(50));
+
+    let mut spawn = Position {
+        x: 0.0,
+        y: 64.0,
+        z: 0.0,
+    };
+    let mut angle: f64 = 0.0;
+    let mut messages = 0;
+    // when each message still waiting for its echo was sent, by its number
+    let mut pending: HashMap<u32, Instant> = HashMap::new();
+
+    loop {
+        tokio::select! {
+            _ = &mut deadline => return Ok(()),
+            packet = rx.recv() => match packet {
+                Some(ClientBoundPacket::KeepAlive { id }) => {
+                    tx.send(ProxyBoundPacket::KeepAlive { id: VarInt::new(id.value()) }).await?;
+                }
+                Some(ClientBoundPacket::PlayerPositionAndLook { x, y, z, yaw, pitch, .. }) => {
+                    spawn = Position { x, y, z };
+                    tx.send(ProxyBoundPacket::PlayerPositionAndLook { x, y, z, yaw, pitch, on_ground: 1 }).await?;
+                }
+                Some(ClientBoundPacket::ChatMessage { json, .. }) => {
+                    let number = json
+                        .text
+                        .rsplit_once(" #")
+                        .and_then(|(_, number)| number.parse().ok())
+                        .filter(|number| json.text.ends_with(&message(name, *number)));
+                    if let Some(sent) = number.and_then(|number| pending.remove(&number)) {
+                        stats.latency("chat echo", sent.elapsed());
+                    }
+                }
+                Some(_) => {}
+                None => return Err("disconnected".into()),
+            },
+            _ = chat.tick(), if script.behaviours.contains(&Behaviour::Chat) => {
+                pending.retain(|_, sent| {
+                    let lost = sent.elapsed() > ECHO_TIMEOUT;
+                    if lost {
+                        stats.lost("chat echo");
+                    }
+                    !lost
+                });
+                messages += 1;
+                pending.insert(messages, Instant::now());
+                tx.send(ProxyBoundPacket::ChatMessage { message: message(name, messages) }).await?;
+            }
+            _ = walk.tick(), if script.behaviours.contains(&Behaviour::Walk) => {
+                angle += 0.05;
+                tx.send(ProxyBoundPacket::PlayerPosition {
+                    x: spawn.x + 3.0 * angle.cos(),
+                    y: spawn.y,
+                    z: spawn.z + 3.0 * angle.sin(),
+                    on_ground: 1,
+                }).await?;
+            }
+        }
+    }
+}
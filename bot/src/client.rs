@@ -0,0 +1,180 @@
+use std::{error::Error, sync::Arc, time::Instant};
+
+use bytes::Bytes;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpStream,
+    },
+    sync::mpsc,
+};
+
+use log::debug;
+
+use makar_proxy::{
+    protocol::{Serialize, State, VarInt},
+    versions::v1_8_8::*,
+};
+
+use crate::stats::Stats;
+
+pub struct Client {
+    reader: OwnedReadHalf,
+    writer: OwnedWriteHalf,
+    state: State,
+    stats: Arc<Stats>,
+}
+
+async fn read_packet<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    state: &State,
+    stats: &Stats,
+) -> Result<Option<ClientBoundPacket>, Box<dyn Error>> {
+    let size = VarInt::read(reader).await?.value();
+    if size < 0 {
+        return Err(format!("invalid packet size {size}").into());
+    }
+    let mut buf = vec![0u8; size as usize];
+    reader.read_exact(&mut buf).await?;
+    stats.received(size as usize + VarInt::new(size).size() as usize);
+
+    match ClientBoundPacket::deserialize(state, Bytes::from(buf)) {
+        Ok(packet) => Ok(Some(packet)),
+        Err(e) => {
+            // the server may send packets we don't know about yet
+            debug!("skipping packet: {e}");
+            Ok(None)
+        }
+    }
+}
+
+impl Client {
+    pub async fn connect(address: &str, stats: Arc<Stats>) -> Result<Self, Box<dyn Error>> {
+        let (reader, writer) = TcpStream::connect(address).await?.into_split();
+        Ok(Self {
+            reader,
+            writer,
+            state: State::Handshake,
+            stats,
+        })
+    }
+
+    pub async fn send(&mut self, packet: ProxyBoundPacket) -> Result<(), Box<dyn Error>> {
+        let buf = packet.serialize();
+        self.writer.write_all(&buf).await?;
+        self.stats.sent(buf.len());
+        Ok(())
+    }
+
+    pub async fn recv(&mut self) -> Result<Option<ClientBoundPacket>, Box<dyn Error>> {
+        read_packet(&mut self.reader, &self.state, &self.stats).await
+    }
+
+    async fn handshake(&mut self, address: &str, next_state: State) -> Result<(), Box<dyn Error>> {
+        let (host, port) = address.rsplit_once(':').unwrap_or((address, "25565"));
+        self.send(ProxyBoundPacket::Handshake {
+            protocol: VarInt::new(PROTOCOL_VERSION),
+            address: host.to_string(),
+            port: port.parse()?,
+            next_state: match next_state {
+                State::Status => 1,
+                State::Login => 2,
+                state => return Err(format!("can't handshake into {state:?}").into()),
+            },
+        })
+        .await?;
+        self.state = next_state;
+        Ok(())
+    }
+
+    /// Queries the server list status, returning the status json.
+    pub async fn status(&mut self, address: &str) -> Result<String, Box<dyn Error>> {
+        self.handshake(address, State::Status).await?;
+        self.send(ProxyBoundPacket::StatusRequest {}).await?;
+        let status = loop {
+            if let Some(ClientBoundPacket::StatusResponse { status }) = self.recv().await? {
+                break status;
+            }
+        };
+
+        let start = Instant::now();
+        self.send(ProxyBoundPacket::StatusPing { payload: 0 })
+            .await?;
+        loop {
+            if let Some(ClientBoundPacket::StatusPong { .. }) = self.recv().await? {
+                self.stats.latency("status ping", start.elapsed());
+                return Ok(status);
+            }
+        }
+    }
+
+    /// Logs in and waits for the server to let us into the game.
+    pub async fn login(&mut self, address: &str, username: &str) -> Result<(), Box<dyn Error>> {
+        let start = Instant::now();
+        self.handshake(address, State::Login).await?;
+        self.send(ProxyBoundPacket::LoginStart {
+            name: username.to_string(),
+        })
+        .await?;
+        loop {
+            if let Some(ClientBoundPacket::LoginSuccess { .. }) = self.recv().await? {
+                break;
+            }
+        }
+
+        self.state = State::Play;
+        loop {
+            if let Some(ClientBoundPacket::JoinGame { .. }) = self.recv().await? {
+                self.stats.latency("login", start.elapsed());
+                return Ok(());
+            }
+        }
+    }
+
+    /// Splits the connection so packets can be received while the bot acts.
+    pub fn into_play(self) -> (mpsc::Receiver<ClientBoundPacket>, Sender) {
+        let (tx, rx) = mpsc::channel(100);
+        let Self {
+            mut reader,
+            writer,
+            state,
+            stats,
+        } = self;
+
+        {
+            let stats = stats.clone();
+            tokio::spawn(async move {
+                loop {
+                    let packet = match read_packet(&mut reader, &state, &stats).await {
+                        Ok(Some(packet)) => packet,
+                        Ok(None) => continue,
+                        Err(e) => {
+                            debug!("connection closed: {e}");
+                            break;
+                        }
+                    };
+                    if tx.send(packet).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        (rx, Sender { writer, stats })
+    }
+}
+
+pub struct Sender {
+    writer: OwnedWriteHalf,
+    stats: Arc<Stats>,
+}
+
+impl Sender {
+    pub async fn send(&mut self, packet: ProxyBoundPacket) -> Result<(), Box<dyn Error>> {
+        let buf = packet.serialize();
+        self.writer.write_all(&buf).await?;
+        self.stats.sent(buf.len());
+        Ok(())
+    }
+}
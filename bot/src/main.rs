@@ -0,0 +1,108 @@
+mod behaviour;
+mod client;
+mod stats;
+
+use std::{
+    error::Error,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+
+use behaviour::{Behaviour, Script};
+use client::Client;
+use stats::Stats;
+
+const USAGE: &str = "usage: makar-bot [--address host:port] [--bots n] [--behaviour idle,chat,walk] [--duration secs] [--chat-interval ms] [--ramp ms]";
+
+struct Config {
+    address: String,
+    bots: usize,
+    ramp: Duration,
+    script: Script,
+}
+
+fn parse_args() -> Result<Config, Box<dyn Error>> {
+    let mut config = Config {
+        address: "127.0.0.1:25565".to_string(),
+        bots: 1,
+        ramp: Duration::from_millis(50),
+        script: Script {
+            behaviours: vec![Behaviour::Idle],
+            chat_interval: Duration::from_secs(1),
+            duration: Duration::from_secs(10),
+        },
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {arg}"));
+        match arg.as_str() {
+            "--address" => config.address = value()?,
+            "--bots" => config.bots = value()?.parse()?,
+            "--ramp" => config.ramp = Duration::from_millis(value()?.parse()?),
+            "--duration" => config.script.duration = Duration::from_secs(value()?.parse()?),
+            "--chat-interval" => {
+                config.script.chat_interval = Duration::from_millis(value()?.parse()?)
+            }
+            "--behaviour" => {
+                config.script.behaviours = value()?
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()?
+            }
+            _ => return Err(USAGE.into()),
+        }
+    }
+
+    Ok(config)
+}
+
+async fn bot(name: String, config: Arc<Config>, stats: Arc<Stats>) -> Result<(), Box<dyn Error>> {
+    let mut client = Client::connect(&config.address, stats.clone()).await?;
+    client.login(&config.address, &name).await?;
+    info!("{name} joined");
+
+    let (rx, tx) = client.into_play();
+    behaviour::play(&name, rx, tx, &config.script, stats).await
+}
+
+#[tokio::main]
+pub async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+
+    let config = Arc::new(parse_args()?);
+    let stats = Arc::new(Stats::default());
+
+    let status = Client::connect(&config.address, stats.clone())
+        .await?
+        .status(&config.address)
+        .await?;
+    info!("server status: {status}");
+
+    let start = Instant::now();
+    let mut bots = Vec::with_capacity(config.bots);
+    for i in 0..config.bots {
+        let name = format!("bot{i}");
+        let ramp = config.ramp;
+        let config = config.clone();
+        let stats = stats.clone();
+        bots.push(tokio::spawn(async move {
+            match bot(name.clone(), config, stats).await {
+                Ok(_) => {}
+                Err(e) => {
+                    warn!("{name} ended unexpectingly: {e}");
+                }
+            };
+        }));
+        tokio::time::sleep(ramp).await;
+    }
+
+    for bot in bots {
+        bot.await?;
+    }
+
+    print!("{}", stats.report(start.elapsed()));
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+#[derive(Default)]
+pub struct Stats {
+    packets_sent: AtomicU64,
+    packets_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    latencies: Mutex<BTreeMap<&'static str, Vec<Duration>>>,
+    lost: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl Stats {
+    pub fn sent(&self, bytes: usize) {
+        self.packets_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn received(&self, bytes: usize) {
+        self.packets_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn latency(&self, name: &'static str, value: Duration) {
+        self.latencies
+            .lock()
+            .unwrap()
+            .entry(name)
+            .or_default()
+            .push(value);
+    }
+
+    /// Counts something that never got the answer its latency is measured
+    /// by.
+    pub fn lost(&self, name: &'static str) {
+        *self.lost.lock().unwrap().entry(name).or_default() += 1;
+    }
+
+    pub fn report(&self, elapsed: Duration) -> String {
+        let secs = elapsed.as_secs_f64();
+        let mut res = String::new();
+
+        for (name, counter) in [
+            ("packets sent", &self.packets_sent),
+            ("packets received", &self.packets_received),
+            ("bytes sent", &self.bytes_sent),
+            ("bytes received", &self.bytes_received),
+        ] {
+            let value = counter.load(Ordering::Relaxed);
+            res += &format!("{name}: {value} ({:.1}/s)\n", value as f64 / secs);
+        }
+
+        for (name, values) in self.latencies.lock().unwrap().iter_mut() {
+            values.sort();
+            let percentile = |p: usize| values[(values.len() - 1) * p / 100];
+            let avg = values.iter().sum::<Duration>() / values.len() as u32;
+            res += &format!(
+                "{name}: n={} min={:?} avg={avg:?} p50={:?} p99={:?} max={:?}\n",
+                values.len(),
+                values[0],
+                percentile(50),
+                percentile(99),
+                values[values.len() - 1],
+            );
+        }
+        for (name, lost) in self.lost.lock().unwrap().iter() {
+            res += &format!("{name} lost: {lost}\n");
+        }
+
+        res
+    }
+}
@@ -1,7 +1,7 @@
 use bytes::{BufMut, BytesMut};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Gamemode {
     Survival,
     Creative,
@@ -17,7 +17,7 @@ pub enum Difficulty {
     Hard,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Chat {
     pub text: String,
     pub color: Option<String>,
@@ -26,25 +26,413 @@ pub struct Chat {
     pub underlined: Option<bool>,
     pub strikethrough: Option<bool>,
     pub obfuscated: Option<bool>,
+    /// A key clients translate to their own language, in place of the text.
+    pub translate: Option<String>,
+    /// The components filling the placeholders of the translation.
+    pub with: Vec<Chat>,
+}
+
+/// Where a chat message is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChatPosition {
+    Chat,
+    /// Shown even to players who only want to see commands in chat.
+    System,
+    /// Above the hotbar, fading out after three seconds.
+    ActionBar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockPosition {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub enum ServerBoundPacket {
-    JoinGameRequest { id: u128, username: String },
-    ClientSettings { player: u128, locale: String },
-    ChatMessage { player: u128, message: String },
+pub struct Abilities {
+    pub invulnerable: bool,
+    pub flying: bool,
+    pub allow_flying: bool,
+    pub creative_mode: bool,
+    pub flying_speed: f32,
+    pub walking_speed: f32,
+}
+
+/// A 16x16x16 cube of blocks, stored in y, z, x order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkSection {
+    /// Block id shifted left by 4, ored with the block metadata.
+    pub blocks: Vec<u16>,
+    /// One nibble per block.
+    pub block_light: Vec<u8>,
+    /// One nibble per block, only present in dimensions with a sky.
+    pub sky_light: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkColumn {
+    pub x: i32,
+    pub z: i32,
+    /// Sixteen sections from bottom to top, empty ones being `None`.
+    pub sections: Vec<Option<ChunkSection>>,
+    /// One biome id per column, in z, x order.
+    pub biomes: Vec<u8>,
+}
+
+/// A block change relative to its chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockRecord {
+    pub x: u8,
+    pub y: u8,
+    pub z: u8,
+    pub block: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub id: i16,
+    pub count: u8,
+    pub damage: i16,
+    pub meta: ItemMeta,
+}
+
+/// How an item is shown, kept in its nbt.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemMeta {
+    /// Replaces the name of the item, with legacy formatting codes.
+    pub name: Option<String>,
+    /// Lines below the name, with legacy formatting codes.
+    pub lore: Vec<String>,
+    /// Makes the item shine like an enchanted one.
+    pub glint: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MetadataValue {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Float(f32),
+    String(String),
+    Slot(Option<ItemStack>),
+    Position(BlockPosition),
+    Rotation(f32, f32, f32),
+}
+
+/// An entry of an entity's metadata, whose meaning depends on its index and
+/// the kind of entity.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Metadata {
+    pub index: u8,
+    pub value: MetadataValue,
+}
+
+/// Animations played by entities, in the order the protocol numbers them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Animation {
+    SwingArm,
+    TakeDamage,
+    LeaveBed,
+    EatFood,
+    CriticalEffect,
+    MagicCriticalEffect,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerListEntry {
+    pub uuid: u128,
+    pub name: String,
+    pub gamemode: Gamemode,
+    /// Latency in milliseconds, shown as bars.
+    pub ping: i32,
+    /// Shown instead of the name when set.
+    pub display_name: Option<Chat>,
+}
+
+/// Changes to the player list, entries being identified by their uuid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PlayerListAction {
+    Add(Vec<PlayerListEntry>),
+    UpdateGamemode(Vec<(u128, Gamemode)>),
+    UpdateLatency(Vec<(u128, i32)>),
+    UpdateDisplayName(Vec<(u128, Option<Chat>)>),
+    Remove(Vec<u128>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NameTagVisibility {
+    Always,
+    Never,
+    HideForOtherTeams,
+    HideForOwnTeam,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeamInfo {
+    pub display_name: String,
+    /// Shown before the names of members.
+    pub prefix: String,
+    /// Shown after the names of members.
+    pub suffix: String,
+    pub friendly_fire: bool,
+    pub see_invisible_teammates: bool,
+    pub name_tag_visibility: NameTagVisibility,
+    /// Named like chat colors, members' names keeping their color when
+    /// unset.
+    pub color: Option<String>,
+}
+
+/// Changes to a team, whose members are identified by their name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TeamAction {
+    Create {
+        info: TeamInfo,
+        members: Vec<String>,
+    },
+    Remove,
+    Update(TeamInfo),
+    AddMembers(Vec<String>),
+    RemoveMembers(Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ObjectiveKind {
+    Integer,
+    Hearts,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ObjectiveAction {
+    Create {
+        display_name: String,
+        kind: ObjectiveKind,
+    },
+    Remove,
+    Update {
+        display_name: String,
+        kind: ObjectiveKind,
+    },
+}
+
+/// Where an objective is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum DisplaySlot {
+    List,
+    Sidebar,
+    BelowName,
+    /// The sidebar of the members of teams with a color, by its code.
+    TeamSidebar(u8),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+pub enum ServerBoundPacket {
+    JoinGameRequest {
+        id: u128,
+        username: String,
+    },
+    LeaveGame {
+        player: u128,
+    },
+    ClientSettings {
+        player: u128,
+        locale: String,
+        view_distance: u8,
+        /// Bits of the skin layers shown on the player.
+        skin_parts: u8,
+    },
+    ChatMessage {
+        player: u128,
+        message: String,
+    },
+    /// Sent by clients every tick, with their position and look when they
+    /// changed.
+    PlayerMovement {
+        player: u128,
+        position: Option<(f64, f64, f64)>,
+        look: Option<(f32, f32)>,
+        on_ground: bool,
+    },
+    SwingArm {
+        player: u128,
+    },
+    /// The hotbar slot the player holds, from 0 to 8.
+    HeldItemChange {
+        player: u128,
+        slot: u8,
+    },
+    /// A click in a window, slot -999 being outside of it. The item is what
+    /// the client thinks was in the slot.
+    ClickWindow {
+        player: u128,
+        window_id: u8,
+        slot: i16,
+        button: u8,
+        action: i16,
+        mode: ClickMode,
+        item: Option<ItemStack>,
+    },
+    /// Acknowledges a click the server rejected.
+    ConfirmTransaction {
+        player: u128,
+        window_id: u8,
+        action: i16,
+        accepted: bool,
+    },
+    CloseWindow {
+        player: u128,
+        window_id: u8,
+    },
+    /// Sets a slot of the inventory in creative mode, slot -1 dropping the
+    /// item.
+    CreativeInventoryAction {
+        player: u128,
+        slot: i16,
+        item: Option<ItemStack>,
+    },
+    PlayerDigging {
+        player: u128,
+        status: DiggingStatus,
+        position: BlockPosition,
+        face: BlockFace,
+    },
+    /// Uses the item held on a block, without a face when the item is used
+    /// in the air.
+    PlayerBlockPlacement {
+        player: u128,
+        position: BlockPosition,
+        face: Option<BlockFace>,
+        item: Option<ItemStack>,
+        /// Where the face was clicked, in sixteenths of a block.
+        cursor: (u8, u8, u8),
+    },
+    UseEntity {
+        player: u128,
+        target: i32,
+        action: UseEntityAction,
+    },
+    ClientStatus {
+        player: u128,
+        action: ClientAction,
+    },
+}
+
+/// Windows opened over the inventory of a player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowKind {
+    /// A chest with 1 to 6 rows of 9 slots.
+    Chest(u8),
+    CraftingTable,
+    Dispenser,
+    Hopper,
+}
+
+/// How players click slots, in the order the protocol numbers them, the
+/// button clicked changing what each does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClickMode {
+    Click,
+    ShiftClick,
+    /// Swaps with the hotbar slot of the number key pressed.
+    NumberKey,
+    MiddleClick,
+    Drop,
+    /// A step of dragging items over several slots.
+    Drag,
+    /// Gathers the items like the one held.
+    DoubleClick,
+}
+
+/// What a player does with the block they're looking at or the item they
+/// hold, in the order the protocol numbers it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiggingStatus {
+    Started,
+    Cancelled,
+    Finished,
+    DropStack,
+    DropItem,
+}
+
+/// How a player uses the entity they're looking at.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum UseEntityAction {
+    Interact,
+    Attack,
+    /// Interacts with the entity where it was clicked, relative to its feet.
+    InteractAt {
+        x: f32,
+        y: f32,
+        z: f32,
+    },
+}
+
+/// What a client asks for, in the order the protocol numbers it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClientAction {
+    /// Respawns a dead player.
+    Respawn,
+    RequestStats,
+    /// Opening the inventory, for the achievement taught that way.
+    OpenInventory,
+}
+
+/// Events played by entities, like getting hurt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EntityStatus {
+    Hurt,
+    Dead,
+}
+
+/// A change to the state of the game shown by a client.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GameStateChange {
+    BeginRaining,
+    EndRaining,
+    ChangeGamemode(Gamemode),
+    /// Shows the credits, which the client closes by asking to respawn.
+    EnterCredits,
+    DemoMessage(DemoMessage),
+    /// How dark the rain makes the sky, from 0 to 1.
+    RainLevel(f32),
+    /// How dark thunder makes the sky, from 0 to 1, only while raining.
+    ThunderLevel(f32),
+}
+
+/// A screen or hint shown to players of the demo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DemoMessage {
+    Welcome,
+    MovementControls,
+    JumpControl,
+    InventoryControl,
+}
+
+/// A side of a block, in the order the protocol numbers them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlockFace {
+    Bottom,
+    Top,
+    North,
+    South,
+    West,
+    East,
+}
+
+/// Changes to the title in the middle of the screen, times being in ticks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TitleAction {
-    Set {
-        title: Option<Chat>,
-        subtitle: Option<Chat>,
+    /// Shows a title, with the subtitle and times set last.
+    SetTitle(Chat),
+    /// Sets the subtitle shown with the next title.
+    SetSubtitle(Chat),
+    SetTimes {
         fade_in: u32,
         stay: u32,
         fade_out: u32,
     },
     Hide,
+    /// Hides the title, clearing the subtitle and times.
     Reset,
 }
 
@@ -63,12 +451,323 @@ pub enum ProxyBoundPacket {
     ChatMessage {
         player: u128,
         json: Chat,
-        position: u8,
+        position: ChatPosition,
     },
+    /// Title actions applied in order.
     Title {
         player: u128,
-        action: TitleAction,
+        actions: Vec<TitleAction>,
+    },
+    SpawnPosition {
+        player: u128,
+        position: BlockPosition,
     },
+    PlayerAbilities {
+        player: u128,
+        abilities: Abilities,
+    },
+    HeldItemChange {
+        player: u128,
+        slot: u8,
+    },
+    ChunkData {
+        player: u128,
+        chunk: ChunkColumn,
+    },
+    /// Makes the client forget a column.
+    UnloadChunk {
+        player: u128,
+        x: i32,
+        z: i32,
+    },
+    /// Several columns at once, all of them having sky light or none of
+    /// them.
+    MapChunkBulk {
+        player: u128,
+        sky_light: bool,
+        chunks: Vec<ChunkColumn>,
+    },
+    PlayerPositionAndLook {
+        player: u128,
+        x: f64,
+        y: f64,
+        z: f64,
+        yaw: f32,
+        pitch: f32,
+    },
+    TimeUpdate {
+        player: u128,
+        world_age: i64,
+        time_of_day: i64,
+    },
+    BlockChange {
+        player: u128,
+        position: BlockPosition,
+        block: u16,
+    },
+    MultiBlockChange {
+        player: u128,
+        chunk_x: i32,
+        chunk_z: i32,
+        records: Vec<BlockRecord>,
+    },
+    PlayerListItem {
+        player: u128,
+        action: PlayerListAction,
+    },
+    /// Text above and below the player list, empty text hiding it.
+    PlayerListHeaderFooter {
+        player: u128,
+        header: Chat,
+        footer: Chat,
+    },
+    Teams {
+        player: u128,
+        name: String,
+        action: TeamAction,
+    },
+    ScoreboardObjective {
+        player: u128,
+        name: String,
+        action: ObjectiveAction,
+    },
+    /// Sets the score of an entry for an objective, removing it when unset.
+    UpdateScore {
+        player: u128,
+        entry: String,
+        objective: String,
+        value: Option<i32>,
+    },
+    /// Shows an objective in a slot, an empty name clearing it.
+    DisplayScoreboard {
+        player: u128,
+        slot: DisplaySlot,
+        objective: String,
+    },
+    /// Spawns a player, whose uuid has to be in the player list.
+    SpawnPlayer {
+        player: u128,
+        entity_id: i32,
+        uuid: u128,
+        x: f64,
+        y: f64,
+        z: f64,
+        yaw: f32,
+        pitch: f32,
+        current_item: i16,
+        metadata: Vec<Metadata>,
+    },
+    /// Velocities are in blocks per tick.
+    SpawnObject {
+        player: u128,
+        entity_id: i32,
+        kind: u8,
+        x: f64,
+        y: f64,
+        z: f64,
+        yaw: f32,
+        pitch: f32,
+        data: i32,
+        velocity: (f64, f64, f64),
+    },
+    SpawnMob {
+        player: u128,
+        entity_id: i32,
+        kind: u8,
+        x: f64,
+        y: f64,
+        z: f64,
+        yaw: f32,
+        pitch: f32,
+        head_yaw: f32,
+        velocity: (f64, f64, f64),
+        metadata: Vec<Metadata>,
+    },
+    DestroyEntities {
+        player: u128,
+        entity_ids: Vec<i32>,
+    },
+    /// Shows an item flying to the entity picking it up, the item still
+    /// having to be destroyed.
+    CollectItem {
+        player: u128,
+        collected: i32,
+        collector: i32,
+    },
+    /// Moves an entity by less than four blocks on every axis, deltas being
+    /// multiples of 1/32 of a block.
+    EntityRelativeMove {
+        player: u128,
+        entity_id: i32,
+        delta: (f64, f64, f64),
+        on_ground: bool,
+    },
+    EntityLook {
+        player: u128,
+        entity_id: i32,
+        yaw: f32,
+        pitch: f32,
+        on_ground: bool,
+    },
+    EntityLookAndRelativeMove {
+        player: u128,
+        entity_id: i32,
+        delta: (f64, f64, f64),
+        yaw: f32,
+        pitch: f32,
+        on_ground: bool,
+    },
+    EntityTeleport {
+        player: u128,
+        entity_id: i32,
+        x: f64,
+        y: f64,
+        z: f64,
+        yaw: f32,
+        pitch: f32,
+        on_ground: bool,
+    },
+    EntityHeadLook {
+        player: u128,
+        entity_id: i32,
+        head_yaw: f32,
+    },
+    EntityVelocity {
+        player: u128,
+        entity_id: i32,
+        velocity: (f64, f64, f64),
+    },
+    EntityMetadata {
+        player: u128,
+        entity_id: i32,
+        metadata: Vec<Metadata>,
+    },
+    Animation {
+        player: u128,
+        entity_id: i32,
+        animation: Animation,
+    },
+    OpenWindow {
+        player: u128,
+        window_id: u8,
+        kind: WindowKind,
+        title: Chat,
+    },
+    CloseWindow {
+        player: u128,
+        window_id: u8,
+    },
+    /// Sets a slot of a window, the one of the item held by the cursor being
+    /// slot -1 of window -1.
+    SetSlot {
+        player: u128,
+        window_id: i8,
+        slot: i16,
+        item: Option<ItemStack>,
+    },
+    WindowItems {
+        player: u128,
+        window_id: u8,
+        items: Vec<Option<ItemStack>>,
+    },
+    /// Tells whether a click was accepted.
+    ConfirmTransaction {
+        player: u128,
+        window_id: u8,
+        action: i16,
+        accepted: bool,
+    },
+    /// Cracks on a block being dug by an entity, stages past 9 removing
+    /// them.
+    BlockBreakAnimation {
+        player: u128,
+        entity_id: i32,
+        position: BlockPosition,
+        stage: i8,
+    },
+    /// A sound or particles, like those of a block breaking.
+    Effect {
+        player: u128,
+        effect: i32,
+        position: BlockPosition,
+        data: i32,
+    },
+    /// The health and hunger bars of a player, who dies at 0 health.
+    UpdateHealth {
+        player: u128,
+        health: f32,
+        food: i32,
+        saturation: f32,
+    },
+    /// Respawns a player in a dimension, keeping the chunks they have when
+    /// it's the one they were in.
+    Respawn {
+        player: u128,
+        dimension: i32,
+        difficulty: Difficulty,
+        gamemode: Gamemode,
+        level_type: String,
+    },
+    EntityStatus {
+        player: u128,
+        entity_id: i32,
+        status: EntityStatus,
+    },
+    ChangeGameState {
+        player: u128,
+        change: GameStateChange,
+    },
+}
+
+impl ProxyBoundPacket {
+    /// The player the packet is sent to.
+    pub fn player(&self) -> u128 {
+        match self {
+            Self::JoinGame { player, .. }
+            | Self::ChatMessage { player, .. }
+            | Self::Title { player, .. }
+            | Self::SpawnPosition { player, .. }
+            | Self::PlayerAbilities { player, .. }
+            | Self::HeldItemChange { player, .. }
+            | Self::ChunkData { player, .. }
+            | Self::UnloadChunk { player, .. }
+            | Self::MapChunkBulk { player, .. }
+            | Self::PlayerPositionAndLook { player, .. }
+            | Self::TimeUpdate { player, .. }
+            | Self::BlockChange { player, .. }
+            | Self::MultiBlockChange { player, .. }
+            | Self::PlayerListItem { player, .. }
+            | Self::PlayerListHeaderFooter { player, .. }
+            | Self::Teams { player, .. }
+            | Self::ScoreboardObjective { player, .. }
+            | Self::UpdateScore { player, .. }
+            | Self::DisplayScoreboard { player, .. }
+            | Self::SpawnPlayer { player, .. }
+            | Self::SpawnObject { player, .. }
+            | Self::SpawnMob { player, .. }
+            | Self::DestroyEntities { player, .. }
+            | Self::CollectItem { player, .. }
+            | Self::EntityRelativeMove { player, .. }
+            | Self::EntityLook { player, .. }
+            | Self::EntityLookAndRelativeMove { player, .. }
+            | Self::EntityTeleport { player, .. }
+            | Self::EntityHeadLook { player, .. }
+            | Self::EntityVelocity { player, .. }
+            | Self::EntityMetadata { player, .. }
+            | Self::Animation { player, .. }
+            | Self::OpenWindow { player, .. }
+            | Self::CloseWindow { player, .. }
+            | Self::SetSlot { player, .. }
+            | Self::WindowItems { player, .. }
+            | Self::ConfirmTransaction { player, .. }
+            | Self::BlockBreakAnimation { player, .. }
+            | Self::Effect { player, .. }
+            | Self::UpdateHealth { player, .. }
+            | Self::Respawn { player, .. }
+            | Self::EntityStatus { player, .. }
+            | Self::ChangeGameState { player, .. } => *player,
+        }
+    }
 }
 
 macro_rules! packet_impl {
@@ -0,0 +1,22 @@
+mod codegen;
+
+use std::{collections::BTreeMap, env, error::Error, fs, path::Path};
+
+use codegen::Protocol;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("cargo:rerun-if-changed=data");
+    println!("cargo:rerun-if-changed=codegen.rs");
+
+    let mut versions = BTreeMap::new();
+    for entry in fs::read_dir("data")? {
+        let path = entry?.path().join("protocol.json");
+        let protocol: Protocol = serde_json::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+        versions.insert(protocol.version.minecraft_version.clone(), protocol);
+    }
+
+    let dest = Path::new(&env::var("OUT_DIR")?).join("versions.rs");
+    fs::write(dest, codegen::generate(&versions)?)?;
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::Write,
+};
+
+use serde::Deserialize;
+
+const STATES: [(&str, &str); 4] = [
+    ("handshaking", "Handshake"),
+    ("status", "Status"),
+    ("login", "Login"),
+    ("play", "Play"),
+];
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Version {
+    pub minecraft_version: String,
+    version: i32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Directions {
+    #[serde(default)]
+    to_client: Vec<Packet>,
+    #[serde(default)]
+    to_server: Vec<Packet>,
+}
+
+#[derive(Deserialize)]
+struct Packet {
+    name: String,
+    id: String,
+    fields: Vec<Field>,
+}
+
+#[derive(Deserialize)]
+struct Field {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Deserialize)]
+pub struct Protocol {
+    pub version: Version,
+    #[serde(flatten)]
+    states: HashMap<String, Directions>,
+}
+
+/// The rust type decoding a protocol type, named as in minecraft-data
+/// where it has them.
+fn rust_type(kind: &str) -> Option<&'static str> {
+    Some(match kind {
+        "bool" => "bool",
+        "i8" => "i8",
+        "u8" => "u8",
+        "i16" => "i16",
+        "u16" => "u16",
+        "i32" => "i32",
+        "i64" => "i64",
+        "u64" => "u64",
+        "f32" => "f32",
+        "f64" => "f64",
+        "UUID" => "u128",
+        "varint" => "VarInt",
+        "varlong" => "VarLong",
+        "string" => "String",
+        "buffer" => "ByteArray",
+        "restBuffer" => "Vec<u8>",
+        "position" => "Position",
+        "slot" => "Slot",
+        "windowItems" => "Slots",
+        "entityMetadata" => "EntityMetadata",
+        "chat" => "Chat",
+        "chatPosition" => "ChatPosition",
+        "gamemode" => "Gamemode",
+        "difficulty" => "Difficulty",
+        "titleAction" => "TitleAction",
+        "playerListAction" => "PlayerListAction",
+        "objectiveAction" => "ObjectiveAction",
+        "displaySlot" => "DisplaySlot",
+        "scoreUpdate" => "ScoreUpdate",
+        "teamAction" => "TeamAction",
+        "useEntityAction" => "UseEntityAction",
+        "blockRecords" => "BlockRecords",
+        "chunkBulk" => "ChunkBulk",
+        "entityIds" => "EntityIds",
+        "objectData" => "ObjectData",
+        _ => return None,
+    })
+}
+
+fn parse_id(id: &str) -> Result<i32, Box<dyn Error>> {
+    Ok(match id.strip_prefix("0x") {
+        Some(hex) => i32::from_str_radix(hex, 16)?,
+        None => id.parse()?,
+    })
+}
+
+fn generate_direction(
+    out: &mut String,
+    version: &str,
+    protocol: &Protocol,
+    macro_name: &str,
+    direction: fn(&Directions) -> &Vec<Packet>,
+) -> Result<(), Box<dyn Error>> {
+    let mut ids = HashMap::new();
+    let mut names = HashMap::new();
+
+    writeln!(out, "    crate::{macro_name}! {{")?;
+    for (key, state) in STATES {
+        let packets = match protocol.states.get(key) {
+            Some(directions) => direction(directions),
+            None => continue,
+        };
+        for packet in packets {
+            let id = parse_id(&packet.id)?;
+            if let Some(other) = ids.insert((state, id), &packet.name) {
+                return Err(format!(
+                    "{version}: {} and {other} share id {id:#04x} in state {key}",
+                    packet.name
+                )
+                .into());
+            }
+            if names.insert(&packet.name, key).is_some() {
+                return Err(format!("{version}: packet {} defined twice", packet.name).into());
+            }
+
+            writeln!(out, "        {}, {state}, {id:#04x} => {{", packet.name)?;
+            for field in &packet.fields {
+                let kind = rust_type(&field.kind).ok_or_else(|| {
+                    format!(
+                        "{version}: unknown type {} for {}.{}",
+                        field.kind, packet.name, field.name
+                    )
+                })?;
+                writeln!(out, "            {}: {kind},", field.name)?;
+            }
+            writeln!(out, "        }},")?;
+        }
+    }
+    writeln!(out, "    }}")?;
+    Ok(())
+}
+
+/// Generates a module of packet definitions for every version, keyed by
+/// their name.
+pub fn generate(versions: &BTreeMap<String, Protocol>) -> Result<String, Box<dyn Error>> {
+    let mut out = String::new();
+    for (name, protocol) in versions {
+        for key in protocol.states.keys() {
+            if !STATES.iter().any(|(state, _)| state == key) {
+                return Err(format!("{name}: unknown state {key}").into());
+            }
+        }
+
+        writeln!(out, "pub mod v{} {{", name.replace('.', "_"))?;
+        writeln!(out, "    use crate::protocol::*;")?;
+        writeln!(out)?;
+        writeln!(out, "    pub const NAME: &str = {name:?};")?;
+        writeln!(
+            out,
+            "    pub const PROTOCOL_VERSION: i32 = {};",
+            protocol.version.version
+        )?;
+        writeln!(out)?;
+        generate_direction(&mut out, name, protocol, "define_client_bound", |d| {
+            &d.to_client
+        })?;
+        writeln!(out)?;
+        generate_direction(&mut out, name, protocol, "define_proxy_bound", |d| {
+            &d.to_server
+        })?;
+        writeln!(out, "}}")?;
+    }
+
+    Ok(out)
+}
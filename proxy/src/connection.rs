@@ -1,15 +1,19 @@
 use std::error::Error;
 
-use bytes::{BufMut, Bytes, BytesMut};
+use bytes::Bytes;
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     net::TcpStream,
     sync::mpsc,
 };
 
-use log::{debug, info};
+use log::info;
 
-use crate::protocol::State;
+use crate::protocol::{
+    block::{block_face, digging_status},
+    window::click_mode,
+    State, VarInt,
+};
 use crate::versions::v1_8_8::*;
 
 pub struct Player {
@@ -32,39 +36,44 @@ pub async fn connection_task(
         username: None,
     };
 
+    // errors aren't Send, so they're kept as text across the awaits below
+    let res = serve(&mut socket, &mut rx, &tx, &ctx, &mut player)
+        .await
+        .map_err(|e| e.to_string());
+    // players leave the game however their connection ended
+    if let Player {
+        id: Some(id),
+        username: Some(username),
+        ..
+    } = player
+    {
+        ctx.players_tx
+            .send(crate::players::Message::Del(id))
+            .await?;
+        let packet = makar_protocol::ServerBoundPacket::LeaveGame { player: id };
+        ctx.server_tx.send(packet).await?;
+        info!("player {username} disconnected");
+    }
+    res.map_err(Into::into)
+}
+
+async fn serve(
+    socket: &mut TcpStream,
+    rx: &mut mpsc::Receiver<Vec<u8>>,
+    tx: &mpsc::Sender<Vec<u8>>,
+    ctx: &crate::ProxyContext,
+    player: &mut Player,
+) -> Result<(), Box<dyn Error>> {
     loop {
         tokio::select! {
             b = socket.read_u8() => {
-                let size = {
-                    let mut res: i32 = 0;
-                    let mut pos: u8 = 0;
-                    let mut b = match b {
-                        Ok(b) => b,
-                        Err(_) => {
-                            match player {
-                                Player { id: Some(id), username: Some(username), .. } => {
-                                    ctx.players_tx.send(crate::players::Message::Del(id)).await?;
-                                    info!("player {username} disconnected");
-                                }
-                                _ => {},
-                            };
-                            return Ok(());
-                        }
-                    };
-
-                    loop {
-                        res |= (b as i32 & 0x7F) << pos;
-                        if (b & 0x80) == 0 {
-                            break Some(res);
-                        }
-
-                        pos += 7;
-                        if pos >= 32 {
-                            break None;
-                        }
-                        b = socket.read_u8().await?;
-                    }
-                }.expect("Packet size too big");
+                let Ok(b) = b else {
+                    return Ok(());
+                };
+                let size = VarInt::read(&mut [b].as_slice().chain(&mut *socket)).await?.value();
+                if size < 0 {
+                    return Err(format!("invalid packet size {size}").into());
+                }
                 let mut buf = vec![0u8; size as usize];
                 socket.read_exact(&mut buf).await?;
                 // debug!("got {buf:?}");
@@ -73,15 +82,12 @@ pub async fn connection_task(
                 let packet = ProxyBoundPacket::deserialize(&player.state, bytes)?;
                 // debug!("{packet:?}");
 
-                handle_packet(packet, &mut socket, &mut player, &tx, &ctx).await?;
+                handle_packet(packet, socket, player, tx, ctx).await?;
             }
             msg = rx.recv() => {
-                match msg {
-                    Some(message) => {
-                        socket.write_all(&message).await?;
-                    }
-                    None => {}
-                };
+                if let Some(message) = msg {
+                    socket.write_all(&message).await?;
+                }
             }
         }
     }
@@ -113,17 +119,13 @@ pub async fn handle_packet(
                 .send(crate::players::Message::Count(tx))
                 .await?;
             let count = rx.await?;
-            let status = format!("{{\"version\":{{\"name\":\"1.8.8\",\"protocol\":47}},\"players\":{{\"max\":100,\"online\":{count},\"sample\":[]}},\"description\":{{\"text\":\"Hello, World!\"}}}}");
+            let status = format!("{{\"version\":{{\"name\":\"{NAME}\",\"protocol\":{PROTOCOL_VERSION}}},\"players\":{{\"max\":100,\"online\":{count},\"sample\":[]}},\"description\":{{\"text\":\"Hello, World!\"}}}}");
             let packet = ClientBoundPacket::StatusResponse { status }.serialize();
 
             socket.write_all(&packet).await?;
         }
         ProxyBoundPacket::StatusPing { payload } => {
-            let mut packet = BytesMut::with_capacity(10);
-            packet.put_u8(9);
-            packet.put_u8(1);
-            packet.put_u64(payload);
-
+            let packet = ClientBoundPacket::StatusPong { payload }.serialize();
             socket.write_all(&packet).await?;
         }
         ProxyBoundPacket::LoginStart { name } => {
@@ -149,12 +151,13 @@ pub async fn handle_packet(
             let packet = makar_protocol::ServerBoundPacket::JoinGameRequest { id, username: name };
             ctx.server_tx.send(packet).await?;
         }
-        ProxyBoundPacket::ChatMessage { message } => match player {
-            Player {
+        ProxyBoundPacket::ChatMessage { message } => {
+            if let Player {
                 id: Some(id),
                 username: Some(ref username),
                 ..
-            } => {
+            } = player
+            {
                 info!("chat: {username}: {message}");
                 let packet = makar_protocol::ServerBoundPacket::ChatMessage {
                     player: *id,
@@ -162,21 +165,35 @@ pub async fn handle_packet(
                 };
                 ctx.server_tx.send(packet).await?;
             }
-            _ => {}
-        },
+        }
         ProxyBoundPacket::ClientSettings {
             locale,
             view_distance,
-            chat_mode,
-            chat_colors,
             displayed_skin_parts,
+            ..
         } => {
             let packet = makar_protocol::ServerBoundPacket::ClientSettings {
                 player: player.id.unwrap(),
                 locale,
+                view_distance,
+                skin_parts: displayed_skin_parts,
             };
             ctx.server_tx.send(packet).await?;
         }
+        ProxyBoundPacket::KeepAlive { .. } => {}
+        ProxyBoundPacket::PlayerIsOnGround { on_ground } => {
+            movement(player, ctx, None, None, on_ground).await?;
+        }
+        ProxyBoundPacket::PlayerPosition { x, y, z, on_ground } => {
+            movement(player, ctx, Some((x, y, z)), None, on_ground).await?;
+        }
+        ProxyBoundPacket::PlayerLook {
+            yaw,
+            pitch,
+            on_ground,
+        } => {
+            movement(player, ctx, None, Some((yaw, pitch)), on_ground).await?;
+        }
         ProxyBoundPacket::PlayerPositionAndLook {
             x,
             y,
@@ -185,20 +202,160 @@ pub async fn handle_packet(
             pitch,
             on_ground,
         } => {
-            let packet = ClientBoundPacket::PlayerPositionAndLook {
-                x,
-                y,
-                z,
-                yaw,
-                pitch,
-                flags: 0,
+            let (position, look) = (Some((x, y, z)), Some((yaw, pitch)));
+            movement(player, ctx, position, look, on_ground).await?;
+        }
+        ProxyBoundPacket::Animation {} => {
+            if let Some(id) = player.id {
+                let packet = makar_protocol::ServerBoundPacket::SwingArm { player: id };
+                ctx.server_tx.send(packet).await?;
+            }
+        }
+        ProxyBoundPacket::HeldItemChange { slot } => {
+            if let (Some(id), 0..=8) = (player.id, slot) {
+                let packet = makar_protocol::ServerBoundPacket::HeldItemChange {
+                    player: id,
+                    slot: slot as u8,
+                };
+                ctx.server_tx.send(packet).await?;
             }
-            .serialize();
-            socket.write_all(&packet).await?;
         }
-        ProxyBoundPacket::PlayerPosition { x, y, z, on_ground } => {}
-        ProxyBoundPacket::PlayerIsOnGround { on_ground } => {}
-        ProxyBoundPacket::PluginMessage { channel } => {}
+        ProxyBoundPacket::CloseWindow { window_id } => {
+            if let Some(id) = player.id {
+                let packet = makar_protocol::ServerBoundPacket::CloseWindow {
+                    player: id,
+                    window_id,
+                };
+                ctx.server_tx.send(packet).await?;
+            }
+        }
+        ProxyBoundPacket::ClickWindow {
+            window_id,
+            slot,
+            button,
+            action,
+            mode,
+            item,
+        } => {
+            let (Some(id), Some(mode)) = (player.id, click_mode(mode)) else {
+                return Ok(());
+            };
+            let packet = makar_protocol::ServerBoundPacket::ClickWindow {
+                player: id,
+                window_id,
+                slot,
+                button,
+                action,
+                mode,
+                item: item.0,
+            };
+            ctx.server_tx.send(packet).await?;
+        }
+        ProxyBoundPacket::ConfirmTransaction {
+            window_id,
+            action,
+            accepted,
+        } => {
+            if let Some(id) = player.id {
+                let packet = makar_protocol::ServerBoundPacket::ConfirmTransaction {
+                    player: id,
+                    window_id,
+                    action,
+                    accepted: accepted != 0,
+                };
+                ctx.server_tx.send(packet).await?;
+            }
+        }
+        ProxyBoundPacket::CreativeInventoryAction { slot, item } => {
+            if let Some(id) = player.id {
+                let packet = makar_protocol::ServerBoundPacket::CreativeInventoryAction {
+                    player: id,
+                    slot,
+                    item: item.0,
+                };
+                ctx.server_tx.send(packet).await?;
+            }
+        }
+        ProxyBoundPacket::PlayerDigging {
+            status,
+            location,
+            face,
+        } => {
+            // dropping items comes with no block
+            let face = block_face(face).unwrap_or(makar_protocol::BlockFace::Bottom);
+            let (Some(id), Some(status)) = (player.id, digging_status(status)) else {
+                return Ok(());
+            };
+            let packet = makar_protocol::ServerBoundPacket::PlayerDigging {
+                player: id,
+                status,
+                position: location.into(),
+                face,
+            };
+            ctx.server_tx.send(packet).await?;
+        }
+        ProxyBoundPacket::PlayerBlockPlacement {
+            location,
+            face,
+            held_item,
+            cursor_x,
+            cursor_y,
+            cursor_z,
+        } => {
+            if let Some(id) = player.id {
+                let packet = makar_protocol::ServerBoundPacket::PlayerBlockPlacement {
+                    player: id,
+                    position: location.into(),
+                    face: block_face(face),
+                    item: held_item.0,
+                    cursor: (cursor_x, cursor_y, cursor_z),
+                };
+                ctx.server_tx.send(packet).await?;
+            }
+        }
+        ProxyBoundPacket::UseEntity { target, action } => {
+            if let Some(id) = player.id {
+                let packet = makar_protocol::ServerBoundPacket::UseEntity {
+                    player: id,
+                    target: target.value(),
+                    action,
+                };
+                ctx.server_tx.send(packet).await?;
+            }
+        }
+        ProxyBoundPacket::ClientStatus { action_id } => {
+            let action = match action_id.value() {
+                0 => makar_protocol::ClientAction::Respawn,
+                1 => makar_protocol::ClientAction::RequestStats,
+                2 => makar_protocol::ClientAction::OpenInventory,
+                _ => return Ok(()),
+            };
+            if let Some(id) = player.id {
+                let packet = makar_protocol::ServerBoundPacket::ClientStatus { player: id, action };
+                ctx.server_tx.send(packet).await?;
+            }
+        }
+        ProxyBoundPacket::PluginMessage { .. } => {}
     };
     Ok(())
 }
+
+/// Forwards a movement packet, the server deciding where the player is.
+async fn movement(
+    player: &Player,
+    ctx: &crate::ProxyContext,
+    position: Option<(f64, f64, f64)>,
+    look: Option<(f32, f32)>,
+    on_ground: u8,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(id) = player.id {
+        let packet = makar_protocol::ServerBoundPacket::PlayerMovement {
+            player: id,
+            position,
+            look,
+            on_ground: on_ground != 0,
+        };
+        ctx.server_tx.send(packet).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+pub mod connection;
+pub mod players;
+pub mod protocol;
+pub mod server;
+pub mod versions;
+
+use std::{error::Error, net::SocketAddr};
+
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    task::{JoinHandle, JoinSet},
+};
+
+use log::{error, info, warn};
+
+#[derive(Clone)]
+pub struct ProxyContext {
+    pub players_tx: mpsc::Sender<players::Message>,
+    pub server_tx: mpsc::Sender<makar_protocol::ServerBoundPacket>,
+}
+
+pub struct ProxyConfig {
+    pub address: String,
+    pub server_address: String,
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        Self {
+            address: "127.0.0.1:25565".to_string(),
+            server_address: "127.0.0.1:25566".to_string(),
+        }
+    }
+}
+
+pub struct ProxyHandle {
+    address: SocketAddr,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl ProxyHandle {
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Waits until the proxy stops accepting connections.
+    pub async fn wait(mut self) -> Result<(), Box<dyn Error>> {
+        for task in self.tasks.drain(..) {
+            task.await?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ProxyHandle {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Connects to the game server and starts accepting players, returning once
+/// the proxy is ready.
+pub async fn run(config: ProxyConfig) -> Result<ProxyHandle, Box<dyn Error>> {
+    let (players_tx, players_rx) = mpsc::channel(100);
+    let (server_tx, server_rx) = mpsc::channel(100);
+    let ctx = ProxyContext {
+        players_tx,
+        server_tx,
+    };
+
+    let socket = TcpStream::connect(&config.server_address).await?;
+    info!("connected to server at {}", config.server_address);
+
+    let listener = TcpListener::bind(&config.address).await?;
+    let address = listener.local_addr()?;
+    info!("accepting connections on {address}");
+
+    let mut tasks = Vec::new();
+    tasks.push(tokio::spawn(async move {
+        match players::players_task(players_rx).await {
+            Ok(_) => {}
+            Err(e) => {
+                error!("players task ended unexpectingly: {e}");
+            }
+        };
+    }));
+
+    {
+        let ctx = ctx.clone();
+        tasks.push(tokio::spawn(async move {
+            match server::server_task(socket, server_rx, ctx).await {
+                Ok(_) => {}
+                Err(e) => {
+                    error!("server task ended unexpectingly: {e}");
+                }
+            };
+        }));
+    }
+
+    tasks.push(tokio::spawn(accept_task(listener, ctx)));
+
+    Ok(ProxyHandle { address, tasks })
+}
+
+async fn accept_task(listener: TcpListener, ctx: ProxyContext) {
+    // connections are aborted along with this task
+    let mut connections = JoinSet::new();
+    loop {
+        let ctx = ctx.clone();
+        let (connection_tx, connection_rx) = mpsc::channel(100);
+
+        let (socket, addr) = tokio::select! {
+            res = listener.accept() => match res {
+                Ok(conn) => conn,
+                Err(e) => {
+                    warn!("couldn't accept new connection: {e}");
+                    continue;
+                }
+            },
+            Some(_) = connections.join_next() => continue,
+        };
+        connections.spawn(async move {
+            match connection::connection_task(socket, connection_rx, connection_tx, ctx).await {
+                Ok(_) => {}
+                Err(e) => {
+                    warn!("{addr} connection ended unexpectingly: {e}");
+                }
+            };
+        });
+    }
+}
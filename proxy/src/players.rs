@@ -18,12 +18,14 @@ pub async fn players_task(mut rx: mpsc::Receiver<Message>) -> Result<(), Box<dyn
             Some(Message::Put(id, channel)) => {
                 players.insert(id, channel);
             }
-            Some(Message::Send(id, data)) => match players.get(&id) {
-                Some(tx) => {
-                    tx.send(data).await?;
+            Some(Message::Send(id, data)) => {
+                // the connection may close before its player is removed
+                if let Some(tx) = players.get(&id) {
+                    if tx.send(data).await.is_err() {
+                        players.remove(&id);
+                    }
                 }
-                None => {}
-            },
+            }
             Some(Message::Count(res)) => match res.send(players.len()) {
                 Ok(_) => {}
                 Err(e) => warn!("couldn't send player count: {e}"),
@@ -0,0 +1,25 @@
+use makar_protocol::{BlockFace, DiggingStatus};
+
+pub fn digging_status(status: u8) -> Option<DiggingStatus> {
+    Some(match status {
+        0 => DiggingStatus::Started,
+        1 => DiggingStatus::Cancelled,
+        2 => DiggingStatus::Finished,
+        3 => DiggingStatus::DropStack,
+        4 => DiggingStatus::DropItem,
+        _ => return None,
+    })
+}
+
+/// The face of a block, -1 (255 for placements) being none.
+pub fn block_face(face: i8) -> Option<BlockFace> {
+    Some(match face {
+        0 => BlockFace::Bottom,
+        1 => BlockFace::Top,
+        2 => BlockFace::North,
+        3 => BlockFace::South,
+        4 => BlockFace::West,
+        5 => BlockFace::East,
+        _ => return None,
+    })
+}
@@ -1,18 +1,18 @@
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+use serde::ser::SerializeMap;
+
+#[derive(Debug, serde::Deserialize)]
 pub struct Chat {
+    #[serde(default)]
     pub text: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub color: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub bold: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub italic: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub underlined: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub strikethrough: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub obfuscated: Option<bool>,
+    pub translate: Option<String>,
+    #[serde(default)]
+    pub with: Vec<Chat>,
 }
 
 impl From<makar_protocol::Chat> for Chat {
@@ -25,6 +25,8 @@ impl From<makar_protocol::Chat> for Chat {
             underlined,
             strikethrough,
             obfuscated,
+            translate,
+            with,
         } = value;
         Self {
             text,
@@ -34,10 +36,53 @@ impl From<makar_protocol::Chat> for Chat {
             underlined,
             strikethrough,
             obfuscated,
+            translate,
+            with: with.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Components are written without their unset fields. Clients read those
+/// with text as plain text, so translated ones leave it out.
+impl serde::Serialize for Chat {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        match &self.translate {
+            Some(translate) => {
+                map.serialize_entry("translate", translate)?;
+                if !self.with.is_empty() {
+                    map.serialize_entry("with", &self.with)?;
+                }
+            }
+            None => map.serialize_entry("text", &self.text)?,
+        }
+        if let Some(color) = &self.color {
+            map.serialize_entry("color", color)?;
         }
+        let styles = [
+            ("bold", self.bold),
+            ("italic", self.italic),
+            ("underlined", self.underlined),
+            ("strikethrough", self.strikethrough),
+            ("obfuscated", self.obfuscated),
+        ];
+        for (name, style) in styles {
+            if let Some(style) = style {
+                map.serialize_entry(name, &style)?;
+            }
+        }
+        map.end()
     }
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum ChatError {
+    #[error(transparent)]
+    String(#[from] crate::protocol::StringError),
+    #[error("Chat malformed: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
 impl crate::protocol::Serialize for Chat {
     fn size(&self) -> i32 {
         serde_json::to_string(self).unwrap().size()
@@ -47,3 +92,11 @@ impl crate::protocol::Serialize for Chat {
         serde_json::to_string(self).unwrap().serialize(buf);
     }
 }
+
+impl crate::protocol::Deserialize for Chat {
+    type Error = ChatError;
+    fn deserialize(buf: &mut bytes::Bytes) -> Result<Self, Self::Error> {
+        let json = <String as crate::protocol::Deserialize>::deserialize(buf)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
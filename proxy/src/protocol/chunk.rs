@@ -0,0 +1,407 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use makar_protocol::{BlockRecord, ChunkColumn, ChunkSection};
+
+use crate::protocol::{Deserialize, PrimitiveError, Serialize, VarInt, VarLenError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RecordsError {
+    #[error(transparent)]
+    Primitive(#[from] PrimitiveError),
+    #[error(transparent)]
+    VarLen(#[from] VarLenError),
+}
+
+/// Multi block change records, each packing its horizontal position in a
+/// byte followed by its y coordinate and block state.
+#[derive(Debug)]
+pub struct BlockRecords(pub Vec<BlockRecord>);
+
+impl Serialize for BlockRecords {
+    fn size(&self) -> i32 {
+        VarInt::new(self.0.len() as i32).size()
+            + self
+                .0
+                .iter()
+                .map(|record| 2 + VarInt::new(record.block as i32).size())
+                .sum::<i32>()
+    }
+
+    fn serialize(&self, buf: &mut BytesMut) {
+        VarInt::new(self.0.len() as i32).serialize(buf);
+        for record in &self.0 {
+            buf.put_u8((record.x << 4) | (record.z & 0xF));
+            buf.put_u8(record.y);
+            VarInt::new(record.block as i32).serialize(buf);
+        }
+    }
+}
+
+impl Deserialize for BlockRecords {
+    type Error = RecordsError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        let count = VarInt::deserialize(buf)?.value();
+        let mut records = Vec::with_capacity(count.clamp(0, 4096) as usize);
+        for _ in 0..count {
+            let horizontal = u8::deserialize(buf)?;
+            records.push(BlockRecord {
+                x: horizontal >> 4,
+                y: u8::deserialize(buf)?,
+                z: horizontal & 0xF,
+                block: VarInt::deserialize(buf)?.value() as u16,
+            });
+        }
+        Ok(Self(records))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ChunkError {
+    #[error("Chunk data incomplete")]
+    Incomplete,
+    #[error(transparent)]
+    Primitive(#[from] PrimitiveError),
+    #[error(transparent)]
+    VarLen(#[from] VarLenError),
+}
+
+/// A column as carried by a map chunk bulk, its data encoded by
+/// [`encode_column`].
+#[derive(Debug)]
+pub struct BulkColumn {
+    pub x: i32,
+    pub z: i32,
+    pub primary_bit_mask: u16,
+    pub data: Vec<u8>,
+}
+
+/// Map chunk bulk payload: the sky light flag and column count, then the
+/// position and bit mask of every column, then the data of every column.
+#[derive(Debug)]
+pub struct ChunkBulk {
+    pub sky_light: bool,
+    pub columns: Vec<BulkColumn>,
+}
+
+impl Serialize for ChunkBulk {
+    fn size(&self) -> i32 {
+        1 + VarInt::new(self.columns.len() as i32).size()
+            + self
+                .columns
+                .iter()
+                .map(|column| 10 + column.data.len() as i32)
+                .sum::<i32>()
+    }
+
+    fn serialize(&self, buf: &mut BytesMut) {
+        buf.put_u8(self.sky_light as u8);
+        VarInt::new(self.columns.len() as i32).serialize(buf);
+        for column in &self.columns {
+            buf.put_i32(column.x);
+            buf.put_i32(column.z);
+            buf.put_u16(column.primary_bit_mask);
+        }
+        for column in &self.columns {
+            buf.put_slice(&column.data);
+        }
+    }
+}
+
+impl Deserialize for ChunkBulk {
+    type Error = ChunkError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        let sky_light = u8::deserialize(buf)? != 0;
+        let count = VarInt::deserialize(buf)?.value();
+        let mut columns = Vec::with_capacity(count.clamp(0, 1024) as usize);
+        for _ in 0..count {
+            columns.push(BulkColumn {
+                x: i32::deserialize(buf)?,
+                z: i32::deserialize(buf)?,
+                primary_bit_mask: u16::deserialize(buf)?,
+                data: Vec::new(),
+            });
+        }
+        // the data carries no length, it has to be inferred from the masks
+        for column in &mut columns {
+            let size = column_size(column.primary_bit_mask, sky_light, true);
+            if buf.remaining() < size {
+                return Err(ChunkError::Incomplete);
+            }
+            column.data = buf.split_to(size).to_vec();
+        }
+        Ok(Self { sky_light, columns })
+    }
+}
+
+/// Size of the data of a column with the given sections.
+pub fn column_size(mask: u16, sky_light: bool, ground_up: bool) -> usize {
+    let section = 8192 + 2048 + if sky_light { 2048 } else { 0 };
+    mask.count_ones() as usize * section + if ground_up { 256 } else { 0 }
+}
+
+/// Encodes the sections of a column the way 1.8 chunk packets expect them:
+/// every block array, then every block light array, then every sky light
+/// array, followed by the biomes. Returns the primary bit mask along with
+/// the data.
+pub fn encode_column(column: &ChunkColumn) -> (u16, Vec<u8>) {
+    let sections = column
+        .sections
+        .iter()
+        .enumerate()
+        .filter_map(|(y, section)| section.as_ref().map(|section| (y, section)));
+
+    let mut mask = 0u16;
+    let mut count = 0;
+    for (y, _) in sections.clone() {
+        mask |= 1 << y;
+        count += 1;
+    }
+
+    let mut data = Vec::with_capacity(count * (8192 + 2048 + 2048) + column.biomes.len());
+    for (_, section) in sections.clone() {
+        for block in &section.blocks {
+            data.put_u16_le(*block);
+        }
+    }
+    for (_, section) in sections.clone() {
+        data.put_slice(&section.block_light);
+    }
+    for (_, section) in sections {
+        if let Some(sky_light) = &section.sky_light {
+            data.put_slice(sky_light);
+        }
+    }
+    data.put_slice(&column.biomes);
+
+    (mask, data)
+}
+
+fn take(data: &mut &[u8], len: usize) -> Vec<u8> {
+    let (head, tail) = data.split_at(len);
+    *data = tail;
+    head.to_vec()
+}
+
+/// Decodes the data of a ground up column sent by a chunk packet, the
+/// inverse of [`encode_column`].
+pub fn decode_column(
+    x: i32,
+    z: i32,
+    mask: u16,
+    sky_light: bool,
+    mut data: &[u8],
+) -> Result<ChunkColumn, ChunkError> {
+    if data.len() < column_size(mask, sky_light, true) {
+        return Err(ChunkError::Incomplete);
+    }
+
+    let count = mask.count_ones() as usize;
+    let mut sections = Vec::with_capacity(count);
+    for _ in 0..count {
+        let blocks = (0..4096).map(|_| data.get_u16_le()).collect();
+        sections.push(ChunkSection {
+            blocks,
+            block_light: Vec::new(),
+            sky_light: None,
+        });
+    }
+    for section in &mut sections {
+        section.block_light = take(&mut data, 2048);
+    }
+    if sky_light {
+        for section in &mut sections {
+            section.sky_light = Some(take(&mut data, 2048));
+        }
+    }
+
+    let mut sections = sections.into_iter();
+    Ok(ChunkColumn {
+        x,
+        z,
+        sections: (0..16)
+            .map(|y| (mask & (1 << y) != 0).then(|| sections.next()).flatten())
+            .collect(),
+        biomes: data[..256].to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{protocol::ByteArray, versions::v1_8_8::ClientBoundPacket};
+
+    fn section(block: u16, block_light: u8, sky_light: Option<u8>) -> ChunkSection {
+        ChunkSection {
+            blocks: vec![block; 4096],
+            block_light: vec![block_light; 2048],
+            sky_light: sky_light.map(|light| vec![light; 2048]),
+        }
+    }
+
+    /// A column with sections at y 0 and 2, every array filled with its own
+    /// byte so that misplaced data shows up.
+    fn column(sky_light: bool) -> ChunkColumn {
+        let mut sections = vec![None; 16];
+        sections[0] = Some(section(0x0011, 0xA0, sky_light.then_some(0xC0)));
+        sections[2] = Some(section(0x0122, 0xA2, sky_light.then_some(0xC2)));
+        ChunkColumn {
+            x: 3,
+            z: -2,
+            sections,
+            biomes: (0..=255).collect(),
+        }
+    }
+
+    /// The layout vanilla sends: all block arrays as little endian shorts,
+    /// then all block light arrays, then all sky light arrays, then biomes.
+    fn vanilla(sky_light: bool) -> Vec<u8> {
+        let mut data = Vec::new();
+        for block in [0x0011u16, 0x0122] {
+            for _ in 0..4096 {
+                data.extend_from_slice(&block.to_le_bytes());
+            }
+        }
+        data.extend([0xA0; 2048]);
+        data.extend([0xA2; 2048]);
+        if sky_light {
+            data.extend([0xC0; 2048]);
+            data.extend([0xC2; 2048]);
+        }
+        data.extend(0..=255u8);
+        data
+    }
+
+    #[test]
+    fn encodes_sky_light_columns() {
+        let (mask, data) = encode_column(&column(true));
+        assert_eq!(mask, 0b101);
+        assert_eq!(data.len(), column_size(mask, true, true));
+        assert_eq!(data, vanilla(true));
+    }
+
+    #[test]
+    fn encodes_columns_without_sky() {
+        let (mask, data) = encode_column(&column(false));
+        assert_eq!(mask, 0b101);
+        assert_eq!(data.len(), column_size(mask, false, true));
+        assert_eq!(data, vanilla(false));
+    }
+
+    #[test]
+    fn sizes_columns() {
+        assert_eq!(column_size(0, true, true), 256);
+        assert_eq!(column_size(0, true, false), 0);
+        assert_eq!(column_size(0xFFFF, true, true), 16 * 12288 + 256);
+        assert_eq!(column_size(0xFFFF, false, false), 16 * 10240);
+    }
+
+    #[test]
+    fn decodes_encoded_columns() {
+        for sky_light in [true, false] {
+            let (mask, data) = encode_column(&column(sky_light));
+            let decoded = decode_column(3, -2, mask, sky_light, &data).unwrap();
+            assert_eq!(encode_column(&decoded), (mask, data.clone()));
+            assert!(decode_column(3, -2, mask, sky_light, &data[..data.len() - 1]).is_err());
+        }
+    }
+
+    #[test]
+    fn serializes_chunk_data() {
+        let (primary_bit_mask, data) = encode_column(&column(true));
+        let packet = ClientBoundPacket::ChunkData {
+            x: 3,
+            z: -2,
+            ground_up: 1,
+            primary_bit_mask,
+            data: ByteArray(data.clone()),
+        }
+        .serialize();
+
+        let mut expected = BytesMut::new();
+        // id, coordinates, ground up continuous, bit mask, data length
+        let size = 1 + 8 + 1 + 2 + VarInt::new(data.len() as i32).size() + data.len() as i32;
+        VarInt::new(size).serialize(&mut expected);
+        expected.put_u8(0x21);
+        expected.put_i32(3);
+        expected.put_i32(-2);
+        expected.put_u8(1);
+        expected.put_u16(0b101);
+        VarInt::new(data.len() as i32).serialize(&mut expected);
+        expected.put_slice(&data);
+        assert_eq!(packet, expected);
+    }
+
+    #[test]
+    fn serializes_unloading_chunk_data() {
+        let packet = ClientBoundPacket::ChunkData {
+            x: 1,
+            z: 2,
+            ground_up: 1,
+            primary_bit_mask: 0,
+            data: ByteArray(Vec::new()),
+        }
+        .serialize();
+        assert_eq!(
+            &packet[..],
+            [13, 0x21, 0, 0, 0, 1, 0, 0, 0, 2, 1, 0, 0, 0].as_slice()
+        );
+    }
+
+    #[test]
+    fn serializes_map_chunk_bulk() {
+        let columns = (0..2)
+            .map(|x| {
+                let (primary_bit_mask, data) = encode_column(&column(true));
+                BulkColumn {
+                    x,
+                    z: -1,
+                    primary_bit_mask,
+                    data,
+                }
+            })
+            .collect();
+        let bulk = ChunkBulk {
+            sky_light: true,
+            columns,
+        };
+
+        let mut buf = BytesMut::new();
+        bulk.serialize(&mut buf);
+        assert_eq!(buf.len() as i32, bulk.size());
+
+        // every column's metadata comes before the data of any column
+        let mut expected = vec![1, 2];
+        expected.extend([0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0b101]);
+        expected.extend([0, 0, 0, 1, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0b101]);
+        expected.extend(vanilla(true));
+        expected.extend(vanilla(true));
+        assert_eq!(&buf[..], expected.as_slice());
+
+        let decoded = ChunkBulk::deserialize(&mut buf.freeze()).unwrap();
+        assert!(decoded.sky_light);
+        assert_eq!(decoded.columns.len(), 2);
+        assert_eq!(decoded.columns[1].x, 1);
+        assert_eq!(decoded.columns[1].data, vanilla(true));
+    }
+
+    #[test]
+    fn rejects_truncated_bulks() {
+        let (primary_bit_mask, mut data) = encode_column(&column(false));
+        data.pop();
+        let bulk = ChunkBulk {
+            sky_light: false,
+            columns: vec![BulkColumn {
+                x: 0,
+                z: 0,
+                primary_bit_mask,
+                data,
+            }],
+        };
+        let mut buf = BytesMut::new();
+        bulk.serialize(&mut buf);
+        assert!(matches!(
+            ChunkBulk::deserialize(&mut buf.freeze()),
+            Err(ChunkError::Incomplete)
+        ));
+    }
+}
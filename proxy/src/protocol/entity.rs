@@ -0,0 +1,357 @@
+use bytes::{BufMut, Bytes, BytesMut};
+
+use makar_protocol::{BlockPosition, Metadata, MetadataValue, UseEntityAction};
+
+use crate::protocol::{
+    Deserialize, PrimitiveError, Serialize, Slot, SlotError, StringError, VarInt, VarLenError,
+};
+
+/// Converts a coordinate to fixed point, in 1/32 of a block.
+pub fn fixed(value: f64) -> i32 {
+    (value * 32.0).floor() as i32
+}
+
+/// Converts a position delta to fixed point, in 1/32 of a block.
+pub fn fixed_delta(value: f64) -> i8 {
+    (value * 32.0).round() as i8
+}
+
+/// Converts an angle in degrees to steps of 1/256 of a turn.
+pub fn angle(degrees: f32) -> i8 {
+    (degrees * 256.0 / 360.0).floor() as i32 as i8
+}
+
+/// Converts a velocity in blocks per tick to 1/8000 of a block per tick,
+/// clamped to what the client accepts.
+pub fn velocity(value: f64) -> i16 {
+    (value.clamp(-3.9, 3.9) * 8000.0) as i16
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MetadataError {
+    #[error("unknown metadata type {0}")]
+    Unknown(u8),
+    #[error(transparent)]
+    Primitive(#[from] PrimitiveError),
+    #[error(transparent)]
+    String(#[from] StringError),
+    #[error(transparent)]
+    Slot(#[from] SlotError),
+}
+
+/// Metadata entries, each starting with a byte holding its type in the top
+/// three bits and its index in the others, ended by 0x7F.
+#[derive(Debug)]
+pub struct EntityMetadata(pub Vec<Metadata>);
+
+impl Serialize for EntityMetadata {
+    fn size(&self) -> i32 {
+        let values: i32 = self
+            .0
+            .iter()
+            .map(|entry| match &entry.value {
+                MetadataValue::Byte(_) => 1,
+                MetadataValue::Short(_) => 2,
+                MetadataValue::Int(_) | MetadataValue::Float(_) => 4,
+                MetadataValue::String(value) => value.size(),
+                MetadataValue::Slot(stack) => Slot(stack.clone()).size(),
+                MetadataValue::Position(_) | MetadataValue::Rotation(..) => 12,
+            })
+            .sum();
+        self.0.len() as i32 + values + 1
+    }
+
+    fn serialize(&self, buf: &mut BytesMut) {
+        for entry in &self.0 {
+            let kind = match entry.value {
+                MetadataValue::Byte(_) => 0,
+                MetadataValue::Short(_) => 1,
+                MetadataValue::Int(_) => 2,
+                MetadataValue::Float(_) => 3,
+                MetadataValue::String(_) => 4,
+                MetadataValue::Slot(_) => 5,
+                MetadataValue::Position(_) => 6,
+                MetadataValue::Rotation(..) => 7,
+            };
+            buf.put_u8((kind << 5) | (entry.index & 0x1F));
+            match &entry.value {
+                MetadataValue::Byte(value) => buf.put_i8(*value),
+                MetadataValue::Short(value) => buf.put_i16(*value),
+                MetadataValue::Int(value) => buf.put_i32(*value),
+                MetadataValue::Float(value) => buf.put_f32(*value),
+                MetadataValue::String(value) => value.serialize(buf),
+                MetadataValue::Slot(stack) => Slot(stack.clone()).serialize(buf),
+                MetadataValue::Position(position) => {
+                    buf.put_i32(position.x);
+                    buf.put_i32(position.y);
+                    buf.put_i32(position.z);
+                }
+                MetadataValue::Rotation(x, y, z) => {
+                    buf.put_f32(*x);
+                    buf.put_f32(*y);
+                    buf.put_f32(*z);
+                }
+            }
+        }
+        buf.put_u8(0x7F);
+    }
+}
+
+impl Deserialize for EntityMetadata {
+    type Error = MetadataError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        let mut entries = Vec::new();
+        loop {
+            let header = u8::deserialize(buf)?;
+            if header == 0x7F {
+                return Ok(Self(entries));
+            }
+            let value = match header >> 5 {
+                0 => MetadataValue::Byte(i8::deserialize(buf)?),
+                1 => MetadataValue::Short(i16::deserialize(buf)?),
+                2 => MetadataValue::Int(i32::deserialize(buf)?),
+                3 => MetadataValue::Float(f32::deserialize(buf)?),
+                4 => MetadataValue::String(String::deserialize(buf)?),
+                5 => MetadataValue::Slot(Slot::deserialize(buf)?.0),
+                6 => MetadataValue::Position(BlockPosition {
+                    x: i32::deserialize(buf)?,
+                    y: i32::deserialize(buf)?,
+                    z: i32::deserialize(buf)?,
+                }),
+                7 => MetadataValue::Rotation(
+                    f32::deserialize(buf)?,
+                    f32::deserialize(buf)?,
+                    f32::deserialize(buf)?,
+                ),
+                kind => return Err(MetadataError::Unknown(kind)),
+            };
+            entries.push(Metadata {
+                index: header & 0x1F,
+                value,
+            });
+        }
+    }
+}
+
+/// A list of entity ids, prefixed by its length.
+#[derive(Debug)]
+pub struct EntityIds(pub Vec<i32>);
+
+impl Serialize for EntityIds {
+    fn size(&self) -> i32 {
+        VarInt::new(self.0.len() as i32).size()
+            + self.0.iter().map(|id| VarInt::new(*id).size()).sum::<i32>()
+    }
+
+    fn serialize(&self, buf: &mut BytesMut) {
+        VarInt::new(self.0.len() as i32).serialize(buf);
+        for id in &self.0 {
+            VarInt::new(*id).serialize(buf);
+        }
+    }
+}
+
+impl Deserialize for EntityIds {
+    type Error = VarLenError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        let count = VarInt::deserialize(buf)?.value();
+        (0..count)
+            .map(|_| Ok(VarInt::deserialize(buf)?.value()))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// The data of a spawned object, followed by its velocity only when the
+/// data isn't zero.
+#[derive(Debug)]
+pub struct ObjectData {
+    pub data: i32,
+    pub velocity: (i16, i16, i16),
+}
+
+impl Serialize for ObjectData {
+    fn size(&self) -> i32 {
+        if self.data == 0 {
+            4
+        } else {
+            10
+        }
+    }
+
+    fn serialize(&self, buf: &mut BytesMut) {
+        buf.put_i32(self.data);
+        if self.data != 0 {
+            buf.put_i16(self.velocity.0);
+            buf.put_i16(self.velocity.1);
+            buf.put_i16(self.velocity.2);
+        }
+    }
+}
+
+impl Deserialize for ObjectData {
+    type Error = PrimitiveError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        let data = i32::deserialize(buf)?;
+        let velocity = if data == 0 {
+            (0, 0, 0)
+        } else {
+            (
+                i16::deserialize(buf)?,
+                i16::deserialize(buf)?,
+                i16::deserialize(buf)?,
+            )
+        };
+        Ok(Self { data, velocity })
+    }
+}
+
+/// How an entity is used, as a varint followed by where it was clicked for
+/// interactions at a point.
+impl Serialize for UseEntityAction {
+    fn size(&self) -> i32 {
+        match self {
+            UseEntityAction::InteractAt { .. } => 13,
+            _ => 1,
+        }
+    }
+
+    fn serialize(&self, buf: &mut BytesMut) {
+        match self {
+            UseEntityAction::Interact => VarInt::new(0).serialize(buf),
+            UseEntityAction::Attack => VarInt::new(1).serialize(buf),
+            UseEntityAction::InteractAt { x, y, z } => {
+                VarInt::new(2).serialize(buf);
+                buf.put_f32(*x);
+                buf.put_f32(*y);
+                buf.put_f32(*z);
+            }
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum UseEntityError {
+    #[error("unknown use entity action {0}")]
+    Unknown(i32),
+    #[error(transparent)]
+    VarLen(#[from] VarLenError),
+    #[error(transparent)]
+    Primitive(#[from] PrimitiveError),
+}
+
+impl Deserialize for UseEntityAction {
+    type Error = UseEntityError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        Ok(match VarInt::deserialize(buf)?.value() {
+            0 => UseEntityAction::Interact,
+            1 => UseEntityAction::Attack,
+            2 => UseEntityAction::InteractAt {
+                x: f32::deserialize(buf)?,
+                y: f32::deserialize(buf)?,
+                z: f32::deserialize(buf)?,
+            },
+            v => return Err(UseEntityError::Unknown(v)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use makar_protocol::{ItemMeta, ItemStack};
+
+    use super::*;
+
+    fn encode(value: &impl Serialize) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        value.serialize(&mut buf);
+        assert_eq!(buf.len() as i32, value.size());
+        buf.to_vec()
+    }
+
+    #[test]
+    fn metadata_round_trip() {
+        let entries = vec![
+            Metadata {
+                index: 0,
+                value: MetadataValue::Byte(0x20),
+            },
+            Metadata {
+                index: 6,
+                value: MetadataValue::Float(20.0),
+            },
+            Metadata {
+                index: 2,
+                value: MetadataValue::String("Bob".to_string()),
+            },
+            Metadata {
+                index: 10,
+                value: MetadataValue::Slot(Some(ItemStack {
+                    id: 1,
+                    count: 64,
+                    damage: 3,
+                    meta: ItemMeta::default(),
+                })),
+            },
+            Metadata {
+                index: 10,
+                value: MetadataValue::Slot(None),
+            },
+            Metadata {
+                index: 17,
+                value: MetadataValue::Position(BlockPosition { x: 1, y: -2, z: 3 }),
+            },
+        ];
+        #[rustfmt::skip]
+        let bytes: &[u8] = &[
+            // byte 0
+            0x00, 0x20,
+            // float 6
+            0x66, 0x41, 0xA0, 0x00, 0x00,
+            // string 2
+            0x82, 0x03, b'B', b'o', b'b',
+            // slot 10, with an item and empty
+            0xAA, 0x00, 0x01, 0x40, 0x00, 0x03, 0x00,
+            0xAA, 0xFF, 0xFF,
+            // position 17
+            0xD1, 0x00, 0x00, 0x00, 0x01, 0xFF, 0xFF, 0xFF, 0xFE, 0x00, 0x00, 0x00, 0x03,
+            0x7F,
+        ];
+
+        assert_eq!(encode(&EntityMetadata(entries.clone())), bytes);
+        let decoded = EntityMetadata::deserialize(&mut Bytes::copy_from_slice(bytes)).unwrap();
+        assert_eq!(decoded.0, entries);
+    }
+
+    #[test]
+    fn object_data_round_trip() {
+        let cases: [(ObjectData, &[u8]); 2] = [
+            (
+                ObjectData {
+                    data: 0,
+                    velocity: (0, 0, 0),
+                },
+                &[0x00, 0x00, 0x00, 0x00],
+            ),
+            (
+                ObjectData {
+                    data: 1,
+                    velocity: (100, -8000, 0),
+                },
+                &[0x00, 0x00, 0x00, 0x01, 0x00, 0x64, 0xE0, 0xC0, 0x00, 0x00],
+            ),
+        ];
+        for (data, bytes) in cases {
+            assert_eq!(encode(&data), bytes);
+            let mut buf = Bytes::copy_from_slice(bytes);
+            let decoded = ObjectData::deserialize(&mut buf).unwrap();
+            assert_eq!((decoded.data, decoded.velocity), (data.data, data.velocity));
+            assert!(buf.is_empty());
+        }
+
+        // velocity isn't read for objects without data
+        let mut buf = Bytes::from_static(&[0x00, 0x00, 0x00, 0x00, 0x7F]);
+        ObjectData::deserialize(&mut buf).unwrap();
+        assert_eq!(buf.as_ref(), [0x7F]);
+    }
+}
@@ -1,15 +1,37 @@
+pub mod block;
 mod chat;
+pub mod chunk;
+pub mod entity;
+mod player_list;
+mod position;
 mod primitive;
+mod scoreboard;
+mod slot;
 mod string;
+mod team;
 mod title;
 mod varlen;
+pub mod window;
 
-pub use chat::Chat;
+pub use chat::{Chat, ChatError};
+pub use chunk::{BlockRecords, ChunkBulk};
+pub use entity::{EntityIds, EntityMetadata, ObjectData, UseEntityError};
+pub use player_list::{PlayerListAction, PlayerListEntry, PlayerListError};
+pub use position::Position;
+pub use primitive::PrimitiveError;
+pub use scoreboard::{ScoreUpdate, ScoreboardError};
+pub use slot::{Slot, SlotError, Slots};
+pub use string::StringError;
+pub use team::TeamError;
 pub use title::TitleAction;
 pub use varlen::*;
 
 use bytes::{BufMut, Bytes, BytesMut};
 
+pub use makar_protocol::{
+    ChatPosition, Difficulty, DisplaySlot, Gamemode, ObjectiveAction, TeamAction, UseEntityAction,
+};
+
 pub trait Serialize {
     fn size(&self) -> i32;
     fn serialize(&self, buf: &mut BytesMut);
@@ -26,17 +48,60 @@ impl Serialize for Vec<u8> {
     }
 
     fn serialize(&self, buf: &mut BytesMut) {
-        buf.put_slice(&self);
+        buf.put_slice(self);
+    }
+}
+
+impl Deserialize for Vec<u8> {
+    type Error = std::convert::Infallible;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        Ok(buf.split_to(buf.len()).to_vec())
     }
 }
 
-impl Serialize for makar_protocol::Gamemode {
+#[derive(Debug)]
+pub struct ByteArray(pub Vec<u8>);
+
+impl Serialize for ByteArray {
+    fn size(&self) -> i32 {
+        let len = self.0.len() as i32;
+        len + VarInt::new(len).size()
+    }
+
+    fn serialize(&self, buf: &mut BytesMut) {
+        VarInt::new(self.0.len() as i32).serialize(buf);
+        buf.put_slice(&self.0);
+    }
+}
+
+impl Deserialize for ByteArray {
+    type Error = StringError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        let length = VarInt::deserialize(buf)
+            .map_err(|_| StringError::TooLong)?
+            .value() as usize;
+        if buf.len() < length {
+            return Err(StringError::Incomplete);
+        }
+        Ok(Self(buf.split_to(length).to_vec()))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EnumError {
+    #[error("unknown enum value {0}")]
+    Unknown(u8),
+    #[error(transparent)]
+    Primitive(#[from] PrimitiveError),
+}
+
+impl Serialize for Gamemode {
     fn size(&self) -> i32 {
         1
     }
 
     fn serialize(&self, buf: &mut BytesMut) {
-        use makar_protocol::Gamemode::*;
+        use Gamemode::*;
         let b: u8 = match self {
             Survival => 0,
             Creative => 1,
@@ -47,13 +112,28 @@ impl Serialize for makar_protocol::Gamemode {
     }
 }
 
-impl Serialize for makar_protocol::Difficulty {
+impl Deserialize for Gamemode {
+    type Error = EnumError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        use Gamemode::*;
+        // the hardcore flag is carried by the fourth bit
+        Ok(match u8::deserialize(buf)? & 0x7 {
+            0 => Survival,
+            1 => Creative,
+            2 => Adventure,
+            3 => Spectator,
+            v => return Err(EnumError::Unknown(v)),
+        })
+    }
+}
+
+impl Serialize for Difficulty {
     fn size(&self) -> i32 {
         1
     }
 
     fn serialize(&self, buf: &mut BytesMut) {
-        use makar_protocol::Difficulty::*;
+        use Difficulty::*;
         let b: u8 = match self {
             Peaceful => 0,
             Easy => 1,
@@ -64,7 +144,50 @@ impl Serialize for makar_protocol::Difficulty {
     }
 }
 
-#[derive(Debug)]
+impl Deserialize for Difficulty {
+    type Error = EnumError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        use Difficulty::*;
+        Ok(match u8::deserialize(buf)? {
+            0 => Peaceful,
+            1 => Easy,
+            2 => Normal,
+            3 => Hard,
+            v => return Err(EnumError::Unknown(v)),
+        })
+    }
+}
+
+impl Serialize for ChatPosition {
+    fn size(&self) -> i32 {
+        1
+    }
+
+    fn serialize(&self, buf: &mut BytesMut) {
+        use ChatPosition::*;
+        let b: u8 = match self {
+            Chat => 0,
+            System => 1,
+            ActionBar => 2,
+        };
+        b.serialize(buf);
+    }
+}
+
+impl Deserialize for ChatPosition {
+    type Error = EnumError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        use ChatPosition::*;
+        Ok(match u8::deserialize(buf)? {
+            0 => Chat,
+            1 => System,
+            2 => ActionBar,
+            v => return Err(EnumError::Unknown(v)),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum State {
     Handshake,
     Status,
@@ -73,49 +196,32 @@ pub enum State {
 }
 
 #[macro_export]
-macro_rules! define_proxy_bound {
-    ($($name:ident, $state:ident, $id:expr => {
+macro_rules! define_packets {
+    ($enum:ident; $($name:ident, $state:ident, $id:expr => {
         $($field:ident: $type:ty,)*
     }),* $(,)?) => {
         #[derive(Debug)]
-        pub enum ProxyBoundPacket {
+        pub enum $enum {
             $($name {
                 $($field: $type,)*
             }),*
         }
 
-        impl ProxyBoundPacket {
-            pub fn deserialize(state: &crate::protocol::State, mut packet: bytes::Bytes) -> Result<Self, Box<dyn std::error::Error>> {
-                use crate::protocol::Deserialize;
-
-                match (state, crate::protocol::VarInt::deserialize(&mut packet)?.value()) {
-                    $((crate::protocol::State::$state, $id) =>
-                        Ok(Self::$name {
-                            $($field: <$type>::deserialize(&mut packet)?,)*
-                        }),
-                    )*
-                    (state, id) => Err(format!("unknown id {id} for state {state:?}").into())
+        impl $enum {
+            pub fn state(&self) -> $crate::protocol::State {
+                match self {
+                    $(Self::$name { .. } => $crate::protocol::State::$state,)*
                 }
             }
-        }
-    };
-}
 
-#[macro_export]
-macro_rules! define_client_bound {
-    ($($name:ident, $id:expr => {
-        $($field:ident: $type:ty,)*
-    }),* $(,)?) => {
-        #[derive(Debug)]
-        pub enum ClientBoundPacket {
-            $($name {
-                $($field: $type,)*
-            }),*
-        }
+            pub fn id(&self) -> i32 {
+                match self {
+                    $(Self::$name { .. } => $id,)*
+                }
+            }
 
-        impl ClientBoundPacket {
             pub fn serialize(&self) -> bytes::BytesMut {
-                use crate::protocol::{VarInt, Serialize};
+                use $crate::protocol::{VarInt, Serialize};
 
                 match self {
                     $(Self::$name { $($field,)* } => {
@@ -131,6 +237,33 @@ macro_rules! define_client_bound {
                     }),*
                 }
             }
+
+            pub fn deserialize(state: &$crate::protocol::State, mut packet: bytes::Bytes) -> Result<Self, Box<dyn std::error::Error>> {
+                use $crate::protocol::Deserialize;
+
+                match (state, $crate::protocol::VarInt::deserialize(&mut packet)?.value()) {
+                    $(($crate::protocol::State::$state, $id) =>
+                        Ok(Self::$name {
+                            $($field: <$type>::deserialize(&mut packet)?,)*
+                        }),
+                    )*
+                    (state, id) => Err(format!("unknown id {id} for state {state:?}").into())
+                }
+            }
         }
     };
 }
+
+#[macro_export]
+macro_rules! define_proxy_bound {
+    ($($packets:tt)*) => {
+        $crate::define_packets! { ProxyBoundPacket; $($packets)* }
+    };
+}
+
+#[macro_export]
+macro_rules! define_client_bound {
+    ($($packets:tt)*) => {
+        $crate::define_packets! { ClientBoundPacket; $($packets)* }
+    };
+}
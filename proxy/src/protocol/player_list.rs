@@ -0,0 +1,327 @@
+use bytes::{BufMut, Bytes, BytesMut};
+
+use crate::protocol::{
+    Chat, ChatError, Deserialize, EnumError, Gamemode, PrimitiveError, Serialize, StringError,
+    VarInt, VarLenError,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PlayerListError {
+    #[error("unknown player list action {0}")]
+    Unknown(i32),
+    #[error("player properties aren't supported")]
+    Properties,
+    #[error(transparent)]
+    Primitive(#[from] PrimitiveError),
+    #[error(transparent)]
+    VarLen(#[from] VarLenError),
+    #[error(transparent)]
+    String(#[from] StringError),
+    #[error(transparent)]
+    Chat(#[from] ChatError),
+    #[error(transparent)]
+    Enum(#[from] EnumError),
+}
+
+#[derive(Debug)]
+pub struct PlayerListEntry {
+    pub uuid: u128,
+    pub name: String,
+    pub gamemode: Gamemode,
+    pub ping: i32,
+    pub display_name: Option<Chat>,
+}
+
+/// Player list changes, as the action id followed by the entries it applies
+/// to, each starting with its uuid.
+#[derive(Debug)]
+pub enum PlayerListAction {
+    Add(Vec<PlayerListEntry>),
+    UpdateGamemode(Vec<(u128, Gamemode)>),
+    UpdateLatency(Vec<(u128, i32)>),
+    UpdateDisplayName(Vec<(u128, Option<Chat>)>),
+    Remove(Vec<u128>),
+}
+
+impl From<makar_protocol::PlayerListAction> for PlayerListAction {
+    fn from(value: makar_protocol::PlayerListAction) -> Self {
+        use makar_protocol::PlayerListAction::*;
+        match value {
+            Add(entries) => Self::Add(
+                entries
+                    .into_iter()
+                    .map(|entry| PlayerListEntry {
+                        uuid: entry.uuid,
+                        name: entry.name,
+                        gamemode: entry.gamemode,
+                        ping: entry.ping,
+                        display_name: entry.display_name.map(Chat::from),
+                    })
+                    .collect(),
+            ),
+            UpdateGamemode(entries) => Self::UpdateGamemode(entries),
+            UpdateLatency(entries) => Self::UpdateLatency(entries),
+            UpdateDisplayName(entries) => Self::UpdateDisplayName(
+                entries
+                    .into_iter()
+                    .map(|(uuid, name)| (uuid, name.map(Chat::from)))
+                    .collect(),
+            ),
+            Remove(uuids) => Self::Remove(uuids),
+        }
+    }
+}
+
+fn display_name_size(name: &Option<Chat>) -> i32 {
+    1 + name.as_ref().map_or(0, Chat::size)
+}
+
+fn serialize_display_name(name: &Option<Chat>, buf: &mut BytesMut) {
+    buf.put_u8(name.is_some() as u8);
+    if let Some(name) = name {
+        name.serialize(buf);
+    }
+}
+
+fn deserialize_display_name(buf: &mut Bytes) -> Result<Option<Chat>, PlayerListError> {
+    Ok(match u8::deserialize(buf)? {
+        0 => None,
+        _ => Some(Chat::deserialize(buf)?),
+    })
+}
+
+impl PlayerListAction {
+    fn id(&self) -> i32 {
+        match self {
+            Self::Add(_) => 0,
+            Self::UpdateGamemode(_) => 1,
+            Self::UpdateLatency(_) => 2,
+            Self::UpdateDisplayName(_) => 3,
+            Self::Remove(_) => 4,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Add(entries) => entries.len(),
+            Self::UpdateGamemode(entries) => entries.len(),
+            Self::UpdateLatency(entries) => entries.len(),
+            Self::UpdateDisplayName(entries) => entries.len(),
+            Self::Remove(uuids) => uuids.len(),
+        }
+    }
+}
+
+impl Serialize for PlayerListAction {
+    fn size(&self) -> i32 {
+        let uuids = 16 * self.len() as i32;
+        let entries: i32 = match self {
+            Self::Add(entries) => entries
+                .iter()
+                .map(|entry| {
+                    // no properties
+                    entry.name.size()
+                        + 1
+                        + entry.gamemode.size()
+                        + VarInt::new(entry.ping).size()
+                        + display_name_size(&entry.display_name)
+                })
+                .sum(),
+            Self::UpdateGamemode(entries) => entries.iter().map(|(_, mode)| mode.size()).sum(),
+            Self::UpdateLatency(entries) => entries
+                .iter()
+                .map(|(_, ping)| VarInt::new(*ping).size())
+                .sum(),
+            Self::UpdateDisplayName(entries) => entries
+                .iter()
+                .map(|(_, name)| display_name_size(name))
+                .sum(),
+            Self::Remove(_) => 0,
+        };
+        VarInt::new(self.id()).size() + VarInt::new(self.len() as i32).size() + uuids + entries
+    }
+
+    fn serialize(&self, buf: &mut BytesMut) {
+        VarInt::new(self.id()).serialize(buf);
+        VarInt::new(self.len() as i32).serialize(buf);
+        match self {
+            Self::Add(entries) => {
+                for entry in entries {
+                    buf.put_u128(entry.uuid);
+                    entry.name.serialize(buf);
+                    VarInt::new(0).serialize(buf);
+                    entry.gamemode.serialize(buf);
+                    VarInt::new(entry.ping).serialize(buf);
+                    serialize_display_name(&entry.display_name, buf);
+                }
+            }
+            Self::UpdateGamemode(entries) => {
+                for (uuid, gamemode) in entries {
+                    buf.put_u128(*uuid);
+                    gamemode.serialize(buf);
+                }
+            }
+            Self::UpdateLatency(entries) => {
+                for (uuid, ping) in entries {
+                    buf.put_u128(*uuid);
+                    VarInt::new(*ping).serialize(buf);
+                }
+            }
+            Self::UpdateDisplayName(entries) => {
+                for (uuid, name) in entries {
+                    buf.put_u128(*uuid);
+                    serialize_display_name(name, buf);
+                }
+            }
+            Self::Remove(uuids) => {
+                for uuid in uuids {
+                    buf.put_u128(*uuid);
+                }
+            }
+        }
+    }
+}
+
+impl Deserialize for PlayerListAction {
+    type Error = PlayerListError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        let action = VarInt::deserialize(buf)?.value();
+        let count = VarInt::deserialize(buf)?.value().max(0);
+        Ok(match action {
+            0 => Self::Add(
+                (0..count)
+                    .map(|_| {
+                        let uuid = u128::deserialize(buf)?;
+                        let name = String::deserialize(buf)?;
+                        if VarInt::deserialize(buf)?.value() != 0 {
+                            return Err(PlayerListError::Properties);
+                        }
+                        Ok(PlayerListEntry {
+                            uuid,
+                            name,
+                            gamemode: Gamemode::deserialize(buf)?,
+                            ping: VarInt::deserialize(buf)?.value(),
+                            display_name: deserialize_display_name(buf)?,
+                        })
+                    })
+                    .collect::<Result<_, PlayerListError>>()?,
+            ),
+            1 => Self::UpdateGamemode(
+                (0..count)
+                    .map(|_| Ok((u128::deserialize(buf)?, Gamemode::deserialize(buf)?)))
+                    .collect::<Result<_, PlayerListError>>()?,
+            ),
+            2 => Self::UpdateLatency(
+                (0..count)
+                    .map(|_| Ok((u128::deserialize(buf)?, VarInt::deserialize(buf)?.value())))
+                    .collect::<Result<_, PlayerListError>>()?,
+            ),
+            3 => Self::UpdateDisplayName(
+                (0..count)
+                    .map(|_| Ok((u128::deserialize(buf)?, deserialize_display_name(buf)?)))
+                    .collect::<Result<_, PlayerListError>>()?,
+            ),
+            4 => Self::Remove(
+                (0..count)
+                    .map(|_| u128::deserialize(buf))
+                    .collect::<Result<_, _>>()?,
+            ),
+            action => return Err(PlayerListError::Unknown(action)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(value: &impl Serialize) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        value.serialize(&mut buf);
+        assert_eq!(buf.len() as i32, value.size());
+        buf.to_vec()
+    }
+
+    /// Decodes the bytes, checking that they encode back the same.
+    fn round_trip(bytes: &[u8]) -> PlayerListAction {
+        let mut buf = Bytes::copy_from_slice(bytes);
+        let action = PlayerListAction::deserialize(&mut buf).unwrap();
+        assert!(buf.is_empty());
+        assert_eq!(encode(&action), bytes);
+        action
+    }
+
+    fn uuid(bytes: &mut Vec<u8>, uuid: u128) {
+        bytes.extend(uuid.to_be_bytes());
+    }
+
+    #[test]
+    fn add_round_trip() {
+        let mut bytes = vec![0x00, 0x01];
+        uuid(&mut bytes, 0x0123_4567_89AB_CDEF_0123_4567_89AB_CDEF);
+        // no properties, creative, 42ms and a display name
+        bytes.extend(b"\x05alice\x00\x01\x2A\x01\x0D{\"text\":\"Al\"}");
+
+        let PlayerListAction::Add(entries) = round_trip(&bytes) else {
+            panic!("expected an add action");
+        };
+        let entry = &entries[0];
+        assert_eq!(entry.uuid, 0x0123_4567_89AB_CDEF_0123_4567_89AB_CDEF);
+        assert_eq!(entry.name, "alice");
+        assert!(matches!(entry.gamemode, Gamemode::Creative));
+        assert_eq!(entry.ping, 42);
+        assert_eq!(entry.display_name.as_ref().unwrap().text, "Al");
+
+        // properties such as skins are never sent
+        let mut bytes = vec![0x00, 0x01];
+        uuid(&mut bytes, 1);
+        bytes.extend(b"\x05alice\x01");
+        assert!(matches!(
+            PlayerListAction::deserialize(&mut Bytes::from(bytes)),
+            Err(PlayerListError::Properties)
+        ));
+    }
+
+    #[test]
+    fn update_round_trip() {
+        let mut bytes = vec![0x01, 0x01];
+        uuid(&mut bytes, 1);
+        bytes.push(0x03);
+        let PlayerListAction::UpdateGamemode(entries) = round_trip(&bytes) else {
+            panic!("expected a gamemode update");
+        };
+        assert!(matches!(entries[..], [(1, Gamemode::Spectator)]));
+
+        let mut bytes = vec![0x02, 0x01];
+        uuid(&mut bytes, 2);
+        bytes.extend([0xAC, 0x02]);
+        let PlayerListAction::UpdateLatency(entries) = round_trip(&bytes) else {
+            panic!("expected a latency update");
+        };
+        assert_eq!(entries, [(2, 300)]);
+
+        let mut bytes = vec![0x03, 0x02];
+        uuid(&mut bytes, 3);
+        bytes.extend(b"\x01\x0D{\"text\":\"Al\"}");
+        uuid(&mut bytes, 4);
+        bytes.push(0x00);
+        let PlayerListAction::UpdateDisplayName(entries) = round_trip(&bytes) else {
+            panic!("expected a display name update");
+        };
+        assert_eq!(entries[0].1.as_ref().unwrap().text, "Al");
+        assert!(entries[1].1.is_none());
+
+        let mut bytes = vec![0x04, 0x02];
+        uuid(&mut bytes, 5);
+        uuid(&mut bytes, 6);
+        let PlayerListAction::Remove(uuids) = round_trip(&bytes) else {
+            panic!("expected a removal");
+        };
+        assert_eq!(uuids, [5, 6]);
+
+        assert!(matches!(
+            PlayerListAction::deserialize(&mut Bytes::from_static(&[0x05, 0x00])),
+            Err(PlayerListError::Unknown(5))
+        ));
+    }
+}
@@ -0,0 +1,50 @@
+use crate::protocol::{Deserialize, PrimitiveError, Serialize};
+
+/// A block position, packed in a single long as x (26 bits), y (12 bits) and
+/// z (26 bits).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl From<makar_protocol::BlockPosition> for Position {
+    fn from(value: makar_protocol::BlockPosition) -> Self {
+        let makar_protocol::BlockPosition { x, y, z } = value;
+        Self { x, y, z }
+    }
+}
+
+impl From<Position> for makar_protocol::BlockPosition {
+    fn from(value: Position) -> Self {
+        let Position { x, y, z } = value;
+        Self { x, y, z }
+    }
+}
+
+impl Serialize for Position {
+    fn size(&self) -> i32 {
+        8
+    }
+
+    fn serialize(&self, buf: &mut bytes::BytesMut) {
+        let value = ((self.x as u64 & 0x3FFFFFF) << 38)
+            | ((self.y as u64 & 0xFFF) << 26)
+            | (self.z as u64 & 0x3FFFFFF);
+        value.serialize(buf);
+    }
+}
+
+impl Deserialize for Position {
+    type Error = PrimitiveError;
+    fn deserialize(buf: &mut bytes::Bytes) -> Result<Self, Self::Error> {
+        let value = i64::deserialize(buf)?;
+        // arithmetic shifts sign extend each component
+        Ok(Self {
+            x: (value >> 38) as i32,
+            y: ((value << 26) >> 52) as i32,
+            z: ((value << 38) >> 38) as i32,
+        })
+    }
+}
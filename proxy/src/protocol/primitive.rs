@@ -1,3 +1,9 @@
+#[derive(Debug, thiserror::Error)]
+pub enum PrimitiveError {
+    #[error("Primitive incomplete")]
+    Incomplete,
+}
+
 macro_rules! primitive {
     ($type:ty, $put:ident, $get:ident, $size:expr) => {
         impl crate::protocol::Serialize for $type {
@@ -13,10 +19,13 @@ macro_rules! primitive {
         }
 
         impl crate::protocol::Deserialize for $type {
-            type Error = std::convert::Infallible;
+            type Error = PrimitiveError;
             fn deserialize(buf: &mut bytes::Bytes) -> Result<Self, Self::Error> {
                 use bytes::Buf;
 
+                if buf.remaining() < $size {
+                    return Err(PrimitiveError::Incomplete);
+                }
                 Ok(buf.$get())
             }
         }
@@ -31,5 +40,6 @@ primitive!(u32, put_u32, get_u32, 4);
 primitive!(i32, put_i32, get_i32, 4);
 primitive!(u64, put_u64, get_u64, 8);
 primitive!(i64, put_i64, get_i64, 8);
+primitive!(u128, put_u128, get_u128, 16);
 primitive!(f32, put_f32, get_f32, 4);
 primitive!(f64, put_f64, get_f64, 8);
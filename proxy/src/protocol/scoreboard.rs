@@ -0,0 +1,248 @@
+use bytes::{BufMut, Bytes, BytesMut};
+
+use makar_protocol::{DisplaySlot, ObjectiveAction, ObjectiveKind};
+
+use crate::protocol::{Deserialize, PrimitiveError, Serialize, StringError, VarInt, VarLenError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScoreboardError {
+    #[error("unknown scoreboard mode {0}")]
+    Unknown(u8),
+    #[error("unknown objective type {0}")]
+    Kind(String),
+    #[error(transparent)]
+    Primitive(#[from] PrimitiveError),
+    #[error(transparent)]
+    VarLen(#[from] VarLenError),
+    #[error(transparent)]
+    String(#[from] StringError),
+}
+
+fn kind_name(kind: ObjectiveKind) -> String {
+    match kind {
+        ObjectiveKind::Integer => "integer",
+        ObjectiveKind::Hearts => "hearts",
+    }
+    .to_string()
+}
+
+fn deserialize_kind(buf: &mut Bytes) -> Result<ObjectiveKind, ScoreboardError> {
+    match String::deserialize(buf)?.as_str() {
+        "integer" => Ok(ObjectiveKind::Integer),
+        "hearts" => Ok(ObjectiveKind::Hearts),
+        kind => Err(ScoreboardError::Kind(kind.to_string())),
+    }
+}
+
+/// The mode byte, followed by the display name and type of the objective
+/// unless it's removed.
+impl Serialize for ObjectiveAction {
+    fn size(&self) -> i32 {
+        match self {
+            ObjectiveAction::Create { display_name, kind }
+            | ObjectiveAction::Update { display_name, kind } => {
+                1 + display_name.size() + kind_name(*kind).size()
+            }
+            ObjectiveAction::Remove => 1,
+        }
+    }
+
+    fn serialize(&self, buf: &mut BytesMut) {
+        match self {
+            ObjectiveAction::Create { display_name, kind } => {
+                buf.put_u8(0);
+                display_name.serialize(buf);
+                kind_name(*kind).serialize(buf);
+            }
+            ObjectiveAction::Remove => buf.put_u8(1),
+            ObjectiveAction::Update { display_name, kind } => {
+                buf.put_u8(2);
+                display_name.serialize(buf);
+                kind_name(*kind).serialize(buf);
+            }
+        }
+    }
+}
+
+impl Deserialize for ObjectiveAction {
+    type Error = ScoreboardError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        Ok(match u8::deserialize(buf)? {
+            0 => ObjectiveAction::Create {
+                display_name: String::deserialize(buf)?,
+                kind: deserialize_kind(buf)?,
+            },
+            1 => ObjectiveAction::Remove,
+            2 => ObjectiveAction::Update {
+                display_name: String::deserialize(buf)?,
+                kind: deserialize_kind(buf)?,
+            },
+            mode => return Err(ScoreboardError::Unknown(mode)),
+        })
+    }
+}
+
+impl Serialize for DisplaySlot {
+    fn size(&self) -> i32 {
+        1
+    }
+
+    fn serialize(&self, buf: &mut BytesMut) {
+        buf.put_u8(match self {
+            DisplaySlot::List => 0,
+            DisplaySlot::Sidebar => 1,
+            DisplaySlot::BelowName => 2,
+            DisplaySlot::TeamSidebar(color) => 3 + (color & 0xF),
+        });
+    }
+}
+
+impl Deserialize for DisplaySlot {
+    type Error = PrimitiveError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        Ok(match u8::deserialize(buf)? {
+            0 => DisplaySlot::List,
+            1 => DisplaySlot::Sidebar,
+            2 => DisplaySlot::BelowName,
+            slot => DisplaySlot::TeamSidebar(slot - 3),
+        })
+    }
+}
+
+/// The score of an entry, as its name, whether it's removed, the objective
+/// and the value when it isn't.
+#[derive(Debug)]
+pub struct ScoreUpdate {
+    pub entry: String,
+    pub objective: String,
+    pub value: Option<i32>,
+}
+
+impl Serialize for ScoreUpdate {
+    fn size(&self) -> i32 {
+        self.entry.size()
+            + 1
+            + self.objective.size()
+            + self.value.map_or(0, |value| VarInt::new(value).size())
+    }
+
+    fn serialize(&self, buf: &mut BytesMut) {
+        self.entry.serialize(buf);
+        buf.put_u8(self.value.is_none() as u8);
+        self.objective.serialize(buf);
+        if let Some(value) = self.value {
+            VarInt::new(value).serialize(buf);
+        }
+    }
+}
+
+impl Deserialize for ScoreUpdate {
+    type Error = ScoreboardError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        let entry = String::deserialize(buf)?;
+        let removed = match u8::deserialize(buf)? {
+            0 => false,
+            1 => true,
+            action => return Err(ScoreboardError::Unknown(action)),
+        };
+        let objective = String::deserialize(buf)?;
+        let value = match removed {
+            true => None,
+            false => Some(VarInt::deserialize(buf)?.value()),
+        };
+        Ok(Self {
+            entry,
+            objective,
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(value: &impl Serialize) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        value.serialize(&mut buf);
+        assert_eq!(buf.len() as i32, value.size());
+        buf.to_vec()
+    }
+
+    #[test]
+    fn objective_action_round_trip() {
+        let cases: [(ObjectiveAction, &[u8]); 3] = [
+            (
+                ObjectiveAction::Create {
+                    display_name: "Kills".to_string(),
+                    kind: ObjectiveKind::Integer,
+                },
+                b"\x00\x05Kills\x07integer",
+            ),
+            (ObjectiveAction::Remove, b"\x01"),
+            (
+                ObjectiveAction::Update {
+                    display_name: "Health".to_string(),
+                    kind: ObjectiveKind::Hearts,
+                },
+                b"\x02\x06Health\x06hearts",
+            ),
+        ];
+        for (action, bytes) in cases {
+            assert_eq!(encode(&action), bytes, "encoding {action:?}");
+            let decoded = ObjectiveAction::deserialize(&mut Bytes::copy_from_slice(bytes)).unwrap();
+            assert_eq!(decoded, action);
+        }
+
+        assert!(matches!(
+            ObjectiveAction::deserialize(&mut Bytes::from_static(b"\x03")),
+            Err(ScoreboardError::Unknown(3))
+        ));
+        assert!(matches!(
+            ObjectiveAction::deserialize(&mut Bytes::from_static(b"\x00\x00\x05stars")),
+            Err(ScoreboardError::Kind(kind)) if kind == "stars"
+        ));
+    }
+
+    #[test]
+    fn display_slot_round_trip() {
+        let cases = [
+            (DisplaySlot::List, 0),
+            (DisplaySlot::Sidebar, 1),
+            (DisplaySlot::BelowName, 2),
+            (DisplaySlot::TeamSidebar(0), 3),
+            (DisplaySlot::TeamSidebar(12), 15),
+        ];
+        for (slot, byte) in cases {
+            assert_eq!(encode(&slot), [byte]);
+            let decoded = DisplaySlot::deserialize(&mut Bytes::copy_from_slice(&[byte])).unwrap();
+            assert_eq!(decoded, slot);
+        }
+    }
+
+    #[test]
+    fn score_update_round_trip() {
+        let cases: [(Option<i32>, &[u8]); 2] = [
+            (Some(300), b"\x05alice\x00\x05kills\xAC\x02"),
+            (None, b"\x05alice\x01\x05kills"),
+        ];
+        for (value, bytes) in cases {
+            let update = ScoreUpdate {
+                entry: "alice".to_string(),
+                objective: "kills".to_string(),
+                value,
+            };
+            assert_eq!(encode(&update), bytes);
+            let decoded = ScoreUpdate::deserialize(&mut Bytes::copy_from_slice(bytes)).unwrap();
+            assert_eq!(
+                (decoded.entry, decoded.objective, decoded.value),
+                (update.entry, update.objective, update.value)
+            );
+        }
+
+        assert!(matches!(
+            ScoreUpdate::deserialize(&mut Bytes::from_static(b"\x05alice\x02\x05kills")),
+            Err(ScoreboardError::Unknown(2))
+        ));
+    }
+}
@@ -0,0 +1,337 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use makar_protocol::{ItemMeta, ItemStack};
+
+use crate::protocol::{Deserialize, PrimitiveError, Serialize};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SlotError {
+    #[error("invalid item nbt")]
+    Nbt,
+    #[error(transparent)]
+    Primitive(#[from] PrimitiveError),
+}
+
+const TAG_END: u8 = 0;
+const TAG_STRING: u8 = 8;
+const TAG_LIST: u8 = 9;
+const TAG_COMPOUND: u8 = 10;
+
+/// An item stack, or -1 for an empty slot. Stacks end with their nbt, only
+/// holding how the item is shown here.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slot(pub Option<ItemStack>);
+
+impl Serialize for Slot {
+    fn size(&self) -> i32 {
+        stack_size(self.0.as_ref())
+    }
+
+    fn serialize(&self, buf: &mut BytesMut) {
+        write_stack(self.0.as_ref(), buf)
+    }
+}
+
+impl Deserialize for Slot {
+    type Error = SlotError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        let id = i16::deserialize(buf)?;
+        if id == -1 {
+            return Ok(Self(None));
+        }
+        let count = u8::deserialize(buf)?;
+        let damage = i16::deserialize(buf)?;
+        let meta = match u8::deserialize(buf)? {
+            TAG_END => ItemMeta::default(),
+            TAG_COMPOUND => {
+                read_string(buf)?;
+                read_meta(buf)?
+            }
+            _ => return Err(SlotError::Nbt),
+        };
+        Ok(Self(Some(ItemStack {
+            id,
+            count,
+            damage,
+            meta,
+        })))
+    }
+}
+
+fn stack_size(stack: Option<&ItemStack>) -> i32 {
+    match stack {
+        Some(stack) if stack.meta == ItemMeta::default() => 6,
+        Some(stack) => 5 + meta_nbt(&stack.meta).len() as i32,
+        None => 2,
+    }
+}
+
+fn write_stack(stack: Option<&ItemStack>, buf: &mut BytesMut) {
+    match stack {
+        Some(stack) => {
+            buf.put_i16(stack.id);
+            buf.put_u8(stack.count);
+            buf.put_i16(stack.damage);
+            if stack.meta == ItemMeta::default() {
+                // TAG_End in place of a compound
+                buf.put_u8(TAG_END);
+            } else {
+                buf.put_slice(&meta_nbt(&stack.meta));
+            }
+        }
+        None => buf.put_i16(-1),
+    }
+}
+
+fn write_string(buf: &mut BytesMut, string: &str) {
+    buf.put_u16(string.len() as u16);
+    buf.put_slice(string.as_bytes());
+}
+
+fn write_entry(buf: &mut BytesMut, id: u8, name: &str) {
+    buf.put_u8(id);
+    write_string(buf, name);
+}
+
+/// The root compound of an item, with its name and lore under `display`.
+/// Glinting items get an empty `ench` list, which clients take for an
+/// enchanted item without listing any enchantment.
+fn meta_nbt(meta: &ItemMeta) -> BytesMut {
+    let mut buf = BytesMut::new();
+    write_entry(&mut buf, TAG_COMPOUND, "");
+    if meta.name.is_some() || !meta.lore.is_empty() {
+        write_entry(&mut buf, TAG_COMPOUND, "display");
+        if let Some(name) = &meta.name {
+            write_entry(&mut buf, TAG_STRING, "Name");
+            write_string(&mut buf, name);
+        }
+        if !meta.lore.is_empty() {
+            write_entry(&mut buf, TAG_LIST, "Lore");
+            buf.put_u8(TAG_STRING);
+            buf.put_i32(meta.lore.len() as i32);
+            for line in &meta.lore {
+                write_string(&mut buf, line);
+            }
+        }
+        buf.put_u8(TAG_END);
+    }
+    if meta.glint {
+        write_entry(&mut buf, TAG_LIST, "ench");
+        buf.put_u8(TAG_COMPOUND);
+        buf.put_i32(0);
+    }
+    buf.put_u8(TAG_END);
+    buf
+}
+
+fn read_string(buf: &mut Bytes) -> Result<String, SlotError> {
+    let len = u16::deserialize(buf)? as usize;
+    if buf.remaining() < len {
+        return Err(SlotError::Nbt);
+    }
+    Ok(String::from_utf8_lossy(&buf.split_to(len)).to_string())
+}
+
+/// Reads the entries of the root compound of an item, skipping those not
+/// about how it's shown.
+fn read_meta(buf: &mut Bytes) -> Result<ItemMeta, SlotError> {
+    let mut meta = ItemMeta::default();
+    loop {
+        let id = u8::deserialize(buf)?;
+        if id == TAG_END {
+            return Ok(meta);
+        }
+        match (id, read_string(buf)?.as_str()) {
+            (TAG_COMPOUND, "display") => loop {
+                let id = u8::deserialize(buf)?;
+                if id == TAG_END {
+                    break;
+                }
+                match (id, read_string(buf)?.as_str()) {
+                    (TAG_STRING, "Name") => meta.name = Some(read_string(buf)?),
+                    (TAG_LIST, "Lore") => {
+                        let element = u8::deserialize(buf)?;
+                        let len = i32::deserialize(buf)?.max(0);
+                        for _ in 0..len {
+                            if element != TAG_STRING {
+                                return Err(SlotError::Nbt);
+                            }
+                            meta.lore.push(read_string(buf)?);
+                        }
+                    }
+                    _ => skip(buf, id)?,
+                }
+            },
+            (TAG_LIST, "ench") => {
+                meta.glint = true;
+                skip(buf, id)?;
+            }
+            _ => skip(buf, id)?,
+        }
+    }
+}
+
+/// Skips the payload of a tag.
+fn skip(buf: &mut Bytes, id: u8) -> Result<(), SlotError> {
+    let size = match id {
+        1 => 1,
+        2 => 2,
+        3 | 5 => 4,
+        4 | 6 => 8,
+        7 => i32::deserialize(buf)?.max(0) as usize,
+        TAG_STRING => u16::deserialize(buf)? as usize,
+        TAG_LIST => {
+            let element = u8::deserialize(buf)?;
+            for _ in 0..i32::deserialize(buf)?.max(0) {
+                skip(buf, element)?;
+            }
+            0
+        }
+        TAG_COMPOUND => {
+            loop {
+                let id = u8::deserialize(buf)?;
+                if id == TAG_END {
+                    break;
+                }
+                read_string(buf)?;
+                skip(buf, id)?;
+            }
+            0
+        }
+        11 => i32::deserialize(buf)?.max(0) as usize * 4,
+        _ => return Err(SlotError::Nbt),
+    };
+    if buf.remaining() < size {
+        return Err(SlotError::Nbt);
+    }
+    buf.advance(size);
+    Ok(())
+}
+
+/// The slots of a window, prefixed by their count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slots(pub Vec<Option<ItemStack>>);
+
+impl Serialize for Slots {
+    fn size(&self) -> i32 {
+        2 + self
+            .0
+            .iter()
+            .map(|slot| stack_size(slot.as_ref()))
+            .sum::<i32>()
+    }
+
+    fn serialize(&self, buf: &mut BytesMut) {
+        buf.put_i16(self.0.len() as i16);
+        for slot in &self.0 {
+            write_stack(slot.as_ref(), buf);
+        }
+    }
+}
+
+impl Deserialize for Slots {
+    type Error = SlotError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        let count = i16::deserialize(buf)?.max(0);
+        let slots = (0..count)
+            .map(|_| Ok(Slot::deserialize(buf)?.0))
+            .collect::<Result<_, SlotError>>()?;
+        Ok(Self(slots))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(value: &impl Serialize) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        value.serialize(&mut buf);
+        assert_eq!(buf.len() as i32, value.size());
+        buf.to_vec()
+    }
+
+    #[test]
+    fn slot_with_meta_round_trip() {
+        let slot = Slot(Some(ItemStack {
+            id: 276,
+            count: 1,
+            damage: 0,
+            meta: ItemMeta {
+                name: Some("Blade".to_string()),
+                lore: vec!["Sharp".to_string()],
+                glint: true,
+            },
+        }));
+        #[rustfmt::skip]
+        let bytes: &[u8] = &[
+            0x01, 0x14, 0x01, 0x00, 0x00,
+            // root compound
+            0x0A, 0x00, 0x00,
+            0x0A, 0x00, 0x07, b'd', b'i', b's', b'p', b'l', b'a', b'y',
+            0x08, 0x00, 0x04, b'N', b'a', b'm', b'e', 0x00, 0x05, b'B', b'l', b'a', b'd', b'e',
+            0x09, 0x00, 0x04, b'L', b'o', b'r', b'e', 0x08, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x05, b'S', b'h', b'a', b'r', b'p',
+            0x00,
+            // an empty list of enchantments
+            0x09, 0x00, 0x04, b'e', b'n', b'c', b'h', 0x0A, 0x00, 0x00, 0x00, 0x00,
+            0x00,
+        ];
+
+        assert_eq!(encode(&slot), bytes);
+        let mut buf = Bytes::copy_from_slice(bytes);
+        assert_eq!(Slot::deserialize(&mut buf).unwrap(), slot);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn slot_without_meta_round_trip() {
+        let cases: [(Slot, &[u8]); 2] = [
+            (
+                Slot(Some(ItemStack {
+                    id: 1,
+                    count: 64,
+                    damage: 3,
+                    meta: ItemMeta::default(),
+                })),
+                &[0x00, 0x01, 0x40, 0x00, 0x03, 0x00],
+            ),
+            (Slot(None), &[0xFF, 0xFF]),
+        ];
+        for (slot, bytes) in cases {
+            assert_eq!(encode(&slot), bytes);
+            assert_eq!(
+                Slot::deserialize(&mut Bytes::copy_from_slice(bytes)).unwrap(),
+                slot
+            );
+        }
+    }
+
+    #[test]
+    fn skips_other_nbt() {
+        #[rustfmt::skip]
+        let bytes: &[u8] = &[
+            0x01, 0x14, 0x01, 0x00, 0x00,
+            0x0A, 0x00, 0x00,
+            // RepairCost: 3
+            0x03, 0x00, 0x0A, b'R', b'e', b'p', b'a', b'i', b'r', b'C', b'o', b's', b't',
+            0x00, 0x00, 0x00, 0x03,
+            // an enchantment, which only makes the item glint
+            0x09, 0x00, 0x04, b'e', b'n', b'c', b'h', 0x0A, 0x00, 0x00, 0x00, 0x01,
+            0x02, 0x00, 0x02, b'i', b'd', 0x00, 0x10,
+            0x02, 0x00, 0x03, b'l', b'v', b'l', 0x00, 0x05,
+            0x00,
+            0x00,
+        ];
+        let slot = Slot::deserialize(&mut Bytes::copy_from_slice(bytes)).unwrap();
+        let meta = slot.0.unwrap().meta;
+        assert_eq!(
+            meta,
+            ItemMeta {
+                name: None,
+                lore: Vec::new(),
+                glint: true,
+            }
+        );
+    }
+}
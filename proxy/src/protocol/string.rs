@@ -5,6 +5,8 @@ use bytes::{BufMut, Bytes, BytesMut};
 pub enum StringError {
     #[error("String too long")]
     TooLong,
+    #[error("String incomplete")]
+    Incomplete,
 }
 
 impl Serialize for String {
@@ -26,6 +28,9 @@ impl Deserialize for String {
         let length = VarInt::deserialize(buf)
             .map_err(|_| StringError::TooLong)?
             .value() as usize;
+        if buf.len() < length {
+            return Err(StringError::Incomplete);
+        }
         let res = buf.split_to(length);
         Ok(String::from_utf8_lossy(&res).to_string())
     }
@@ -0,0 +1,240 @@
+use bytes::{BufMut, Bytes, BytesMut};
+
+use makar_protocol::{NameTagVisibility, TeamAction, TeamInfo};
+
+use crate::protocol::{Deserialize, PrimitiveError, Serialize, StringError, VarInt, VarLenError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TeamError {
+    #[error("unknown team mode {0}")]
+    Unknown(u8),
+    #[error("unknown name tag visibility {0}")]
+    Visibility(String),
+    #[error(transparent)]
+    Primitive(#[from] PrimitiveError),
+    #[error(transparent)]
+    VarLen(#[from] VarLenError),
+    #[error(transparent)]
+    String(#[from] StringError),
+}
+
+/// Chat colors by their formatting code.
+const COLORS: [&str; 16] = [
+    "black",
+    "dark_blue",
+    "dark_green",
+    "dark_aqua",
+    "dark_red",
+    "dark_purple",
+    "gold",
+    "gray",
+    "dark_gray",
+    "blue",
+    "green",
+    "aqua",
+    "red",
+    "light_purple",
+    "yellow",
+    "white",
+];
+
+const VISIBILITIES: [(NameTagVisibility, &str); 4] = [
+    (NameTagVisibility::Always, "always"),
+    (NameTagVisibility::Never, "never"),
+    (NameTagVisibility::HideForOtherTeams, "hideForOtherTeams"),
+    (NameTagVisibility::HideForOwnTeam, "hideForOwnTeam"),
+];
+
+fn visibility_name(visibility: NameTagVisibility) -> String {
+    VISIBILITIES
+        .iter()
+        .find(|(v, _)| *v == visibility)
+        .map(|(_, name)| name.to_string())
+        .expect("every visibility should be named")
+}
+
+fn color_code(color: &Option<String>) -> i8 {
+    color
+        .as_ref()
+        .and_then(|color| COLORS.iter().position(|name| name == color))
+        .map_or(-1, |code| code as i8)
+}
+
+fn info_size(info: &TeamInfo) -> i32 {
+    info.display_name.size()
+        + info.prefix.size()
+        + info.suffix.size()
+        + 1
+        + visibility_name(info.name_tag_visibility).size()
+        + 1
+}
+
+fn serialize_info(info: &TeamInfo, buf: &mut BytesMut) {
+    info.display_name.serialize(buf);
+    info.prefix.serialize(buf);
+    info.suffix.serialize(buf);
+    buf.put_u8(info.friendly_fire as u8 | (info.see_invisible_teammates as u8) << 1);
+    visibility_name(info.name_tag_visibility).serialize(buf);
+    buf.put_i8(color_code(&info.color));
+}
+
+fn deserialize_info(buf: &mut Bytes) -> Result<TeamInfo, TeamError> {
+    let display_name = String::deserialize(buf)?;
+    let prefix = String::deserialize(buf)?;
+    let suffix = String::deserialize(buf)?;
+    let flags = u8::deserialize(buf)?;
+    let visibility = String::deserialize(buf)?;
+    let name_tag_visibility = VISIBILITIES
+        .iter()
+        .find(|(_, name)| *name == visibility)
+        .map(|(v, _)| *v)
+        .ok_or(TeamError::Visibility(visibility))?;
+    let color = COLORS
+        .get(i8::deserialize(buf)? as usize)
+        .map(|name| name.to_string());
+    Ok(TeamInfo {
+        display_name,
+        prefix,
+        suffix,
+        friendly_fire: flags & 1 != 0,
+        see_invisible_teammates: flags & 2 != 0,
+        name_tag_visibility,
+        color,
+    })
+}
+
+fn members_size(members: &[String]) -> i32 {
+    VarInt::new(members.len() as i32).size() + members.iter().map(String::size).sum::<i32>()
+}
+
+fn serialize_members(members: &[String], buf: &mut BytesMut) {
+    VarInt::new(members.len() as i32).serialize(buf);
+    for member in members {
+        member.serialize(buf);
+    }
+}
+
+fn deserialize_members(buf: &mut Bytes) -> Result<Vec<String>, TeamError> {
+    let count = VarInt::deserialize(buf)?.value();
+    (0..count).map(|_| Ok(String::deserialize(buf)?)).collect()
+}
+
+/// A team change, as the mode byte followed by the team's info, its
+/// members, or both.
+impl Serialize for TeamAction {
+    fn size(&self) -> i32 {
+        1 + match self {
+            TeamAction::Create { info, members } => info_size(info) + members_size(members),
+            TeamAction::Remove => 0,
+            TeamAction::Update(info) => info_size(info),
+            TeamAction::AddMembers(members) | TeamAction::RemoveMembers(members) => {
+                members_size(members)
+            }
+        }
+    }
+
+    fn serialize(&self, buf: &mut BytesMut) {
+        match self {
+            TeamAction::Create { info, members } => {
+                buf.put_u8(0);
+                serialize_info(info, buf);
+                serialize_members(members, buf);
+            }
+            TeamAction::Remove => buf.put_u8(1),
+            TeamAction::Update(info) => {
+                buf.put_u8(2);
+                serialize_info(info, buf);
+            }
+            TeamAction::AddMembers(members) => {
+                buf.put_u8(3);
+                serialize_members(members, buf);
+            }
+            TeamAction::RemoveMembers(members) => {
+                buf.put_u8(4);
+                serialize_members(members, buf);
+            }
+        }
+    }
+}
+
+impl Deserialize for TeamAction {
+    type Error = TeamError;
+    fn deserialize(buf: &mut Bytes) -> Result<Self, Self::Error> {
+        Ok(match u8::deserialize(buf)? {
+            0 => TeamAction::Create {
+                info: deserialize_info(buf)?,
+                members: deserialize_members(buf)?,
+            },
+            1 => TeamAction::Remove,
+            2 => TeamAction::Update(deserialize_info(buf)?),
+            3 => TeamAction::AddMembers(deserialize_members(buf)?),
+            4 => TeamAction::RemoveMembers(deserialize_members(buf)?),
+            mode => return Err(TeamError::Unknown(mode)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(value: &impl Serialize) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        value.serialize(&mut buf);
+        assert_eq!(buf.len() as i32, value.size());
+        buf.to_vec()
+    }
+
+    #[test]
+    fn team_action_round_trip() {
+        let info = TeamInfo {
+            display_name: "Red".to_string(),
+            prefix: "[R] ".to_string(),
+            suffix: String::new(),
+            friendly_fire: true,
+            see_invisible_teammates: true,
+            name_tag_visibility: NameTagVisibility::HideForOtherTeams,
+            color: Some("red".to_string()),
+        };
+        let cases: [(TeamAction, &[u8]); 4] = [
+            (
+                TeamAction::Create {
+                    info: info.clone(),
+                    members: vec!["alice".to_string()],
+                },
+                b"\x00\x03Red\x04[R] \x00\x03\x11hideForOtherTeams\x0C\x01\x05alice",
+            ),
+            (TeamAction::Remove, b"\x01"),
+            (
+                TeamAction::Update(TeamInfo {
+                    friendly_fire: false,
+                    name_tag_visibility: NameTagVisibility::Always,
+                    color: None,
+                    ..info
+                }),
+                b"\x02\x03Red\x04[R] \x00\x02\x06always\xFF",
+            ),
+            (
+                TeamAction::RemoveMembers(vec!["bob".to_string(), "eve".to_string()]),
+                b"\x04\x02\x03bob\x03eve",
+            ),
+        ];
+        for (action, bytes) in cases {
+            assert_eq!(encode(&action), bytes, "encoding {action:?}");
+            let decoded = TeamAction::deserialize(&mut Bytes::copy_from_slice(bytes)).unwrap();
+            assert_eq!(decoded, action);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_teams() {
+        assert!(matches!(
+            TeamAction::deserialize(&mut Bytes::from_static(b"\x05")),
+            Err(TeamError::Unknown(5))
+        ));
+        assert!(matches!(
+            TeamAction::deserialize(&mut Bytes::from_static(b"\x02\x00\x00\x00\x00\x03off\x00")),
+            Err(TeamError::Visibility(visibility)) if visibility == "off"
+        ));
+    }
+}
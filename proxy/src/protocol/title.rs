@@ -1,6 +1,16 @@
 use bytes::BufMut;
 
-use crate::protocol::{Chat, Serialize};
+use crate::protocol::{Chat, ChatError, Deserialize, PrimitiveError, Serialize};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TitleError {
+    #[error("unknown title action {0}")]
+    Unknown(u8),
+    #[error(transparent)]
+    Primitive(#[from] PrimitiveError),
+    #[error(transparent)]
+    Chat(#[from] ChatError),
+}
 
 #[derive(Debug)]
 pub enum TitleAction {
@@ -15,6 +25,27 @@ pub enum TitleAction {
     Reset,
 }
 
+impl From<makar_protocol::TitleAction> for TitleAction {
+    fn from(value: makar_protocol::TitleAction) -> Self {
+        use makar_protocol::TitleAction::*;
+        match value {
+            SetTitle(chat) => Self::SetTitle(chat.into()),
+            SetSubtitle(chat) => Self::SetSubtitle(chat.into()),
+            SetTimes {
+                fade_in,
+                stay,
+                fade_out,
+            } => Self::SetTimes {
+                fade_in,
+                stay,
+                fade_out,
+            },
+            Hide => Self::Hide,
+            Reset => Self::Reset,
+        }
+    }
+}
+
 impl Serialize for TitleAction {
     fn size(&self) -> i32 {
         match self {
@@ -53,3 +84,21 @@ impl Serialize for TitleAction {
         }
     }
 }
+
+impl Deserialize for TitleAction {
+    type Error = TitleError;
+    fn deserialize(buf: &mut bytes::Bytes) -> Result<Self, Self::Error> {
+        Ok(match u8::deserialize(buf)? {
+            0 => TitleAction::SetTitle(Chat::deserialize(buf)?),
+            1 => TitleAction::SetSubtitle(Chat::deserialize(buf)?),
+            2 => TitleAction::SetTimes {
+                fade_in: u32::deserialize(buf)?,
+                stay: u32::deserialize(buf)?,
+                fade_out: u32::deserialize(buf)?,
+            },
+            3 => TitleAction::Hide,
+            4 => TitleAction::Reset,
+            v => return Err(TitleError::Unknown(v)),
+        })
+    }
+}
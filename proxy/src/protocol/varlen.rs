@@ -1,12 +1,18 @@
+use tokio::io::{AsyncRead, AsyncReadExt};
+
 #[derive(Debug, thiserror::Error)]
 pub enum VarLenError {
     #[error("VarLen too long")]
     TooLong,
+    #[error("VarLen incomplete")]
+    Incomplete,
+    #[error("VarLen read failed: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 macro_rules! varlen {
-    ($name:ident, $type:ty) => {
-        #[derive(Debug)]
+    ($name:ident, $type:ty, $unsigned:ty, $max:expr) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
         pub struct $name($type);
 
         impl $name {
@@ -17,11 +23,25 @@ macro_rules! varlen {
             pub fn value(&self) -> $type {
                 self.0
             }
+
+            /// Decodes a value byte by byte from an async reader, without
+            /// consuming anything past its last byte.
+            pub async fn read<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self, VarLenError> {
+                let mut res: $unsigned = 0;
+                for i in 0..$max {
+                    let b = reader.read_u8().await?;
+                    res |= ((b & 0x7F) as $unsigned) << (7 * i);
+                    if (b & 0x80) == 0 {
+                        return Ok(Self::new(res as $type));
+                    }
+                }
+                Err(VarLenError::TooLong)
+            }
         }
 
         impl crate::protocol::Serialize for $name {
             fn size(&self) -> i32 {
-                let mut value = self.value();
+                let mut value = self.value() as $unsigned;
                 let mut size = 1;
                 while value >= 0x80 {
                     value >>= 7;
@@ -33,9 +53,9 @@ macro_rules! varlen {
             fn serialize(&self, buf: &mut bytes::BytesMut) {
                 use bytes::BufMut;
 
-                let mut value = self.value();
-                while (value & 0x80) == 0x80 {
-                    buf.put_u8((value as u8) | 0x80);
+                let mut value = self.value() as $unsigned;
+                while value >= 0x80 {
+                    buf.put_u8((value as u8 & 0x7F) | 0x80);
                     value >>= 7;
                 }
                 buf.put_u8(value as u8);
@@ -47,27 +67,119 @@ macro_rules! varlen {
             fn deserialize(buf: &mut bytes::Bytes) -> Result<Self, Self::Error> {
                 use bytes::Buf;
 
-                let mut res: $type = 0;
-                let mut pos: u8 = 0;
-                let mut b: u8;
-
-                loop {
-                    b = buf.get_u8();
-                    res |= (b as $type & 0x7F) << pos;
-                    if (b & 0x80) == 0 {
-                        break;
+                let mut res: $unsigned = 0;
+                for i in 0..$max {
+                    if !buf.has_remaining() {
+                        return Err(VarLenError::Incomplete);
                     }
-
-                    pos += 7;
-                    if pos >= 32 {
-                        return Err(VarLenError::TooLong);
+                    let b = buf.get_u8();
+                    res |= ((b & 0x7F) as $unsigned) << (7 * i);
+                    if (b & 0x80) == 0 {
+                        return Ok(Self::new(res as $type));
                     }
                 }
-                Ok(Self::new(res))
+                Err(VarLenError::TooLong)
             }
         }
     };
 }
 
-varlen!(VarInt, i32);
-varlen!(VarLong, i64);
+varlen!(VarInt, i32, u32, 5);
+varlen!(VarLong, i64, u64, 10);
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+
+    use super::*;
+    use crate::protocol::{Deserialize, Serialize};
+
+    fn encode(value: impl Serialize) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        value.serialize(&mut buf);
+        assert_eq!(buf.len() as i32, value.size());
+        buf.to_vec()
+    }
+
+    #[test]
+    fn varint_round_trip() {
+        let cases: [(i32, &[u8]); 7] = [
+            (0, &[0x00]),
+            (127, &[0x7F]),
+            (128, &[0x80, 0x01]),
+            (300, &[0xAC, 0x02]),
+            (-1, &[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]),
+            (i32::MAX, &[0xFF, 0xFF, 0xFF, 0xFF, 0x07]),
+            (i32::MIN, &[0x80, 0x80, 0x80, 0x80, 0x08]),
+        ];
+        for (value, bytes) in cases {
+            assert_eq!(encode(VarInt::new(value)), bytes, "encoding {value}");
+            let decoded = VarInt::deserialize(&mut Bytes::copy_from_slice(bytes)).unwrap();
+            assert_eq!(decoded.value(), value);
+        }
+    }
+
+    #[test]
+    fn varlong_round_trip() {
+        let cases: [(i64, &[u8]); 7] = [
+            (0, &[0x00]),
+            (127, &[0x7F]),
+            (128, &[0x80, 0x01]),
+            (300, &[0xAC, 0x02]),
+            (
+                -1,
+                &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01],
+            ),
+            (
+                i64::MAX,
+                &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F],
+            ),
+            (
+                i64::MIN,
+                &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01],
+            ),
+        ];
+        for (value, bytes) in cases {
+            assert_eq!(encode(VarLong::new(value)), bytes, "encoding {value}");
+            let decoded = VarLong::deserialize(&mut Bytes::copy_from_slice(bytes)).unwrap();
+            assert_eq!(decoded.value(), value);
+        }
+    }
+
+    #[test]
+    fn rejects_overlong_encodings() {
+        let mut varint = Bytes::from_static(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x01]);
+        assert!(matches!(
+            VarInt::deserialize(&mut varint),
+            Err(VarLenError::TooLong)
+        ));
+        let mut varlong = Bytes::from_static(&[0x80; 11]);
+        assert!(matches!(
+            VarLong::deserialize(&mut varlong),
+            Err(VarLenError::TooLong)
+        ));
+        assert!(matches!(
+            VarInt::deserialize(&mut Bytes::from_static(&[0x80, 0x80])),
+            Err(VarLenError::Incomplete)
+        ));
+    }
+
+    #[tokio::test]
+    async fn reads_from_a_stream() {
+        let mut stream: &[u8] = &[0xAC, 0x02, 0x2A];
+        assert_eq!(VarInt::read(&mut stream).await.unwrap().value(), 300);
+        // nothing past the value is consumed
+        assert_eq!(stream, [0x2A]);
+
+        let mut stream: &[u8] = &[0xFF; 6];
+        assert!(matches!(
+            VarInt::read(&mut stream).await,
+            Err(VarLenError::TooLong)
+        ));
+        let mut stream: &[u8] = &[0xFF; 11];
+        assert!(matches!(
+            VarLong::read(&mut stream).await,
+            Err(VarLenError::TooLong)
+        ));
+    }
+}
@@ -0,0 +1,25 @@
+use makar_protocol::{ClickMode, WindowKind};
+
+/// The network name of a window, and how many slots it has besides the
+/// inventory of the player, none for windows that aren't storage.
+pub fn window_type(kind: WindowKind) -> (&'static str, u8) {
+    match kind {
+        WindowKind::Chest(rows) => ("minecraft:chest", rows.clamp(1, 6) * 9),
+        WindowKind::CraftingTable => ("minecraft:crafting_table", 0),
+        WindowKind::Dispenser => ("minecraft:dispenser", 9),
+        WindowKind::Hopper => ("minecraft:hopper", 5),
+    }
+}
+
+pub fn click_mode(mode: u8) -> Option<ClickMode> {
+    Some(match mode {
+        0 => ClickMode::Click,
+        1 => ClickMode::ShiftClick,
+        2 => ClickMode::NumberKey,
+        3 => ClickMode::MiddleClick,
+        4 => ClickMode::Drop,
+        5 => ClickMode::Drag,
+        6 => ClickMode::DoubleClick,
+        _ => return None,
+    })
+}
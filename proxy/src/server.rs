@@ -1,44 +1,66 @@
 use std::error::Error;
 
-use makar_protocol::{ProxyBoundPacket, ServerBoundPacket, TitleAction};
+use bytes::BytesMut;
+
+use makar_protocol::{
+    DemoMessage, EntityStatus, GameStateChange, Gamemode, ProxyBoundPacket, ServerBoundPacket,
+};
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     net::TcpStream,
     sync::mpsc,
 };
 
-use log::info;
+use log::warn;
 
-use crate::{versions::v1_8_8::*, ProxyContext};
+use crate::{
+    protocol::{
+        chunk::{encode_column, BulkColumn},
+        entity::{angle, fixed, fixed_delta, velocity},
+        window::window_type,
+        BlockRecords, ByteArray, ChunkBulk, EntityIds, EntityMetadata, ObjectData, ScoreUpdate,
+        Slot, Slots, VarInt,
+    },
+    versions::v1_8_8::*,
+    ProxyContext,
+};
 
 pub async fn server_task(
-    address: &str,
+    socket: TcpStream,
     mut rx: mpsc::Receiver<ServerBoundPacket>,
     ctx: ProxyContext,
 ) -> Result<(), Box<dyn Error>> {
-    let mut socket = TcpStream::connect(address).await?;
-    info!("connected to server at {address}");
+    let (mut reader, mut writer) = socket.into_split();
 
-    let mut size = [0u8; 4];
-    loop {
-        tokio::select! {
-            _ = socket.read_exact(&mut size) => {
-                let size = u32::from_be_bytes(size);
-                let mut buf = vec![0u8; size as usize];
-                socket.read_exact(&mut buf).await?;
-                let packet = ProxyBoundPacket::deserialize(&buf)?;
-                handle_packet(packet, &ctx).await?;
-            }
-            msg = rx.recv() => {
-                match msg {
-                    Some(message) => {
-                        socket.write_all(&message.serialize()?).await?;
-                    }
-                    None => {}
-                };
+    // reads aren't cancel safe, so writes happen on their own task
+    let writer = tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            let buf = match message.serialize() {
+                Ok(buf) => buf,
+                Err(e) => {
+                    warn!("couldn't serialize {message:?}: {e}");
+                    continue;
+                }
+            };
+            if let Err(e) = writer.write_all(&buf).await {
+                warn!("couldn't write to server: {e}");
+                break;
             }
         }
+    });
+
+    let res = async {
+        loop {
+            let size = reader.read_u32().await?;
+            let mut buf = vec![0u8; size as usize];
+            reader.read_exact(&mut buf).await?;
+            let packet = ProxyBoundPacket::deserialize(&buf)?;
+            handle_packet(packet, &ctx).await?;
+        }
     }
+    .await;
+    writer.abort();
+    res
 }
 
 pub async fn handle_packet(
@@ -65,12 +87,7 @@ pub async fn handle_packet(
                 level_type,
                 reduced_debug_info: if reduced_debug_info { 1 } else { 0 },
             };
-            ctx.players_tx
-                .send(crate::players::Message::Send(
-                    player,
-                    packet.serialize().to_vec(),
-                ))
-                .await?;
+            send(ctx, player, packet).await?;
         }
         ProxyBoundPacket::ChatMessage {
             player,
@@ -81,92 +98,599 @@ pub async fn handle_packet(
                 json: json.into(),
                 position,
             };
-            ctx.players_tx
-                .send(crate::players::Message::Send(
-                    player,
-                    packet.serialize().to_vec(),
-                ))
-                .await?;
-        }
-        ProxyBoundPacket::Title { player, action } => match action {
-            TitleAction::Set {
-                title,
-                subtitle,
-                fade_in,
-                stay,
-                fade_out,
-            } => {
-                match title {
-                    Some(chat) => {
-                        ctx.players_tx
-                            .send(crate::players::Message::Send(
-                                player,
-                                ClientBoundPacket::Title {
-                                    action: crate::protocol::TitleAction::SetTitle(chat.into()),
-                                }
-                                .serialize()
-                                .to_vec(),
-                            ))
-                            .await?
-                    }
-                    None => {}
-                };
-                match subtitle {
-                    Some(chat) => {
-                        ctx.players_tx
-                            .send(crate::players::Message::Send(
-                                player,
-                                ClientBoundPacket::Title {
-                                    action: crate::protocol::TitleAction::SetSubtitle(chat.into()),
-                                }
-                                .serialize()
-                                .to_vec(),
-                            ))
-                            .await?
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::Title { player, actions } => {
+            let packets = actions.into_iter().map(|action| ClientBoundPacket::Title {
+                action: action.into(),
+            });
+            send_all(ctx, player, packets).await?;
+        }
+        ProxyBoundPacket::SpawnPosition { player, position } => {
+            let packet = ClientBoundPacket::SpawnPosition {
+                location: position.into(),
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::PlayerAbilities { player, abilities } => {
+            let flags = [
+                abilities.invulnerable,
+                abilities.flying,
+                abilities.allow_flying,
+                abilities.creative_mode,
+            ]
+            .iter()
+            .enumerate()
+            .fold(0, |flags, (i, set)| flags | ((*set as i8) << i));
+            let packet = ClientBoundPacket::PlayerAbilities {
+                flags,
+                flying_speed: abilities.flying_speed,
+                walking_speed: abilities.walking_speed,
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::HeldItemChange { player, slot } => {
+            let packet = ClientBoundPacket::HeldItemChange { slot: slot as i8 };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::ChunkData { player, chunk } => {
+            let (primary_bit_mask, data) = encode_column(&chunk);
+            let packet = ClientBoundPacket::ChunkData {
+                x: chunk.x,
+                z: chunk.z,
+                ground_up: 1,
+                primary_bit_mask,
+                data: ByteArray(data),
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::UnloadChunk { player, x, z } => {
+            // a ground up column without any section unloads it
+            let packet = ClientBoundPacket::ChunkData {
+                x,
+                z,
+                ground_up: 1,
+                primary_bit_mask: 0,
+                data: ByteArray(Vec::new()),
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::MapChunkBulk {
+            player,
+            sky_light,
+            chunks,
+        } => {
+            let columns = chunks
+                .iter()
+                .map(|chunk| {
+                    let (primary_bit_mask, data) = encode_column(chunk);
+                    BulkColumn {
+                        x: chunk.x,
+                        z: chunk.z,
+                        primary_bit_mask,
+                        data,
                     }
-                    None => {}
-                };
-
-                ctx.players_tx
-                    .send(crate::players::Message::Send(
-                        player,
-                        ClientBoundPacket::Title {
-                            action: crate::protocol::TitleAction::SetTimes {
-                                fade_in,
-                                stay,
-                                fade_out,
-                            },
-                        }
-                        .serialize()
-                        .to_vec(),
-                    ))
-                    .await?;
-            }
-            TitleAction::Hide => {
-                ctx.players_tx
-                    .send(crate::players::Message::Send(
-                        player,
-                        ClientBoundPacket::Title {
-                            action: crate::protocol::TitleAction::Hide,
-                        }
-                        .serialize()
-                        .to_vec(),
-                    ))
-                    .await?;
-            }
-            TitleAction::Reset => {
-                ctx.players_tx
-                    .send(crate::players::Message::Send(
-                        player,
-                        ClientBoundPacket::Title {
-                            action: crate::protocol::TitleAction::Reset,
-                        }
-                        .serialize()
-                        .to_vec(),
-                    ))
-                    .await?;
-            }
-        },
+                })
+                .collect();
+            let packet = ClientBoundPacket::MapChunkBulk {
+                bulk: ChunkBulk { sky_light, columns },
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::PlayerPositionAndLook {
+            player,
+            x,
+            y,
+            z,
+            yaw,
+            pitch,
+        } => {
+            let packet = ClientBoundPacket::PlayerPositionAndLook {
+                x,
+                y,
+                z,
+                yaw,
+                pitch,
+                flags: 0,
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::TimeUpdate {
+            player,
+            world_age,
+            time_of_day,
+        } => {
+            let packet = ClientBoundPacket::TimeUpdate {
+                world_age,
+                time_of_day,
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::BlockChange {
+            player,
+            position,
+            block,
+        } => {
+            let packet = ClientBoundPacket::BlockChange {
+                location: position.into(),
+                block: VarInt::new(block as i32),
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::MultiBlockChange {
+            player,
+            chunk_x,
+            chunk_z,
+            records,
+        } => {
+            let packet = ClientBoundPacket::MultiBlockChange {
+                chunk_x,
+                chunk_z,
+                records: BlockRecords(records),
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::PlayerListItem { player, action } => {
+            let packet = ClientBoundPacket::PlayerListItem {
+                action: action.into(),
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::PlayerListHeaderFooter {
+            player,
+            header,
+            footer,
+        } => {
+            let packet = ClientBoundPacket::PlayerListHeaderFooter {
+                header: header.into(),
+                footer: footer.into(),
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::Teams {
+            player,
+            name,
+            action,
+        } => {
+            let packet = ClientBoundPacket::Teams { name, action };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::ScoreboardObjective {
+            player,
+            name,
+            action,
+        } => {
+            let packet = ClientBoundPacket::ScoreboardObjective { name, action };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::UpdateScore {
+            player,
+            entry,
+            objective,
+            value,
+        } => {
+            let packet = ClientBoundPacket::UpdateScore {
+                score: ScoreUpdate {
+                    entry,
+                    objective,
+                    value,
+                },
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::DisplayScoreboard {
+            player,
+            slot,
+            objective,
+        } => {
+            let packet = ClientBoundPacket::DisplayScoreboard { slot, objective };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::SpawnPlayer {
+            player,
+            entity_id,
+            uuid,
+            x,
+            y,
+            z,
+            yaw,
+            pitch,
+            current_item,
+            metadata,
+        } => {
+            let packet = ClientBoundPacket::SpawnPlayer {
+                entity_id: VarInt::new(entity_id),
+                uuid,
+                x: fixed(x),
+                y: fixed(y),
+                z: fixed(z),
+                yaw: angle(yaw),
+                pitch: angle(pitch),
+                current_item,
+                metadata: EntityMetadata(metadata),
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::SpawnObject {
+            player,
+            entity_id,
+            kind,
+            x,
+            y,
+            z,
+            yaw,
+            pitch,
+            data,
+            velocity: (velocity_x, velocity_y, velocity_z),
+        } => {
+            let packet = ClientBoundPacket::SpawnObject {
+                entity_id: VarInt::new(entity_id),
+                kind: kind as i8,
+                x: fixed(x),
+                y: fixed(y),
+                z: fixed(z),
+                pitch: angle(pitch),
+                yaw: angle(yaw),
+                data: ObjectData {
+                    data,
+                    velocity: (
+                        velocity(velocity_x),
+                        velocity(velocity_y),
+                        velocity(velocity_z),
+                    ),
+                },
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::SpawnMob {
+            player,
+            entity_id,
+            kind,
+            x,
+            y,
+            z,
+            yaw,
+            pitch,
+            head_yaw,
+            velocity: (velocity_x, velocity_y, velocity_z),
+            metadata,
+        } => {
+            let packet = ClientBoundPacket::SpawnMob {
+                entity_id: VarInt::new(entity_id),
+                kind,
+                x: fixed(x),
+                y: fixed(y),
+                z: fixed(z),
+                yaw: angle(yaw),
+                pitch: angle(pitch),
+                head_yaw: angle(head_yaw),
+                velocity_x: velocity(velocity_x),
+                velocity_y: velocity(velocity_y),
+                velocity_z: velocity(velocity_z),
+                metadata: EntityMetadata(metadata),
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::DestroyEntities { player, entity_ids } => {
+            let packet = ClientBoundPacket::DestroyEntities {
+                entity_ids: EntityIds(entity_ids),
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::CollectItem {
+            player,
+            collected,
+            collector,
+        } => {
+            let packet = ClientBoundPacket::CollectItem {
+                collected_entity_id: VarInt::new(collected),
+                collector_entity_id: VarInt::new(collector),
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::EntityRelativeMove {
+            player,
+            entity_id,
+            delta: (delta_x, delta_y, delta_z),
+            on_ground,
+        } => {
+            let packet = ClientBoundPacket::EntityRelativeMove {
+                entity_id: VarInt::new(entity_id),
+                delta_x: fixed_delta(delta_x),
+                delta_y: fixed_delta(delta_y),
+                delta_z: fixed_delta(delta_z),
+                on_ground: on_ground as u8,
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::EntityLook {
+            player,
+            entity_id,
+            yaw,
+            pitch,
+            on_ground,
+        } => {
+            let packet = ClientBoundPacket::EntityLook {
+                entity_id: VarInt::new(entity_id),
+                yaw: angle(yaw),
+                pitch: angle(pitch),
+                on_ground: on_ground as u8,
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::EntityLookAndRelativeMove {
+            player,
+            entity_id,
+            delta: (delta_x, delta_y, delta_z),
+            yaw,
+            pitch,
+            on_ground,
+        } => {
+            let packet = ClientBoundPacket::EntityLookAndRelativeMove {
+                entity_id: VarInt::new(entity_id),
+                delta_x: fixed_delta(delta_x),
+                delta_y: fixed_delta(delta_y),
+                delta_z: fixed_delta(delta_z),
+                yaw: angle(yaw),
+                pitch: angle(pitch),
+                on_ground: on_ground as u8,
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::EntityTeleport {
+            player,
+            entity_id,
+            x,
+            y,
+            z,
+            yaw,
+            pitch,
+            on_ground,
+        } => {
+            let packet = ClientBoundPacket::EntityTeleport {
+                entity_id: VarInt::new(entity_id),
+                x: fixed(x),
+                y: fixed(y),
+                z: fixed(z),
+                yaw: angle(yaw),
+                pitch: angle(pitch),
+                on_ground: on_ground as u8,
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::EntityHeadLook {
+            player,
+            entity_id,
+            head_yaw,
+        } => {
+            let packet = ClientBoundPacket::EntityHeadLook {
+                entity_id: VarInt::new(entity_id),
+                head_yaw: angle(head_yaw),
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::EntityVelocity {
+            player,
+            entity_id,
+            velocity: (velocity_x, velocity_y, velocity_z),
+        } => {
+            let packet = ClientBoundPacket::EntityVelocity {
+                entity_id: VarInt::new(entity_id),
+                velocity_x: velocity(velocity_x),
+                velocity_y: velocity(velocity_y),
+                velocity_z: velocity(velocity_z),
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::EntityMetadata {
+            player,
+            entity_id,
+            metadata,
+        } => {
+            let packet = ClientBoundPacket::EntityMetadata {
+                entity_id: VarInt::new(entity_id),
+                metadata: EntityMetadata(metadata),
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::Animation {
+            player,
+            entity_id,
+            animation,
+        } => {
+            let packet = ClientBoundPacket::Animation {
+                entity_id: VarInt::new(entity_id),
+                // variants are in the order the protocol numbers them
+                animation: animation as u8,
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::OpenWindow {
+            player,
+            window_id,
+            kind,
+            title,
+        } => {
+            let (kind, slot_count) = window_type(kind);
+            let packet = ClientBoundPacket::OpenWindow {
+                window_id,
+                kind: kind.to_string(),
+                title: title.into(),
+                slot_count,
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::CloseWindow { player, window_id } => {
+            let packet = ClientBoundPacket::CloseWindow { window_id };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::SetSlot {
+            player,
+            window_id,
+            slot,
+            item,
+        } => {
+            let packet = ClientBoundPacket::SetSlot {
+                window_id,
+                slot,
+                item: Slot(item),
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::WindowItems {
+            player,
+            window_id,
+            items,
+        } => {
+            let packet = ClientBoundPacket::WindowItems {
+                window_id,
+                items: Slots(items),
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::ConfirmTransaction {
+            player,
+            window_id,
+            action,
+            accepted,
+        } => {
+            let packet = ClientBoundPacket::ConfirmTransaction {
+                window_id,
+                action,
+                accepted: accepted as u8,
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::BlockBreakAnimation {
+            player,
+            entity_id,
+            position,
+            stage,
+        } => {
+            let packet = ClientBoundPacket::BlockBreakAnimation {
+                entity_id: VarInt::new(entity_id),
+                location: position.into(),
+                destroy_stage: stage,
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::Effect {
+            player,
+            effect,
+            position,
+            data,
+        } => {
+            let packet = ClientBoundPacket::Effect {
+                effect_id: effect,
+                location: position.into(),
+                data,
+                disable_relative_volume: 0,
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::UpdateHealth {
+            player,
+            health,
+            food,
+            saturation,
+        } => {
+            let packet = ClientBoundPacket::UpdateHealth {
+                health,
+                food: VarInt::new(food),
+                food_saturation: saturation,
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::Respawn {
+            player,
+            dimension,
+            difficulty,
+            gamemode,
+            level_type,
+        } => {
+            let packet = ClientBoundPacket::Respawn {
+                dimension,
+                difficulty,
+                gamemode,
+                level_type,
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::EntityStatus {
+            player,
+            entity_id,
+            status,
+        } => {
+            let packet = ClientBoundPacket::EntityStatus {
+                entity_id,
+                entity_status: match status {
+                    EntityStatus::Hurt => 2,
+                    EntityStatus::Dead => 3,
+                },
+            };
+            send(ctx, player, packet).await?;
+        }
+        ProxyBoundPacket::ChangeGameState { player, change } => {
+            let (reason, value) = match change {
+                GameStateChange::BeginRaining => (1, 0.0),
+                GameStateChange::EndRaining => (2, 0.0),
+                GameStateChange::ChangeGamemode(gamemode) => {
+                    let value = match gamemode {
+                        Gamemode::Survival => 0.0,
+                        Gamemode::Creative => 1.0,
+                        Gamemode::Adventure => 2.0,
+                        Gamemode::Spectator => 3.0,
+                    };
+                    (3, value)
+                }
+                GameStateChange::EnterCredits => (4, 0.0),
+                GameStateChange::DemoMessage(message) => {
+                    let value = match message {
+                        DemoMessage::Welcome => 0.0,
+                        DemoMessage::MovementControls => 101.0,
+                        DemoMessage::JumpControl => 102.0,
+                        DemoMessage::InventoryControl => 103.0,
+                    };
+                    (5, value)
+                }
+                GameStateChange::RainLevel(level) => (7, level),
+                GameStateChange::ThunderLevel(level) => (8, level),
+            };
+            let packet = ClientBoundPacket::ChangeGameState { reason, value };
+            send(ctx, player, packet).await?;
+        }
     };
     Ok(())
 }
+
+async fn send(
+    ctx: &ProxyContext,
+    player: u128,
+    packet: ClientBoundPacket,
+) -> Result<(), Box<dyn Error>> {
+    ctx.players_tx
+        .send(crate::players::Message::Send(
+            player,
+            packet.serialize().to_vec(),
+        ))
+        .await?;
+    Ok(())
+}
+
+/// Sends packets to a player in a single write.
+async fn send_all(
+    ctx: &ProxyContext,
+    player: u128,
+    packets: impl IntoIterator<Item = ClientBoundPacket>,
+) -> Result<(), Box<dyn Error>> {
+    let mut buf = BytesMut::new();
+    for packet in packets {
+        buf.extend_from_slice(&packet.serialize());
+    }
+    if !buf.is_empty() {
+        ctx.players_tx
+            .send(crate::players::Message::Send(player, buf.to_vec()))
+            .await?;
+    }
+    Ok(())
+}
@@ -1 +1,2 @@
-pub mod v1_8_8;
+// One module per protocol description in `data/`, generated by `build.rs`.
+include!(concat!(env!("OUT_DIR"), "/versions.rs"));
@@ -0,0 +1,65 @@
+#[path = "../codegen.rs"]
+mod codegen;
+
+use std::collections::BTreeMap;
+
+use codegen::Protocol;
+
+fn generate(play: &str) -> Result<String, String> {
+    let json = format!(
+        r#"{{ "version": {{ "minecraftVersion": "1.0", "version": 1 }}, "play": {play} }}"#
+    );
+    let protocol: Protocol = serde_json::from_str(&json).expect("protocol should parse");
+    let versions = BTreeMap::from([(protocol.version.minecraft_version.clone(), protocol)]);
+    codegen::generate(&versions).map_err(|e| e.to_string())
+}
+
+#[test]
+fn generates_packets() {
+    let out = generate(
+        r#"{ "toClient": [
+            { "name": "KeepAlive", "id": "0x00", "fields": [{ "name": "id", "type": "varint" }] },
+            { "name": "JoinGame", "id": "0x01", "fields": [{ "name": "gamemode", "type": "gamemode" }] }
+        ] }"#,
+    )
+    .unwrap();
+    assert!(out.contains("KeepAlive, Play, 0x00 => {\n            id: VarInt,"));
+    assert!(out.contains("JoinGame, Play, 0x01 => {\n            gamemode: Gamemode,"));
+}
+
+#[test]
+fn rejects_duplicate_ids() {
+    let err = generate(
+        r#"{ "toClient": [
+            { "name": "KeepAlive", "id": "0x00", "fields": [] },
+            { "name": "JoinGame", "id": "0", "fields": [] }
+        ] }"#,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        "1.0: JoinGame and KeepAlive share id 0x00 in state play"
+    );
+}
+
+#[test]
+fn allows_the_same_id_in_both_directions() {
+    let out = generate(
+        r#"{
+            "toClient": [{ "name": "KeepAlive", "id": "0x00", "fields": [] }],
+            "toServer": [{ "name": "KeepAliveResponse", "id": "0x00", "fields": [] }]
+        }"#,
+    );
+    assert!(out.is_ok());
+}
+
+#[test]
+fn rejects_unknown_types() {
+    let err = generate(
+        r#"{ "toClient": [
+            { "name": "KeepAlive", "id": "0x00", "fields": [{ "name": "id", "type": "VarInt" }] }
+        ] }"#,
+    )
+    .unwrap_err();
+    assert_eq!(err, "1.0: unknown type VarInt for KeepAlive.id");
+}
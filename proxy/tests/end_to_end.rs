@@ -0,0 +1,1398 @@
+mod harness;
+
+use std::{collections::HashSet, sync::Arc};
+
+use makar_proxy::{
+    protocol::{
+        chunk::decode_column, ChatPosition, DisplaySlot, Gamemode, ObjectiveAction,
+        PlayerListAction, Position, Slot, TeamAction, TitleAction, UseEntityAction, VarInt,
+    },
+    versions::v1_8_8::*,
+};
+
+use makar_protocol::{
+    Chat, ItemMeta, ItemStack, Metadata, MetadataValue, NameTagVisibility, ObjectiveKind, TeamInfo,
+    WindowKind,
+};
+use makar_server::{
+    boss_bar::BossBar,
+    game::DEFAULT_WORLD,
+    inventory::Window,
+    menu::{Menu, MenuItem},
+    title::Title,
+    world::{
+        generator::{FlatGenerator, NoiseGenerator, VoidGenerator},
+        Block, Dimension, Weather, World,
+    },
+    ServerConfig,
+};
+
+use harness::{Harness, TestClient};
+
+async fn settings(client: &mut TestClient, locale: &str) {
+    client
+        .send(ProxyBoundPacket::ClientSettings {
+            locale: locale.to_string(),
+            view_distance: 8,
+            chat_mode: 0,
+            chat_colors: 1,
+            displayed_skin_parts: 0x7F,
+        })
+        .await;
+}
+
+async fn chat(client: &mut TestClient) -> String {
+    client
+        .expect(|packet| match packet {
+            ClientBoundPacket::ChatMessage { json, .. } => Some(json.text),
+            _ => None,
+        })
+        .await
+}
+
+async fn title(client: &mut TestClient) -> String {
+    client
+        .expect(|packet| match packet {
+            ClientBoundPacket::Title {
+                action: TitleAction::SetTitle(chat),
+            } => Some(chat.text),
+            _ => None,
+        })
+        .await
+}
+
+/// Clicks a slot with a button, numbering the action and telling how, along
+/// with the item the client thinks was there.
+async fn click(
+    client: &mut TestClient,
+    window_id: u8,
+    (slot, button, action, mode): (i16, u8, i16, u8),
+    item: Option<ItemStack>,
+) {
+    client
+        .send(ProxyBoundPacket::ClickWindow {
+            window_id,
+            slot,
+            button,
+            action,
+            mode,
+            item: Slot(item),
+        })
+        .await;
+}
+
+/// Confirms the teleport to the spawn point like clients do, returning
+/// where it was.
+async fn confirm_spawn(client: &mut TestClient) -> (f64, f64, f64) {
+    let (x, y, z) = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::PlayerPositionAndLook { x, y, z, .. } => Some((x, y, z)),
+            _ => None,
+        })
+        .await;
+    client
+        .send(ProxyBoundPacket::PlayerPositionAndLook {
+            x,
+            y,
+            z,
+            yaw: 0.0,
+            pitch: 0.0,
+            on_ground: 1,
+        })
+        .await;
+    (x, y, z)
+}
+
+/// Waits until the server ran at least `ticks` ticks, going by the world
+/// age of the time updates it sends every second.
+async fn wait_ticks(client: &mut TestClient, ticks: i64) {
+    let age = |packet| match packet {
+        ClientBoundPacket::TimeUpdate { world_age, .. } => Some(world_age),
+        _ => None,
+    };
+    let start = client.expect(age).await;
+    while client.expect(age).await < start + ticks {}
+}
+
+/// Decodes the block right below `spawn` from the next chunk bulk.
+async fn block_below(client: &mut TestClient, spawn: Position) -> u16 {
+    let bulk = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::MapChunkBulk { bulk } => Some(bulk),
+            _ => None,
+        })
+        .await;
+    assert!(bulk.sky_light);
+    let spawn_chunk = bulk
+        .columns
+        .iter()
+        .find(|column| (column.x, column.z) == (spawn.x >> 4, spawn.z >> 4))
+        .expect("spawn chunk should be sent first");
+    let column = decode_column(
+        spawn_chunk.x,
+        spawn_chunk.z,
+        spawn_chunk.primary_bit_mask,
+        bulk.sky_light,
+        &spawn_chunk.data,
+    )
+    .expect("spawn chunk should decode");
+    let y = spawn.y - 1;
+    let ground = column.sections[(y >> 4) as usize]
+        .as_ref()
+        .expect("ground should be sent");
+    ground.blocks[((y & 0xF) << 8 | (spawn.z & 0xF) << 4 | spawn.x & 0xF) as usize]
+}
+
+#[tokio::test]
+async fn join_game() {
+    let harness = Harness::start().await;
+    let mut client = harness.connect("alice").await;
+
+    let (gamemode, dimension) = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::JoinGame {
+                gamemode,
+                dimension,
+                ..
+            } => Some((gamemode, dimension)),
+            _ => None,
+        })
+        .await;
+    assert!(matches!(gamemode, Gamemode::Survival));
+    assert_eq!(dimension, 0);
+}
+
+#[tokio::test]
+async fn players_have_their_own_entity() {
+    let harness = Harness::start().await;
+    let mut ids = Vec::new();
+    for username in ["alice", "bob"] {
+        let mut client = harness.connect(username).await;
+        ids.push(
+            client
+                .expect(|packet| match packet {
+                    ClientBoundPacket::JoinGame { entity_id, .. } => Some(entity_id),
+                    _ => None,
+                })
+                .await,
+        );
+    }
+    assert_ne!(ids[0], ids[1]);
+}
+
+#[tokio::test]
+async fn join_sequence() {
+    let harness = Harness::start().await;
+    let mut client = harness.connect("alice").await;
+
+    let spawn = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::SpawnPosition { location } => Some(location),
+            _ => None,
+        })
+        .await;
+    client
+        .expect(|packet| match packet {
+            ClientBoundPacket::PlayerAbilities { .. } => Some(()),
+            _ => None,
+        })
+        .await;
+    client
+        .expect(|packet| match packet {
+            ClientBoundPacket::HeldItemChange { slot } => Some(slot),
+            _ => None,
+        })
+        .await;
+    assert_eq!(block_below(&mut client, spawn).await >> 4, 2);
+
+    let (x, y, z) = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::PlayerPositionAndLook { x, y, z, .. } => Some((x, y, z)),
+            _ => None,
+        })
+        .await;
+    assert_eq!(
+        (x.floor() as i32, y as i32, z.floor() as i32),
+        (spawn.x, spawn.y, spawn.z)
+    );
+    client
+        .expect(|packet| match packet {
+            ClientBoundPacket::TimeUpdate { .. } => Some(()),
+            _ => None,
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn serves_saved_world() {
+    let directory = std::env::temp_dir().join(format!("makar-world-{}", std::process::id()));
+    let mut world = World::open(&directory, Dimension::Overworld, Arc::new(VoidGenerator));
+    let chunk = world.load_chunk(0, 0);
+    chunk.set_block(8, 3, 8, Block::new(1, 2));
+    world.save();
+    world.flush();
+
+    let harness = Harness::start_with(ServerConfig {
+        world: Some(directory.clone()),
+        ..Default::default()
+    })
+    .await;
+    let mut client = harness.connect("alice").await;
+    let spawn = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::SpawnPosition { location } => Some(location),
+            _ => None,
+        })
+        .await;
+    let block = block_below(&mut client, spawn).await;
+    std::fs::remove_dir_all(&directory).expect("world should be removed");
+    assert_eq!(block, 1 << 4 | 2);
+}
+
+#[tokio::test]
+async fn saves_world_once_stopped() {
+    let directory = std::env::temp_dir().join(format!("makar-stopped-{}", std::process::id()));
+    let server = makar_server::run(ServerConfig {
+        address: "127.0.0.1:0".to_string(),
+        world: Some(directory.clone()),
+        generator: Arc::new(VoidGenerator),
+        setup: Arc::new(|game| {
+            if let Some(world) = game.world_mut(DEFAULT_WORLD) {
+                world.load_chunk(0, 0).set_block(8, 3, 8, Block::new(1, 2));
+            }
+        }),
+        ..Default::default()
+    })
+    .await
+    .expect("server should start");
+    // a proxy that stays connected mustn't keep the game running
+    let _proxy = tokio::net::TcpStream::connect(server.address())
+        .await
+        .expect("server should accept");
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    drop(server);
+
+    let mut saved = false;
+    for _ in 0..50 {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let mut world = World::open(&directory, Dimension::Overworld, Arc::new(VoidGenerator));
+        if world.load_chunk(0, 0).get_block(8, 3, 8) == Block::new(1, 2) {
+            saved = true;
+            break;
+        }
+    }
+    std::fs::remove_dir_all(&directory).expect("world should be removed");
+    assert!(saved);
+}
+
+#[tokio::test]
+async fn spawns_on_generated_terrain() {
+    let harness = Harness::start_with(ServerConfig {
+        generator: Arc::new(NoiseGenerator::new(42)),
+        ..Default::default()
+    })
+    .await;
+    let mut client = harness.connect("alice").await;
+    let spawn = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::SpawnPosition { location } => Some(location),
+            _ => None,
+        })
+        .await;
+    assert!(spawn.y > 8);
+    assert_ne!(block_below(&mut client, spawn).await >> 4, 0);
+}
+
+#[tokio::test]
+async fn chunks_follow_the_player() {
+    let harness = Harness::start().await;
+    let mut client = harness.connect("alice").await;
+    settings(&mut client, "en_US").await;
+    let (mut x, y, z) = confirm_spawn(&mut client).await;
+
+    // walking far enough for every chunk sent so far to leave the view
+    while x < 16.0 * 40.0 {
+        x = (x + 8.0).min(16.0 * 40.0);
+        client
+            .send(ProxyBoundPacket::PlayerPosition {
+                x,
+                y,
+                z,
+                on_ground: 1,
+            })
+            .await;
+    }
+    let (x, z) = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::ChunkData {
+                x,
+                z,
+                primary_bit_mask: 0,
+                ..
+            } => Some((x, z)),
+            _ => None,
+        })
+        .await;
+    assert!(x.abs() <= 10 && z.abs() <= 10);
+
+    let columns = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::MapChunkBulk { bulk } if bulk.columns[0].x > 10 => {
+                Some(bulk.columns)
+            }
+            _ => None,
+        })
+        .await;
+    assert_eq!((columns[0].x, columns[0].z), (40, 0));
+    assert!(columns.iter().all(|column| column.x.abs_diff(40) <= 8));
+}
+
+#[tokio::test]
+async fn moving_too_quickly_is_rejected() {
+    let harness = Harness::start().await;
+    let mut client = harness.connect("alice").await;
+    let spawn = confirm_spawn(&mut client).await;
+
+    client
+        .send(ProxyBoundPacket::PlayerPosition {
+            x: spawn.0 + 100.0,
+            y: spawn.1,
+            z: spawn.2,
+            on_ground: 1,
+        })
+        .await;
+    let position = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::PlayerPositionAndLook { x, y, z, .. } => Some((x, y, z)),
+            _ => None,
+        })
+        .await;
+    assert_eq!(position, spawn);
+}
+
+#[tokio::test]
+async fn players_see_each_other() {
+    let harness = Harness::start().await;
+    let mut alice = harness.connect("alice").await;
+    let (x, y, z) = confirm_spawn(&mut alice).await;
+    let mut bob = harness.connect("bob").await;
+
+    let uuid = bob
+        .expect(|packet| match packet {
+            ClientBoundPacket::PlayerListItem {
+                action: PlayerListAction::Add(entries),
+            } => entries
+                .into_iter()
+                .find(|entry| entry.name == "alice")
+                .map(|entry| entry.uuid),
+            _ => None,
+        })
+        .await;
+    let entity = bob
+        .expect(|packet| match packet {
+            ClientBoundPacket::SpawnPlayer {
+                entity_id, uuid: u, ..
+            } if u == uuid => Some(entity_id),
+            _ => None,
+        })
+        .await;
+
+    alice
+        .send(ProxyBoundPacket::PlayerPosition {
+            x: x + 1.0,
+            y,
+            z,
+            on_ground: 1,
+        })
+        .await;
+    let delta = bob
+        .expect(|packet| match packet {
+            ClientBoundPacket::EntityRelativeMove {
+                entity_id, delta_x, ..
+            } if entity_id == entity => Some(delta_x),
+            _ => None,
+        })
+        .await;
+    assert_eq!(delta, 32);
+
+    drop(alice);
+    bob.expect(|packet| match packet {
+        ClientBoundPacket::PlayerListItem {
+            action: PlayerListAction::Remove(uuids),
+        } if uuids == [uuid] => Some(()),
+        _ => None,
+    })
+    .await;
+    bob.expect(|packet| match packet {
+        ClientBoundPacket::DestroyEntities { entity_ids } if entity_ids.0 == [entity.value()] => {
+            Some(())
+        }
+        _ => None,
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn proxies_share_one_game() {
+    let mut harness = Harness::start().await;
+    let second = harness.add_proxy().await;
+    let mut alice = harness.connect("alice").await;
+    confirm_spawn(&mut alice).await;
+    let mut bob = harness.connect_through(second, "bob").await;
+
+    bob.expect(|packet| match packet {
+        ClientBoundPacket::PlayerListItem {
+            action: PlayerListAction::Add(entries),
+        } => entries.into_iter().find(|entry| entry.name == "alice"),
+        _ => None,
+    })
+    .await;
+    alice
+        .expect(|packet| match packet {
+            ClientBoundPacket::PlayerListItem {
+                action: PlayerListAction::Add(entries),
+            } => entries.into_iter().find(|entry| entry.name == "bob"),
+            _ => None,
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn tab_list_is_customizable() {
+    let harness = Harness::start_with(ServerConfig {
+        setup: Arc::new(|game| {
+            let tab_list = game.tab_list();
+            tab_list.set_header(Some(Chat {
+                text: "makar".to_string(),
+                ..Default::default()
+            }));
+            let uuid = tab_list.add_fake("~spacer", None, -1);
+            tab_list.set_team(uuid, Some("zz"));
+        }),
+        ..Default::default()
+    })
+    .await;
+    let mut client = harness.connect("alice").await;
+
+    let names = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::PlayerListItem {
+                action: PlayerListAction::Add(entries),
+            } => Some(
+                entries
+                    .into_iter()
+                    .map(|entry| entry.name)
+                    .collect::<Vec<_>>(),
+            ),
+            _ => None,
+        })
+        .await;
+    assert!(names.contains(&"alice".to_string()));
+    assert!(names.contains(&"~spacer".to_string()));
+    let members = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::Teams {
+                name,
+                action: TeamAction::Create { members, .. },
+            } if name == "zz" => Some(members),
+            _ => None,
+        })
+        .await;
+    assert_eq!(members, ["~spacer"]);
+    let header = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::PlayerListHeaderFooter { header, .. } => Some(header.text),
+            _ => None,
+        })
+        .await;
+    assert_eq!(header, "makar");
+}
+
+#[tokio::test]
+async fn scoreboard_is_synced() {
+    let harness = Harness::start_with(ServerConfig {
+        setup: Arc::new(|game| {
+            let scoreboard = game.scoreboard();
+            scoreboard
+                .add_objective("kills", "Kills", ObjectiveKind::Integer)
+                .set_score("alice", 3);
+            scoreboard.display(DisplaySlot::Sidebar, Some("kills"));
+            scoreboard.add_team(
+                "red",
+                TeamInfo {
+                    display_name: "Red".to_string(),
+                    prefix: "[red] ".to_string(),
+                    suffix: String::new(),
+                    friendly_fire: false,
+                    see_invisible_teammates: true,
+                    name_tag_visibility: NameTagVisibility::Always,
+                    color: Some("red".to_string()),
+                },
+            );
+            scoreboard.set_team("alice", Some("red"));
+            // scored a few ticks after alice joined, however long that took
+            let mut scored = false;
+            game.scheduler().run_repeating(1, 1, move |game| {
+                if game.players().is_empty() || std::mem::replace(&mut scored, true) {
+                    return;
+                }
+                game.scheduler().run_later(5, |game| {
+                    if let Some(kills) = game.scoreboard().objective_mut("kills") {
+                        kills.add_score("alice", 1);
+                    }
+                });
+            });
+        }),
+        ..Default::default()
+    })
+    .await;
+    let mut client = harness.connect("alice").await;
+
+    let members = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::Teams {
+                name,
+                action: TeamAction::Create { members, .. },
+            } if name == "red" => Some(members),
+            _ => None,
+        })
+        .await;
+    assert_eq!(members, ["alice"]);
+    client
+        .expect(|packet| match packet {
+            ClientBoundPacket::ScoreboardObjective {
+                name,
+                action: ObjectiveAction::Create { .. },
+            } if name == "kills" => Some(()),
+            _ => None,
+        })
+        .await;
+    let score = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::UpdateScore { score } => Some(score),
+            _ => None,
+        })
+        .await;
+    assert_eq!((score.entry.as_str(), score.value), ("alice", Some(3)));
+    let objective = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::DisplayScoreboard {
+                slot: DisplaySlot::Sidebar,
+                objective,
+            } => Some(objective),
+            _ => None,
+        })
+        .await;
+    assert_eq!(objective, "kills");
+    let score = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::UpdateScore { score } => Some(score),
+            _ => None,
+        })
+        .await;
+    assert_eq!(score.value, Some(4));
+}
+
+#[tokio::test]
+async fn action_bar_and_boss_bar() {
+    let harness = Harness::start_with(ServerConfig {
+        setup: Arc::new(|game| {
+            let mut shown = HashSet::new();
+            game.scheduler().run_repeating(1, 1, move |game| {
+                let players: Vec<_> = game.players().keys().copied().collect();
+                for player in players {
+                    if !shown.insert(player) {
+                        continue;
+                    }
+                    let message = Chat {
+                        text: "3 kills".to_string(),
+                        ..Default::default()
+                    };
+                    game.set_action_bar(player, Some(message));
+                    let bar = BossBar {
+                        text: "Wave 1".to_string(),
+                        progress: 0.5,
+                    };
+                    game.set_boss_bar(player, Some(bar));
+                }
+            });
+        }),
+        ..Default::default()
+    })
+    .await;
+    let mut client = harness.connect("alice").await;
+
+    let text = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::ChatMessage {
+                json,
+                position: ChatPosition::ActionBar,
+            } => Some(json.text),
+            _ => None,
+        })
+        .await;
+    assert_eq!(text, "3 kills");
+    let metadata = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::SpawnMob {
+                kind: 64, metadata, ..
+            } => Some(metadata.0),
+            _ => None,
+        })
+        .await;
+    assert!(metadata.contains(&Metadata {
+        index: 2,
+        value: MetadataValue::String("Wave 1".to_string()),
+    }));
+    assert!(metadata.contains(&Metadata {
+        index: 6,
+        value: MetadataValue::Float(150.0),
+    }));
+    // kept messages are sent again before they fade out
+    let text = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::ChatMessage {
+                json,
+                position: ChatPosition::ActionBar,
+            } => Some(json.text),
+            _ => None,
+        })
+        .await;
+    assert_eq!(text, "3 kills");
+}
+
+#[tokio::test]
+async fn titles_are_queued() {
+    let harness = Harness::start_with(ServerConfig {
+        setup: Arc::new(|game| {
+            let mut shown = HashSet::new();
+            game.scheduler().run_repeating(1, 1, move |game| {
+                let players: Vec<_> = game.players().keys().copied().collect();
+                for player in players {
+                    if !shown.insert(player) {
+                        continue;
+                    }
+                    let text = |text: &str| Chat {
+                        text: text.to_string(),
+                        ..Default::default()
+                    };
+                    let title = Title {
+                        title: text("Round 1"),
+                        subtitle: Some(text("Fight!")),
+                        fade_in: 5,
+                        stay: 20,
+                        fade_out: 5,
+                    };
+                    game.show_title(player, title);
+                    game.countdown(player, 2, |second| Title {
+                        title: text(&second.to_string()),
+                        ..Default::default()
+                    });
+                }
+            });
+        }),
+        ..Default::default()
+    })
+    .await;
+    let mut client = harness.connect("alice").await;
+
+    let mut actions = Vec::new();
+    while actions.len() < 7 {
+        let action = client
+            .expect(|packet| match packet {
+                ClientBoundPacket::Title { action } => Some(action),
+                _ => None,
+            })
+            .await;
+        actions.push(match action {
+            TitleAction::SetTitle(chat) => format!("title {}", chat.text),
+            TitleAction::SetSubtitle(chat) => format!("subtitle {}", chat.text),
+            TitleAction::SetTimes {
+                fade_in,
+                stay,
+                fade_out,
+            } => format!("times {fade_in} {stay} {fade_out}"),
+            action => format!("{action:?}"),
+        });
+    }
+    // clients keep the times and subtitle between titles
+    assert_eq!(
+        actions,
+        [
+            "times 5 20 5",
+            "subtitle Fight!",
+            "title Round 1",
+            "times 0 20 0",
+            "subtitle ",
+            "title 2",
+            "times 0 20 20",
+        ]
+    );
+    assert_eq!(title(&mut client).await, "1");
+}
+
+#[tokio::test]
+async fn inventory_clicks() {
+    let stone = |count| ItemStack {
+        id: 1,
+        count,
+        damage: 0,
+        meta: ItemMeta::default(),
+    };
+    let harness = Harness::start_with(ServerConfig {
+        setup: Arc::new(move |game| {
+            let mut shown = HashSet::new();
+            game.scheduler().run_repeating(1, 1, move |game| {
+                let players: Vec<_> = game.players().keys().copied().collect();
+                for player in players {
+                    if !shown.insert(player) {
+                        continue;
+                    }
+                    if let Some(inventory) = game.inventory(player) {
+                        inventory.add(stone(64));
+                    }
+                    let title = Chat {
+                        text: "Chest".to_string(),
+                        ..Default::default()
+                    };
+                    game.open_window(player, Window::new(WindowKind::Chest(3), title));
+                }
+            });
+        }),
+        ..Default::default()
+    })
+    .await;
+    let mut client = harness.connect("alice").await;
+
+    let (window_id, kind) = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::OpenWindow {
+                window_id,
+                kind,
+                slot_count: 27,
+                ..
+            } => Some((window_id, kind)),
+            _ => None,
+        })
+        .await;
+    assert_eq!(kind, "minecraft:chest");
+    let items = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::WindowItems { items, .. } => Some(items.0),
+            _ => None,
+        })
+        .await;
+    // the chest, then the inventory, the hotbar last
+    assert_eq!(items.len(), 27 + 36);
+    assert_eq!(items[54], Some(stone(64)));
+
+    // picks up the stack, then puts one in the chest
+    click(&mut client, window_id, (54, 0, 1, 0), Some(stone(64))).await;
+    click(&mut client, window_id, (0, 1, 2, 0), None).await;
+    let mut accepted = Vec::new();
+    while accepted.len() < 2 {
+        accepted.push(
+            client
+                .expect(|packet| match packet {
+                    ClientBoundPacket::ConfirmTransaction {
+                        action, accepted, ..
+                    } => Some((action, accepted)),
+                    _ => None,
+                })
+                .await,
+        );
+    }
+    assert_eq!(accepted, [(1, 1), (2, 1)]);
+
+    // middle clicks do nothing in survival, the client thinking otherwise
+    click(&mut client, window_id, (5, 2, 3, 3), Some(stone(1))).await;
+    let (action, accepted) = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::ConfirmTransaction {
+                action, accepted, ..
+            } => Some((action, accepted)),
+            _ => None,
+        })
+        .await;
+    assert_eq!((action, accepted), (3, 0));
+    let items = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::WindowItems { items, .. } => Some(items.0),
+            _ => None,
+        })
+        .await;
+    assert_eq!((&items[0], &items[54]), (&Some(stone(1)), &None));
+    client
+        .send(ProxyBoundPacket::ConfirmTransaction {
+            window_id,
+            action: 3,
+            accepted: 1,
+        })
+        .await;
+
+    // shift clicking moves the stone to the end of the hotbar
+    click(&mut client, window_id, (0, 0, 4, 1), Some(stone(1))).await;
+    let slot = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::SetSlot {
+                slot,
+                item: Slot(Some(item)),
+                ..
+            } if slot >= 0 => Some((slot, item)),
+            _ => None,
+        })
+        .await;
+    assert_eq!(slot, (62, stone(1)));
+
+    // closing the chest drops the stone held
+    client
+        .send(ProxyBoundPacket::CloseWindow { window_id })
+        .await;
+    client
+        .expect(|packet| match packet {
+            ClientBoundPacket::SpawnObject { kind: 2, .. } => Some(()),
+            _ => None,
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn menus_keep_their_items() {
+    let harness = Harness::start_with(ServerConfig {
+        setup: Arc::new(|game| {
+            let mut shown = HashSet::new();
+            game.scheduler().run_repeating(1, 1, move |game| {
+                let players: Vec<_> = game.players().keys().copied().collect();
+                for player in players {
+                    if !shown.insert(player) {
+                        continue;
+                    }
+                    let title = Chat {
+                        text: "Shop".to_string(),
+                        ..Default::default()
+                    };
+                    let mut menu = Menu::new(title, 2);
+                    for i in 0..20 {
+                        let item = MenuItem::new(264)
+                            .name(format!("§bDiamond {i}"))
+                            .lore(["§7Click to buy"])
+                            .on_click(|game, click| {
+                                let bought = MenuItem::new(265).name("Bought");
+                                if let Some(menu) = game.menu(click.player) {
+                                    menu.set(click.index, Some(bought));
+                                }
+                            });
+                        menu.push(if i == 0 { item.glint() } else { item });
+                    }
+                    game.open_menu(player, menu);
+                }
+            });
+        }),
+        ..Default::default()
+    })
+    .await;
+    let mut client = harness.connect("alice").await;
+
+    let window_id = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::OpenWindow {
+                window_id,
+                slot_count: 18,
+                ..
+            } => Some(window_id),
+            _ => None,
+        })
+        .await;
+    let items = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::WindowItems { items, .. } => Some(items.0),
+            _ => None,
+        })
+        .await;
+    let diamond = ItemStack {
+        id: 264,
+        count: 1,
+        damage: 0,
+        meta: ItemMeta {
+            name: Some("§bDiamond 0".to_string()),
+            lore: vec!["§7Click to buy".to_string()],
+            glint: true,
+        },
+    };
+    assert_eq!(items[0], Some(diamond.clone()));
+    // the bottom row holds the arrow to the next page
+    assert_eq!(items[9], None);
+    let next = items[17]
+        .clone()
+        .expect("the next page should have an arrow");
+    assert_eq!(next.meta.name.as_deref(), Some("Next page"));
+
+    // the client takes the diamond, but the menu keeps it
+    click(&mut client, window_id, (0, 0, 1, 0), Some(diamond.clone())).await;
+    let (action, accepted) = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::ConfirmTransaction {
+                action, accepted, ..
+            } => Some((action, accepted)),
+            _ => None,
+        })
+        .await;
+    assert_eq!((action, accepted), (1, 0));
+    let items = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::WindowItems { items, .. } => Some(items.0),
+            _ => None,
+        })
+        .await;
+    assert_eq!(items[0], Some(diamond));
+    // then the item clicked changes
+    let bought = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::SetSlot {
+                slot: 0,
+                item: Slot(Some(item)),
+                ..
+            } => Some(item),
+            _ => None,
+        })
+        .await;
+    assert_eq!(bought.id, 265);
+    client
+        .send(ProxyBoundPacket::ConfirmTransaction {
+            window_id,
+            action: 1,
+            accepted: 1,
+        })
+        .await;
+
+    // the arrow turns the page
+    click(&mut client, window_id, (17, 0, 2, 0), Some(next)).await;
+    let previous = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::SetSlot {
+                slot: 9,
+                item: Slot(Some(item)),
+                ..
+            } => Some(item),
+            _ => None,
+        })
+        .await;
+    assert_eq!(previous.meta.name.as_deref(), Some("Previous page"));
+}
+
+#[tokio::test]
+async fn blocks_are_placed_and_broken() {
+    let torches = ItemStack {
+        id: 50,
+        count: 2,
+        damage: 0,
+        meta: ItemMeta::default(),
+    };
+    let given = torches.clone();
+    let harness = Harness::start_with(ServerConfig {
+        setup: Arc::new(move |game| {
+            let given = given.clone();
+            let mut shown = HashSet::new();
+            game.scheduler().run_repeating(1, 1, move |game| {
+                let players: Vec<_> = game.players().keys().copied().collect();
+                for player in players {
+                    if let (true, Some(inventory)) = (shown.insert(player), game.inventory(player))
+                    {
+                        inventory.add(given.clone());
+                    }
+                }
+            });
+        }),
+        ..Default::default()
+    })
+    .await;
+    let mut client = harness.connect("alice").await;
+    let (x, y, z) = confirm_spawn(&mut client).await;
+    let held = |packet| match packet {
+        ClientBoundPacket::SetSlot {
+            slot: 36,
+            item: Slot(item),
+            ..
+        } => Some(item.map(|item| item.count)),
+        _ => None,
+    };
+    assert_eq!(client.expect(held).await, Some(2));
+    let ground = Position {
+        x: x.floor() as i32 + 2,
+        y: y as i32 - 1,
+        z: z.floor() as i32,
+    };
+    let torch = Position {
+        y: ground.y + 1,
+        ..ground
+    };
+    let block_change = |position: Position| {
+        move |packet| match packet {
+            ClientBoundPacket::BlockChange { location, block } if location == position => {
+                Some(block.value())
+            }
+            _ => None,
+        }
+    };
+
+    // torches stand on the top of the grass
+    client
+        .send(ProxyBoundPacket::PlayerBlockPlacement {
+            location: ground,
+            face: 1,
+            held_item: Slot(Some(torches.clone())),
+            cursor_x: 8,
+            cursor_y: 16,
+            cursor_z: 8,
+        })
+        .await;
+    assert_eq!(client.expect(held).await, Some(1));
+    assert_eq!(client.expect(block_change(torch)).await, 50 << 4 | 5);
+
+    // but not on the side of the torch
+    client
+        .send(ProxyBoundPacket::PlayerBlockPlacement {
+            location: torch,
+            face: 5,
+            held_item: Slot(Some(torches)),
+            cursor_x: 16,
+            cursor_y: 8,
+            cursor_z: 8,
+        })
+        .await;
+    let east = Position {
+        x: torch.x + 1,
+        ..torch
+    };
+    assert_eq!(client.expect(block_change(east)).await, 0);
+
+    // torches break in a single hit, dropping themselves
+    client
+        .send(ProxyBoundPacket::PlayerDigging {
+            status: 0,
+            location: torch,
+            face: 1,
+        })
+        .await;
+    assert_eq!(client.expect(block_change(torch)).await, 0);
+    let item = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::SpawnObject {
+                entity_id, kind: 2, ..
+            } => Some(entity_id),
+            _ => None,
+        })
+        .await;
+
+    // and are picked up by walking over them
+    client
+        .send(ProxyBoundPacket::PlayerPosition {
+            x: torch.x as f64 + 0.5,
+            y,
+            z,
+            on_ground: 1,
+        })
+        .await;
+    let collected = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::CollectItem {
+                collected_entity_id,
+                ..
+            } => Some(collected_entity_id),
+            _ => None,
+        })
+        .await;
+    assert_eq!(collected, item);
+    assert_eq!(client.expect(held).await, Some(2));
+}
+
+#[tokio::test]
+async fn players_fight_and_respawn() {
+    let harness = Harness::start_with(ServerConfig {
+        setup: Arc::new(|game| {
+            let mut shown = HashSet::new();
+            game.scheduler().run_repeating(1, 1, move |game| {
+                let players: Vec<_> = game.players().keys().copied().collect();
+                for player in players {
+                    let bob = game.players()[&player].username() == "bob";
+                    if let (true, true, Some(inventory)) =
+                        (bob, shown.insert(player), game.inventory(player))
+                    {
+                        inventory.add(ItemStack {
+                            id: 276,
+                            count: 1,
+                            damage: 0,
+                            meta: ItemMeta::default(),
+                        });
+                    }
+                }
+            });
+        }),
+        ..Default::default()
+    })
+    .await;
+    let mut alice = harness.connect("alice").await;
+    confirm_spawn(&mut alice).await;
+    let health = |packet| match packet {
+        ClientBoundPacket::UpdateHealth { health, .. } => Some(health),
+        _ => None,
+    };
+    assert_eq!(alice.expect(health).await, 20.0);
+    let mut bob = harness.connect("bob").await;
+    bob.expect(|packet| match packet {
+        ClientBoundPacket::SetSlot {
+            slot: 36,
+            item: Slot(Some(_)),
+            ..
+        } => Some(()),
+        _ => None,
+    })
+    .await;
+    let entity = bob
+        .expect(|packet| match packet {
+            ClientBoundPacket::SpawnPlayer { entity_id, .. } => Some(entity_id.value()),
+            _ => None,
+        })
+        .await;
+
+    // diamond swords take off 8 health, once per 10 ticks of invulnerability
+    for (i, left) in [12.0, 4.0, 0.0].into_iter().enumerate() {
+        // both keep reading, the proxy not reading from clients it's
+        // stuck writing to
+        if i > 0 {
+            tokio::join!(wait_ticks(&mut alice, 10), wait_ticks(&mut bob, 10));
+        }
+        bob.send(ProxyBoundPacket::UseEntity {
+            target: VarInt::new(entity),
+            action: UseEntityAction::Attack,
+        })
+        .await;
+        // knocked back straight up, bob standing where alice is
+        let knockback = alice
+            .expect(|packet| match packet {
+                ClientBoundPacket::EntityVelocity {
+                    entity_id,
+                    velocity_x,
+                    velocity_y,
+                    ..
+                } if entity_id.value() == entity => Some((velocity_x, velocity_y)),
+                _ => None,
+            })
+            .await;
+        assert_eq!(knockback, (0, 3200));
+        assert_eq!(alice.expect(health).await, left);
+    }
+    let message = bob
+        .expect(|packet| match packet {
+            ClientBoundPacket::ChatMessage { json, .. } => json.translate.map(|key| {
+                let names: Vec<_> = json.with.into_iter().map(|name| name.text).collect();
+                (key, names)
+            }),
+            _ => None,
+        })
+        .await;
+    assert_eq!(
+        message,
+        (
+            "death.attack.player".to_string(),
+            vec!["alice".to_string(), "bob".to_string()]
+        )
+    );
+    // the death message goes out right away, the entity status at the end
+    // of the tick
+    bob.expect(|packet| match packet {
+        ClientBoundPacket::EntityStatus {
+            entity_id,
+            entity_status: 3,
+        } if entity_id == entity => Some(()),
+        _ => None,
+    })
+    .await;
+
+    alice
+        .send(ProxyBoundPacket::ClientStatus {
+            action_id: VarInt::new(0),
+        })
+        .await;
+    alice
+        .expect(|packet| match packet {
+            ClientBoundPacket::Respawn { .. } => Some(()),
+            _ => None,
+        })
+        .await;
+    assert_eq!(alice.expect(health).await, 20.0);
+}
+
+#[tokio::test]
+async fn players_change_worlds() {
+    let harness = Harness::start_with(ServerConfig {
+        setup: Arc::new(|game| {
+            let generator = Arc::new(FlatGenerator::default());
+            game.add_world("nether", World::new(Dimension::Nether, generator.clone()))
+                .unwrap();
+            let lobby = move || World::new(Dimension::Overworld, generator.clone());
+            game.add_world("lobby", lobby()).unwrap();
+            let mut moved = HashSet::new();
+            game.scheduler().run_repeating(1, 1, move |game| {
+                let players: Vec<_> = game.players().keys().copied().collect();
+                for player in players {
+                    if moved.insert(player) {
+                        assert!(game.change_world(player, "lobby"));
+                        assert!(!game.change_world(player, "end"));
+                        let lobby = lobby.clone();
+                        game.scheduler().run_later(40, move |game| {
+                            // worlds can only be replaced once their players left
+                            assert!(game.add_world("lobby", lobby()).is_err());
+                            assert!(game.change_world(player, "nether"));
+                            assert!(game.add_world("lobby", lobby()).unwrap().is_some());
+                        });
+                    }
+                }
+            });
+        }),
+        ..Default::default()
+    })
+    .await;
+    let mut client = harness.connect("alice").await;
+    let respawn = |packet| match packet {
+        ClientBoundPacket::Respawn { dimension, .. } => Some(dimension),
+        _ => None,
+    };
+
+    // the lobby is in the overworld too, so chunks are dropped by going
+    // through the nether
+    assert_eq!(client.expect(respawn).await, -1);
+    assert_eq!(client.expect(respawn).await, 0);
+    let bulk = |packet| match packet {
+        ClientBoundPacket::MapChunkBulk { bulk } => Some(bulk),
+        _ => None,
+    };
+    assert!(client.expect(bulk).await.sky_light);
+    confirm_spawn(&mut client).await;
+
+    // chunks of the nether carry no sky light
+    assert_eq!(client.expect(respawn).await, -1);
+    let bulk = client.expect(bulk).await;
+    assert!(!bulk.sky_light);
+    for column in &bulk.columns {
+        let (x, z, mask) = (column.x, column.z, column.primary_bit_mask);
+        let decoded = decode_column(x, z, mask, false, &column.data).expect("chunk should decode");
+        assert!(decoded
+            .sections
+            .iter()
+            .flatten()
+            .all(|s| s.sky_light.is_none()));
+    }
+    confirm_spawn(&mut client).await;
+}
+
+#[tokio::test]
+async fn frozen_night_thunder_and_creative() {
+    let harness = Harness::start_with(ServerConfig {
+        setup: Arc::new(|game| {
+            if let Some(world) = game.world_mut(DEFAULT_WORLD) {
+                world.set_time(18000);
+                world.freeze_time(true);
+            }
+            let mut switched = HashSet::new();
+            game.scheduler().run_repeating(1, 1, move |game| {
+                let players: Vec<_> = game.players().keys().copied().collect();
+                for player in players {
+                    if switched.insert(player) {
+                        game.set_gamemode(player, Gamemode::Creative);
+                        if let Some(world) = game.world_mut(DEFAULT_WORLD) {
+                            world.set_weather(Weather::Thunder);
+                        }
+                    }
+                }
+            });
+        }),
+        ..Default::default()
+    })
+    .await;
+    let mut client = harness.connect("alice").await;
+
+    // a negative time stops clients from advancing it
+    let time = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::TimeUpdate { time_of_day, .. } => Some(time_of_day),
+            _ => None,
+        })
+        .await;
+    assert_eq!(time, -18000);
+    let state = |reason| {
+        move |packet| match packet {
+            ClientBoundPacket::ChangeGameState { reason: r, value } if r == reason => Some(value),
+            _ => None,
+        }
+    };
+    assert_eq!(client.expect(state(3)).await, 1.0);
+    // invulnerable, allowed to fly and in creative mode
+    let flags = client
+        .expect(|packet| match packet {
+            ClientBoundPacket::PlayerAbilities { flags, .. } => Some(flags),
+            _ => None,
+        })
+        .await;
+    assert_eq!(flags, 0b1101);
+
+    // rain begins, then fades in along with thunder
+    client.expect(state(1)).await;
+    assert_eq!(client.expect(state(7)).await, 0.01);
+    assert_eq!(client.expect(state(8)).await, 0.01);
+}
+
+#[tokio::test]
+async fn welcome_chat_and_title() {
+    let harness = Harness::start().await;
+    let mut client = harness.connect("alice").await;
+    settings(&mut client, "en_US").await;
+
+    assert_eq!(chat(&mut client).await, "hello, welcome to the server!");
+    assert_eq!(title(&mut client).await, "Hey there!");
+}
+
+#[tokio::test]
+async fn localized_welcome() {
+    let harness = Harness::start().await;
+    let mut client = harness.connect("alice").await;
+    settings(&mut client, "fr_FR").await;
+
+    assert_eq!(
+        chat(&mut client).await,
+        "bonjour, bienvenue sur le serveur!"
+    );
+    assert_eq!(title(&mut client).await, "Salut!");
+}
+
+#[tokio::test]
+async fn chat_is_broadcast() {
+    let harness = Harness::start().await;
+    let mut alice = harness.connect("alice").await;
+    let mut bob = harness.connect("bob").await;
+    for client in [&mut alice, &mut bob] {
+        client
+            .expect(|packet| match packet {
+                ClientBoundPacket::JoinGame { .. } => Some(()),
+                _ => None,
+            })
+            .await;
+    }
+
+    alice
+        .send(ProxyBoundPacket::ChatMessage {
+            message: "hi bob".to_string(),
+        })
+        .await;
+    assert_eq!(chat(&mut bob).await, "<alice> hi bob");
+    assert_eq!(chat(&mut alice).await, "<alice> hi bob");
+}
+
+#[tokio::test]
+async fn status_counts_players() {
+    let harness = Harness::start().await;
+    assert!(harness.status().await.contains("\"online\":0"));
+
+    let _client = harness.connect("alice").await;
+    assert!(harness.status().await.contains("\"online\":1"));
+}
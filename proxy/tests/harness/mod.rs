@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time,
+};
+
+use makar_proxy::{
+    protocol::{State, VarInt},
+    versions::v1_8_8::*,
+    ProxyConfig, ProxyHandle,
+};
+use makar_server::{ServerConfig, ServerHandle};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Proxies and a game server listening on ephemeral ports.
+pub struct Harness {
+    server: ServerHandle,
+    proxies: Vec<ProxyHandle>,
+}
+
+impl Harness {
+    pub async fn start() -> Self {
+        Self::start_with(ServerConfig::default()).await
+    }
+
+    /// Starts a server with the given settings, on an ephemeral port.
+    pub async fn start_with(config: ServerConfig) -> Self {
+        let server = makar_server::run(ServerConfig {
+            address: "127.0.0.1:0".to_string(),
+            ..config
+        })
+        .await
+        .expect("server should start");
+        let mut harness = Self {
+            server,
+            proxies: Vec::new(),
+        };
+        harness.add_proxy().await;
+        harness
+    }
+
+    /// Starts another proxy in front of the same server, returning its index.
+    pub async fn add_proxy(&mut self) -> usize {
+        let proxy = makar_proxy::run(ProxyConfig {
+            address: "127.0.0.1:0".to_string(),
+            server_address: self.server.address().to_string(),
+        })
+        .await
+        .expect("proxy should start");
+        self.proxies.push(proxy);
+        self.proxies.len() - 1
+    }
+
+    async fn open(&self, proxy: usize, next_state: State) -> TestClient {
+        let address = self.proxies[proxy].address();
+        let mut client = TestClient {
+            socket: TcpStream::connect(address)
+                .await
+                .expect("proxy should accept"),
+            state: State::Handshake,
+        };
+        client
+            .send(ProxyBoundPacket::Handshake {
+                protocol: VarInt::new(PROTOCOL_VERSION),
+                address: address.ip().to_string(),
+                port: address.port(),
+                next_state: match next_state {
+                    State::Status => 1,
+                    _ => 2,
+                },
+            })
+            .await;
+        client.state = next_state;
+        client
+    }
+
+    pub async fn status(&self) -> String {
+        let mut client = self.open(0, State::Status).await;
+        client.send(ProxyBoundPacket::StatusRequest {}).await;
+        client
+            .expect(|packet| match packet {
+                ClientBoundPacket::StatusResponse { status } => Some(status),
+                _ => None,
+            })
+            .await
+    }
+
+    /// Logs a new player in, returning once the client is in the play state.
+    pub async fn connect(&self, username: &str) -> TestClient {
+        self.connect_through(0, username).await
+    }
+
+    /// Logs a new player in through the given proxy.
+    pub async fn connect_through(&self, proxy: usize, username: &str) -> TestClient {
+        let mut client = self.open(proxy, State::Login).await;
+        client
+            .send(ProxyBoundPacket::LoginStart {
+                name: username.to_string(),
+            })
+            .await;
+        client
+            .expect(|packet| match packet {
+                ClientBoundPacket::LoginSuccess { .. } => Some(()),
+                _ => None,
+            })
+            .await;
+        client.state = State::Play;
+        client
+    }
+}
+
+pub struct TestClient {
+    socket: TcpStream,
+    state: State,
+}
+
+impl TestClient {
+    pub async fn send(&mut self, packet: ProxyBoundPacket) {
+        self.socket
+            .write_all(&packet.serialize())
+            .await
+            .expect("proxy should accept packets");
+    }
+
+    async fn recv(&mut self) -> Option<ClientBoundPacket> {
+        let size = VarInt::read(&mut self.socket)
+            .await
+            .expect("proxy should send packets")
+            .value();
+        let mut buf = vec![0u8; size as usize];
+        self.socket
+            .read_exact(&mut buf)
+            .await
+            .expect("proxy should send whole packets");
+        // packets the test definitions don't know about are skipped
+        ClientBoundPacket::deserialize(&self.state, Bytes::from(buf)).ok()
+    }
+
+    /// Skips packets until `f` accepts one, failing the test after a timeout.
+    pub async fn expect<T>(&mut self, mut f: impl FnMut(ClientBoundPacket) -> Option<T>) -> T {
+        time::timeout(TIMEOUT, async {
+            loop {
+                if let Some(res) = self.recv().await.and_then(&mut f) {
+                    return res;
+                }
+            }
+        })
+        .await
+        .expect("expected packet wasn't received in time")
+    }
+}
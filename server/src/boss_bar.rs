@@ -0,0 +1,157 @@
+use makar_protocol::{Metadata, MetadataValue, ProxyBoundPacket};
+
+use crate::entity::EYE_HEIGHT;
+
+/// Network type of withers, whose health is shown at the top of the screen.
+const WITHER: u8 = 64;
+/// Health of withers, the progress being a fraction of it.
+const MAX_HEALTH: f32 = 300.0;
+/// How far in front of players their wither is, as clients only show the
+/// bar of bosses they render.
+const DISTANCE: f64 = 32.0;
+
+/// Metadata index of the flags of an entity, and the one hiding it.
+const FLAGS: u8 = 0;
+const INVISIBLE: i8 = 0x20;
+/// Metadata index of the name of an entity, shown on its boss bar.
+const CUSTOM_NAME: u8 = 2;
+/// Metadata index of the health of a living entity.
+const HEALTH: u8 = 6;
+
+/// A bar at the top of the screen, with some text above it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BossBar {
+    /// At most 64 characters.
+    pub text: String,
+    /// How full the bar is, from 0 to 1.
+    pub progress: f32,
+}
+
+impl BossBar {
+    fn health(&self) -> f32 {
+        // withers without health die on clients
+        (self.progress.clamp(0.0, 1.0) * MAX_HEALTH).max(1.0)
+    }
+}
+
+/// The invisible wither showing a player their boss bar, only spawned for
+/// them.
+pub(crate) struct Boss {
+    entity_id: i32,
+    bar: BossBar,
+    position: (f64, f64, f64),
+}
+
+/// Where a player's wither is, in front of their eyes.
+fn position((x, y, z): (f64, f64, f64), yaw: f32, pitch: f32) -> (f64, f64, f64) {
+    let (yaw, pitch) = ((yaw as f64).to_radians(), (pitch as f64).to_radians());
+    (
+        x - yaw.sin() * pitch.cos() * DISTANCE,
+        y + EYE_HEIGHT - pitch.sin() * DISTANCE,
+        z + yaw.cos() * pitch.cos() * DISTANCE,
+    )
+}
+
+impl Boss {
+    pub fn spawn(
+        player: u128,
+        entity_id: i32,
+        bar: &BossBar,
+        feet: (f64, f64, f64),
+        yaw: f32,
+        pitch: f32,
+    ) -> (Self, ProxyBoundPacket) {
+        let boss = Self {
+            entity_id,
+            bar: bar.clone(),
+            position: position(feet, yaw, pitch),
+        };
+        let (x, y, z) = boss.position;
+        let packet = ProxyBoundPacket::SpawnMob {
+            player,
+            entity_id,
+            kind: WITHER,
+            x,
+            y,
+            z,
+            yaw: 0.0,
+            pitch: 0.0,
+            head_yaw: 0.0,
+            velocity: (0.0, 0.0, 0.0),
+            metadata: vec![
+                Metadata {
+                    index: FLAGS,
+                    value: MetadataValue::Byte(INVISIBLE),
+                },
+                Metadata {
+                    index: CUSTOM_NAME,
+                    value: MetadataValue::String(bar.text.clone()),
+                },
+                Metadata {
+                    index: HEALTH,
+                    value: MetadataValue::Float(bar.health()),
+                },
+            ],
+        };
+        (boss, packet)
+    }
+
+    /// Moves the wither along with the player and changes its bar.
+    pub fn update(
+        &mut self,
+        player: u128,
+        bar: &BossBar,
+        feet: (f64, f64, f64),
+        yaw: f32,
+        pitch: f32,
+    ) -> Vec<ProxyBoundPacket> {
+        let mut packets = Vec::new();
+        let entity_id = self.entity_id;
+
+        let mut metadata = Vec::new();
+        if bar.text != self.bar.text {
+            metadata.push(Metadata {
+                index: CUSTOM_NAME,
+                value: MetadataValue::String(bar.text.clone()),
+            });
+        }
+        if bar.health() != self.bar.health() {
+            metadata.push(Metadata {
+                index: HEALTH,
+                value: MetadataValue::Float(bar.health()),
+            });
+        }
+        if !metadata.is_empty() {
+            packets.push(ProxyBoundPacket::EntityMetadata {
+                player,
+                entity_id,
+                metadata,
+            });
+        }
+        self.bar = bar.clone();
+
+        let position = position(feet, yaw, pitch);
+        if position != self.position {
+            self.position = position;
+            let (x, y, z) = position;
+            packets.push(ProxyBoundPacket::EntityTeleport {
+                player,
+                entity_id,
+                x,
+                y,
+                z,
+                yaw: 0.0,
+                pitch: 0.0,
+                on_ground: false,
+            });
+        }
+        packets
+    }
+
+    pub fn destroy(&self, player: u128) -> ProxyBoundPacket {
+        ProxyBoundPacket::DestroyEntities {
+            player,
+            entity_ids: vec![self.entity_id],
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+
+use makar_protocol::{Animation, EntityStatus, ItemStack, MetadataValue};
+
+mod tracker;
+
+pub use tracker::Tracker;
+
+/// Metadata index of the stack carried by an item.
+const ITEM_STACK: u8 = 10;
+/// Metadata index of the skin layers shown on a player.
+pub const SKIN_PARTS: u8 = 10;
+/// Height of the eyes of players above their feet.
+pub const EYE_HEIGHT: f64 = 1.62;
+/// Ticks before players can pick up the items spawned, as the vanilla
+/// server does for blocks broken.
+const PICKUP_DELAY: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityKind {
+    /// A connected player, by the id the proxy gave them.
+    Player(u128),
+    /// A living entity other than a player, by its network type.
+    Mob(u8),
+    /// An item lying on the ground.
+    Item,
+    /// A vehicle, projectile or any other object, by its network type, with
+    /// the data spawning it carries.
+    Object { kind: u8, data: i32 },
+}
+
+impl EntityKind {
+    /// How close in blocks players have to be to see the entity.
+    pub fn tracking_range(&self) -> f64 {
+        match self {
+            EntityKind::Player(_) => 128.0,
+            EntityKind::Mob(_) => 80.0,
+            EntityKind::Item | EntityKind::Object { .. } => 64.0,
+        }
+    }
+}
+
+/// Something in the world that isn't a block. Angles are in degrees and
+/// velocities in blocks per tick.
+#[derive(Debug, Clone)]
+pub struct Entity {
+    id: i32,
+    kind: EntityKind,
+    /// The name of the world the entity is in.
+    world: String,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub head_yaw: f32,
+    pub velocity: (f64, f64, f64),
+    pub on_ground: bool,
+    /// Ticks since the entity spawned.
+    pub age: u32,
+    /// Ticks left before players can pick the entity up, for items.
+    pub pickup_delay: u32,
+    /// Entries by index, their meaning depending on the kind of entity.
+    pub metadata: BTreeMap<u8, MetadataValue>,
+    /// Animations played since the entity was last tracked.
+    animations: Vec<Animation>,
+    statuses: Vec<EntityStatus>,
+}
+
+impl Entity {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn kind(&self) -> EntityKind {
+        self.kind
+    }
+
+    pub fn world(&self) -> &str {
+        &self.world
+    }
+
+    /// Moves the entity to another world, players being moved with
+    /// `Game::change_world` instead.
+    pub fn set_world(&mut self, world: impl Into<String>) {
+        self.world = world.into();
+    }
+
+    pub fn chunk(&self) -> (i32, i32) {
+        ((self.x.floor() as i32) >> 4, (self.z.floor() as i32) >> 4)
+    }
+
+    /// The stack an item carries.
+    pub fn item(&self) -> Option<&ItemStack> {
+        match self.metadata.get(&ITEM_STACK) {
+            Some(MetadataValue::Slot(item)) => item.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn set_item(&mut self, stack: ItemStack) {
+        self.metadata
+            .insert(ITEM_STACK, MetadataValue::Slot(Some(stack)));
+    }
+
+    pub fn teleport(&mut self, x: f64, y: f64, z: f64) {
+        (self.x, self.y, self.z) = (x, y, z);
+    }
+
+    /// Plays an animation for the players who see the entity.
+    pub fn animate(&mut self, animation: Animation) {
+        self.animations.push(animation);
+    }
+
+    pub fn take_animations(&mut self) -> Vec<Animation> {
+        std::mem::take(&mut self.animations)
+    }
+
+    /// Plays an event, like getting hurt, for the players who see the
+    /// entity.
+    pub fn play_status(&mut self, status: EntityStatus) {
+        self.statuses.push(status);
+    }
+
+    pub fn take_statuses(&mut self) -> Vec<EntityStatus> {
+        std::mem::take(&mut self.statuses)
+    }
+}
+
+/// Every entity of the world by id, ids being unique for the lifetime of
+/// the server as clients use them to tell entities apart.
+pub struct Entities {
+    next_id: i32,
+    entities: BTreeMap<i32, Entity>,
+}
+
+impl Default for Entities {
+    fn default() -> Self {
+        Self {
+            // some clients treat 0 as no entity
+            next_id: 1,
+            entities: BTreeMap::new(),
+        }
+    }
+}
+
+impl Entities {
+    pub fn spawn(&mut self, kind: EntityKind, world: &str, x: f64, y: f64, z: f64) -> &mut Entity {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entities.entry(id).or_insert(Entity {
+            id,
+            kind,
+            world: world.to_string(),
+            x,
+            y,
+            z,
+            yaw: 0.0,
+            pitch: 0.0,
+            head_yaw: 0.0,
+            velocity: (0.0, 0.0, 0.0),
+            on_ground: false,
+            age: 0,
+            pickup_delay: 0,
+            metadata: BTreeMap::new(),
+            animations: Vec::new(),
+            statuses: Vec::new(),
+        })
+    }
+
+    /// An id no entity will have, for entities only some players see that
+    /// aren't part of the world.
+    pub fn reserve_id(&mut self) -> i32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    pub fn spawn_item(
+        &mut self,
+        stack: ItemStack,
+        world: &str,
+        x: f64,
+        y: f64,
+        z: f64,
+    ) -> &mut Entity {
+        let entity = self.spawn(EntityKind::Item, world, x, y, z);
+        entity.set_item(stack);
+        entity.pickup_delay = PICKUP_DELAY;
+        entity
+    }
+
+    pub fn remove(&mut self, id: i32) -> Option<Entity> {
+        self.entities.remove(&id)
+    }
+
+    pub fn get(&self, id: i32) -> Option<&Entity> {
+        self.entities.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: i32) -> Option<&mut Entity> {
+        self.entities.get_mut(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Entity> {
+        self.entities.values()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Entity> {
+        self.entities.values_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
@@ -0,0 +1,394 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use makar_protocol::{Animation, EntityStatus, Metadata, MetadataValue, ProxyBoundPacket};
+
+use super::{Entities, Entity, EntityKind};
+use crate::game::Player;
+
+/// Ticks between teleports of moving entities resyncing their position,
+/// as the vanilla server does.
+const TELEPORT_PERIOD: u32 = 400;
+/// Network type of the object items spawn as.
+const ITEM_OBJECT: u8 = 2;
+
+/// Converts a coordinate to fixed point, in 1/32 of a block.
+fn fixed(value: f64) -> i32 {
+    (value * 32.0).floor() as i32
+}
+
+/// Converts an angle in degrees to steps of 1/256 of a turn.
+fn angle(degrees: f32) -> i8 {
+    (degrees * 256.0 / 360.0).floor() as i32 as i8
+}
+
+enum Update {
+    Move {
+        delta: (f64, f64, f64),
+        look: Option<(f32, f32)>,
+    },
+    Look(f32, f32),
+    Teleport,
+    HeadLook(f32),
+    Velocity((f64, f64, f64)),
+    Metadata(Vec<Metadata>),
+    Animation(Animation),
+    Status(EntityStatus),
+}
+
+/// What the players seeing an entity were told about it.
+struct Tracked {
+    /// Position in fixed point.
+    position: (i32, i32, i32),
+    look: (i8, i8),
+    head_yaw: i8,
+    velocity: (f64, f64, f64),
+    metadata: BTreeMap<u8, MetadataValue>,
+    since_teleport: u32,
+    viewers: HashSet<u128>,
+}
+
+impl Tracked {
+    fn new(entity: &Entity) -> Self {
+        Self {
+            position: (fixed(entity.x), fixed(entity.y), fixed(entity.z)),
+            look: (angle(entity.yaw), angle(entity.pitch)),
+            head_yaw: angle(entity.head_yaw),
+            velocity: entity.velocity,
+            metadata: entity.metadata.clone(),
+            since_teleport: 0,
+            viewers: HashSet::new(),
+        }
+    }
+
+    /// The position sent to players, which they round the same way.
+    fn sent_position(&self) -> (f64, f64, f64) {
+        let (x, y, z) = self.position;
+        (x as f64 / 32.0, y as f64 / 32.0, z as f64 / 32.0)
+    }
+
+    /// Records the entity's state, returning what changed since last time.
+    fn update(&mut self, entity: &mut Entity) -> Vec<Update> {
+        let mut updates = Vec::new();
+
+        let position = (fixed(entity.x), fixed(entity.y), fixed(entity.z));
+        let look = (angle(entity.yaw), angle(entity.pitch));
+        let delta = (
+            position.0 - self.position.0,
+            position.1 - self.position.1,
+            position.2 - self.position.2,
+        );
+        let moved = position != self.position;
+        let looked = look != self.look;
+        self.since_teleport += 1;
+        // relative moves are bytes
+        let far = [delta.0, delta.1, delta.2]
+            .iter()
+            .any(|d| !(-128..128).contains(d));
+        if moved && (far || self.since_teleport >= TELEPORT_PERIOD) {
+            self.since_teleport = 0;
+            updates.push(Update::Teleport);
+        } else if moved {
+            updates.push(Update::Move {
+                delta: (
+                    delta.0 as f64 / 32.0,
+                    delta.1 as f64 / 32.0,
+                    delta.2 as f64 / 32.0,
+                ),
+                look: looked.then_some((entity.yaw, entity.pitch)),
+            });
+        } else if looked {
+            updates.push(Update::Look(entity.yaw, entity.pitch));
+        }
+        self.position = position;
+        self.look = look;
+
+        let head_yaw = angle(entity.head_yaw);
+        if head_yaw != self.head_yaw {
+            self.head_yaw = head_yaw;
+            updates.push(Update::HeadLook(entity.head_yaw));
+        }
+
+        // players move on their own, their velocity only matters when
+        // they're knocked back
+        if entity.velocity != self.velocity {
+            self.velocity = entity.velocity;
+            if !matches!(entity.kind(), EntityKind::Player(_)) {
+                updates.push(Update::Velocity(entity.velocity));
+            }
+        }
+
+        let changed: Vec<_> = entity
+            .metadata
+            .iter()
+            .filter(|(index, value)| self.metadata.get(index) != Some(value))
+            .map(|(index, value)| Metadata {
+                index: *index,
+                value: value.clone(),
+            })
+            .collect();
+        if !changed.is_empty() {
+            self.metadata = entity.metadata.clone();
+            updates.push(Update::Metadata(changed));
+        }
+        updates.extend(entity.take_animations().into_iter().map(Update::Animation));
+        updates.extend(entity.take_statuses().into_iter().map(Update::Status));
+        updates
+    }
+
+    fn packet(&self, update: &Update, player: u128, entity: &Entity) -> ProxyBoundPacket {
+        let entity_id = entity.id();
+        let on_ground = entity.on_ground;
+        match update {
+            Update::Move {
+                delta,
+                look: Some((yaw, pitch)),
+            } => ProxyBoundPacket::EntityLookAndRelativeMove {
+                player,
+                entity_id,
+                delta: *delta,
+                yaw: *yaw,
+                pitch: *pitch,
+                on_ground,
+            },
+            Update::Move { delta, look: None } => ProxyBoundPacket::EntityRelativeMove {
+                player,
+                entity_id,
+                delta: *delta,
+                on_ground,
+            },
+            Update::Look(yaw, pitch) => ProxyBoundPacket::EntityLook {
+                player,
+                entity_id,
+                yaw: *yaw,
+                pitch: *pitch,
+                on_ground,
+            },
+            Update::Teleport => {
+                let (x, y, z) = self.sent_position();
+                ProxyBoundPacket::EntityTeleport {
+                    player,
+                    entity_id,
+                    x,
+                    y,
+                    z,
+                    yaw: entity.yaw,
+                    pitch: entity.pitch,
+                    on_ground,
+                }
+            }
+            Update::HeadLook(head_yaw) => ProxyBoundPacket::EntityHeadLook {
+                player,
+                entity_id,
+                head_yaw: *head_yaw,
+            },
+            Update::Velocity(velocity) => ProxyBoundPacket::EntityVelocity {
+                player,
+                entity_id,
+                velocity: *velocity,
+            },
+            Update::Metadata(metadata) => ProxyBoundPacket::EntityMetadata {
+                player,
+                entity_id,
+                metadata: metadata.clone(),
+            },
+            Update::Animation(animation) => ProxyBoundPacket::Animation {
+                player,
+                entity_id,
+                animation: *animation,
+            },
+            Update::Status(status) => ProxyBoundPacket::EntityStatus {
+                player,
+                entity_id,
+                status: *status,
+            },
+        }
+    }
+
+    fn spawn_packets(&self, player: u128, entity: &Entity) -> Vec<ProxyBoundPacket> {
+        let (x, y, z) = self.sent_position();
+        let entity_id = entity.id();
+        let metadata: Vec<_> = entity
+            .metadata
+            .iter()
+            .map(|(index, value)| Metadata {
+                index: *index,
+                value: value.clone(),
+            })
+            .collect();
+
+        let (kind, data) = match entity.kind() {
+            EntityKind::Player(uuid) => {
+                return vec![ProxyBoundPacket::SpawnPlayer {
+                    player,
+                    entity_id,
+                    uuid,
+                    x,
+                    y,
+                    z,
+                    yaw: entity.yaw,
+                    pitch: entity.pitch,
+                    current_item: 0,
+                    metadata,
+                }]
+            }
+            EntityKind::Mob(kind) => {
+                return vec![ProxyBoundPacket::SpawnMob {
+                    player,
+                    entity_id,
+                    kind,
+                    x,
+                    y,
+                    z,
+                    yaw: entity.yaw,
+                    pitch: entity.pitch,
+                    head_yaw: entity.head_yaw,
+                    velocity: entity.velocity,
+                    metadata,
+                }]
+            }
+            EntityKind::Item => (ITEM_OBJECT, 1),
+            EntityKind::Object { kind, data } => (kind, data),
+        };
+        let mut packets = vec![ProxyBoundPacket::SpawnObject {
+            player,
+            entity_id,
+            kind,
+            x,
+            y,
+            z,
+            yaw: entity.yaw,
+            pitch: entity.pitch,
+            data,
+            velocity: entity.velocity,
+        }];
+        // objects don't carry their metadata when spawning
+        if !metadata.is_empty() {
+            packets.push(ProxyBoundPacket::EntityMetadata {
+                player,
+                entity_id,
+                metadata,
+            });
+        }
+        packets
+    }
+}
+
+/// Shows players the entities around them, keeping them up to date as they
+/// change.
+#[derive(Default)]
+pub struct Tracker {
+    tracked: HashMap<i32, Tracked>,
+}
+
+impl Tracker {
+    /// Forgets what a player was sent, like when they change worlds and
+    /// their client drops every entity, the ones they see being spawned
+    /// again on the next update.
+    pub fn reset_viewer(&mut self, player: u128) {
+        for tracked in self.tracked.values_mut() {
+            tracked.viewers.remove(&player);
+        }
+    }
+
+    /// Destroys an entity for the players seeing it, who get it spawned
+    /// anew on the next update, like players coming back to life.
+    pub fn forget(&mut self, id: i32) -> Vec<ProxyBoundPacket> {
+        let Some(tracked) = self.tracked.remove(&id) else {
+            return Vec::new();
+        };
+        tracked
+            .viewers
+            .into_iter()
+            .map(|player| ProxyBoundPacket::DestroyEntities {
+                player,
+                entity_ids: vec![id],
+            })
+            .collect()
+    }
+
+    /// Shows the players seeing an item it flying to whoever picked it up.
+    pub fn collect(&self, item: i32, collector: i32) -> Vec<ProxyBoundPacket> {
+        let Some(tracked) = self.tracked.get(&item) else {
+            return Vec::new();
+        };
+        tracked
+            .viewers
+            .iter()
+            .map(|player| ProxyBoundPacket::CollectItem {
+                player: *player,
+                collected: item,
+                collector,
+            })
+            .collect()
+    }
+
+    /// Sends the changes of every entity to the players seeing it, spawning
+    /// entities for the players who came close and destroying them for the
+    /// others.
+    pub fn update(
+        &mut self,
+        entities: &mut Entities,
+        players: &HashMap<u128, Player>,
+    ) -> Vec<ProxyBoundPacket> {
+        let mut packets = Vec::new();
+        let mut destroyed: BTreeMap<u128, Vec<i32>> = BTreeMap::new();
+
+        self.tracked.retain(|id, tracked| {
+            if entities.get(*id).is_some() {
+                return true;
+            }
+            for player in &tracked.viewers {
+                destroyed.entry(*player).or_default().push(*id);
+            }
+            false
+        });
+
+        let viewers: Vec<_> = players
+            .iter()
+            .filter_map(|(id, player)| {
+                let entity = entities.get(player.entity())?;
+                Some((*id, player, entity.x, entity.z))
+            })
+            .collect();
+
+        for entity in entities.iter_mut() {
+            let tracked = self
+                .tracked
+                .entry(entity.id())
+                .or_insert_with(|| Tracked::new(entity));
+            tracked
+                .viewers
+                .retain(|player| players.contains_key(player));
+
+            for update in tracked.update(entity) {
+                for player in &tracked.viewers {
+                    packets.push(tracked.packet(&update, *player, entity));
+                }
+            }
+
+            let range = entity.kind().tracking_range();
+            for (id, player, x, z) in &viewers {
+                if player.entity() == entity.id() {
+                    continue;
+                }
+                // players only see entities close to them, in chunks they have
+                let visible = player.world() == entity.world()
+                    && (x - entity.x).abs() <= range
+                    && (z - entity.z).abs() <= range
+                    && player.view().is_loaded(entity.chunk());
+                if visible {
+                    if tracked.viewers.insert(*id) {
+                        packets.extend(tracked.spawn_packets(*id, entity));
+                    }
+                } else if tracked.viewers.remove(id) {
+                    destroyed.entry(*id).or_default().push(entity.id());
+                }
+            }
+        }
+
+        for (player, entity_ids) in destroyed {
+            packets.push(ProxyBoundPacket::DestroyEntities { player, entity_ids });
+        }
+        packets
+    }
+}
@@ -0,0 +1,117 @@
+use makar_protocol::*;
+
+use super::Game;
+use crate::{
+    view::{spiral, View},
+    world::{self, World, SPAWN_RADIUS},
+};
+
+/// Most chunks sent in a single map chunk bulk, as the vanilla server does.
+const BULK_SIZE: usize = 10;
+/// Most chunks sent to a player every tick.
+const CHUNKS_PER_TICK: usize = BULK_SIZE;
+/// Ticks between sweeps freeing the chunks no player can see, every 5
+/// seconds.
+pub(super) const EVICTION_PERIOD: u64 = 100;
+
+pub(super) fn chunk_pos(x: f64, z: f64) -> (i32, i32) {
+    ((x.floor() as i32) >> 4, (z.floor() as i32) >> 4)
+}
+
+/// Bulks sending loaded chunks to a player, skipping the others.
+fn chunk_packets(player: u128, world: &World, chunks: &[(i32, i32)]) -> Vec<ProxyBoundPacket> {
+    chunks
+        .chunks(BULK_SIZE)
+        .map(|bulk| ProxyBoundPacket::MapChunkBulk {
+            player,
+            sky_light: world.has_sky(),
+            chunks: bulk
+                .iter()
+                .filter_map(|(x, z)| world.chunk(*x, *z))
+                .map(|chunk| chunk.to_protocol())
+                .collect(),
+        })
+        .collect()
+}
+
+impl Game {
+    /// Sends the area around a spawn point right away, the spawn area of
+    /// worlds being kept loaded, and returns the view of the player, the
+    /// rest of which is streamed as it gets loaded.
+    pub(super) fn send_spawn_area(
+        &mut self,
+        player: u128,
+        world: &str,
+        spawn: BlockPosition,
+        distance: i32,
+    ) -> View {
+        let center = (spawn.x >> 4, spawn.z >> 4);
+        let mut view = View::new(center, distance);
+        if let Some(world) = self.worlds.get(world) {
+            let chunks: Vec<_> = spiral(center, SPAWN_RADIUS.min(distance))
+                .into_iter()
+                .filter(|(x, z)| world.chunk(*x, *z).is_some())
+                .collect();
+            let packets = chunk_packets(player, world, &chunks);
+            self.outgoing.extend(packets);
+            view.mark_loaded(&chunks);
+        }
+        view
+    }
+
+    pub(super) fn move_view(&mut self, id: u128, center: (i32, i32), distance: i32) {
+        let Some(player) = self.players.get_mut(&id) else {
+            return;
+        };
+        let unloaded = player.view.update(center, distance);
+        for (x, z) in unloaded {
+            self.send(ProxyBoundPacket::UnloadChunk { player: id, x, z });
+        }
+    }
+
+    pub(super) fn broadcast_block_changes(&mut self) {
+        for (name, world) in self.worlds.iter_mut() {
+            for (chunk, changes) in world.take_block_changes() {
+                for (id, player) in &self.players {
+                    if player.sees(name, chunk) {
+                        self.outgoing
+                            .push(world::block_changes_packet(*id, chunk, &changes));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Loads the chunks generated since the last tick, letting in the
+    /// players who were waiting for them.
+    pub(super) fn load_chunks(&mut self) {
+        for world in self.worlds.values_mut() {
+            world.poll_generated();
+        }
+        self.admit_players();
+        self.admit_arrivals();
+    }
+
+    /// Sends players the chunks coming into their view.
+    pub(super) fn stream_chunks(&mut self) {
+        for (id, player) in self.players.iter_mut() {
+            let Some(world) = self.worlds.get_mut(&player.world) else {
+                continue;
+            };
+            let chunks = player.view.next_chunks(world, CHUNKS_PER_TICK);
+            self.outgoing.extend(chunk_packets(*id, world, &chunks));
+        }
+    }
+
+    /// Frees the chunks no player can see.
+    pub(super) fn evict_chunks(&mut self) {
+        for (name, world) in self.worlds.iter_mut() {
+            let players: Vec<_> = self
+                .players
+                .values()
+                .filter(|player| player.world == *name)
+                .collect();
+            world.evict(|chunk| players.iter().any(|player| player.view.contains(chunk)));
+        }
+    }
+}
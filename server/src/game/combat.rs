@@ -0,0 +1,224 @@
+use makar_protocol::*;
+
+use super::{items::THROWN_PICKUP_DELAY, Game};
+use crate::{
+    entity::{EntityKind, EYE_HEIGHT},
+    health::DamageCause,
+    inventory::Tool,
+};
+
+/// Farthest players hit others from.
+const ATTACK_REACH: f64 = 6.0;
+/// Speed at which players are knocked back by hits, in blocks per tick.
+const KNOCKBACK: f64 = 0.4;
+/// How much harder players falling on others hit them.
+const CRITICAL_HIT: f32 = 1.5;
+/// Blocks players fall without getting hurt, each block past them taking
+/// half a heart.
+const SAFE_FALL: f64 = 3.0;
+/// Height below which players are hurt every tick.
+const VOID_DEPTH: f64 = -64.0;
+/// Health players below the void depth lose, the invulnerability after a
+/// hit making it two hearts every half second.
+const VOID_DAMAGE: f32 = 4.0;
+
+impl Game {
+    /// Sets a player's health, up to 20 for 10 hearts, dead players staying
+    /// dead until they respawn.
+    pub fn set_health(&mut self, player: u128, health: f32) {
+        if let Some(player) = self.players.get_mut(&player) {
+            if !player.health.is_dead() && health > 0.0 {
+                player.health.set(health);
+            }
+        }
+    }
+
+    /// Hurts a player, killing them once they're out of health, returning
+    /// whether they were hurt. Only the void hurts creative players.
+    pub fn damage(&mut self, id: u128, amount: f32, cause: DamageCause) -> bool {
+        let tick = self.scheduler.tick();
+        let Some(player) = self.players.get_mut(&id) else {
+            return false;
+        };
+        let vulnerable = match player.gamemode {
+            Gamemode::Survival | Gamemode::Adventure => true,
+            Gamemode::Creative => cause == DamageCause::Void,
+            Gamemode::Spectator => false,
+        };
+        if !vulnerable {
+            return false;
+        }
+        let Some((_, new_hit)) = player.health.hurt(tick, amount) else {
+            return false;
+        };
+        let dead = player.health.is_dead();
+        if let Some(entity) = self.entities.get_mut(player.entity) {
+            if new_hit {
+                entity.play_status(EntityStatus::Hurt);
+            }
+        }
+        if dead {
+            self.die(id, cause);
+        }
+        true
+    }
+
+    /// Drops everything a dead player had and tells everyone how they died.
+    fn die(&mut self, id: u128, cause: DamageCause) {
+        self.stop_digging(id);
+        self.window_closed(id);
+        let killer = match cause {
+            DamageCause::Attack(killer) => self.players.get(&killer).map(|p| p.username.clone()),
+            _ => None,
+        };
+        let Some(player) = self.players.get_mut(&id) else {
+            return;
+        };
+        let message = cause.death_message(&player.username, killer.as_deref());
+        let items: Vec<_> = player.inventory.slots().iter().flatten().cloned().collect();
+        player.inventory.clear();
+        let Some(entity) = self.entities.get_mut(player.entity) else {
+            return;
+        };
+        entity.play_status(EntityStatus::Dead);
+
+        // items spread out around where the player died
+        let (x, y, z) = (entity.x, entity.y + EYE_HEIGHT - 0.3, entity.z);
+        let world = entity.world().to_string();
+        for (i, item) in items.into_iter().enumerate() {
+            let angle = i as f64 * 2.4;
+            let item = self.entities.spawn_item(item, &world, x, y, z);
+            item.velocity = (angle.cos() * 0.1, 0.2, angle.sin() * 0.1);
+            item.pickup_delay = THROWN_PICKUP_DELAY;
+        }
+        self.broadcast(|player| ProxyBoundPacket::ChatMessage {
+            player,
+            json: message.clone(),
+            position: ChatPosition::System,
+        });
+    }
+
+    /// Brings a dead player back to life at the spawn point.
+    pub(super) fn respawn(&mut self, id: u128) {
+        let Some(player) = self.players.get_mut(&id) else {
+            return;
+        };
+        if !player.health.is_dead() {
+            return;
+        }
+        // the spawn area is kept loaded, so players respawn right away
+        let Some((world, spawn)) = self
+            .worlds
+            .get_mut(&player.world)
+            .and_then(|world| world.spawn_point().map(|spawn| (world, spawn)))
+        else {
+            return;
+        };
+        player.health.reset();
+        let (gamemode, entity, slot) = (player.gamemode, player.entity, player.inventory.held());
+        // the same dimension, so that the client keeps its chunks
+        self.outgoing.push(ProxyBoundPacket::Respawn {
+            player: id,
+            dimension: world.dimension().id(),
+            difficulty: Difficulty::Easy,
+            gamemode,
+            level_type: world.level_type().to_string(),
+        });
+        self.send(ProxyBoundPacket::HeldItemChange { player: id, slot });
+        // others saw the player die, so they get them spawned again
+        let packets = self.tracker.forget(entity);
+        self.outgoing.extend(packets);
+        let (x, y, z) = (spawn.x as f64 + 0.5, spawn.y as f64, spawn.z as f64 + 0.5);
+        self.teleport(id, x, y, z, 0.0, 0.0);
+    }
+
+    /// Hits the player an attacker looks at, harder with weapons and while
+    /// falling, knocking them back.
+    pub(super) fn attack(&mut self, id: u128, target: i32) {
+        let Some(attacker) = self.players.get(&id) else {
+            return;
+        };
+        if attacker.gamemode == Gamemode::Spectator || attacker.health.is_dead() {
+            return;
+        }
+        let (Some(from), Some(to)) = (
+            self.entities.get(attacker.entity),
+            self.entities.get(target),
+        ) else {
+            return;
+        };
+        let EntityKind::Player(victim) = to.kind() else {
+            return;
+        };
+        let (dx, dz) = (to.x - from.x, to.z - from.z);
+        if victim == id || dx * dx + (to.y - from.y).powi(2) + dz * dz >= ATTACK_REACH.powi(2) {
+            return;
+        }
+
+        let weapon = attacker
+            .inventory
+            .held_item()
+            .and_then(|item| Tool::of(&item));
+        let mut damage = 1.0 + weapon.map_or(0.0, |tool| tool.attack_damage());
+        let feet = BlockPosition {
+            x: from.x.floor() as i32,
+            y: from.y.floor() as i32,
+            z: from.z.floor() as i32,
+        };
+        let in_water = matches!(self.block_at(id, feet).id, 8 | 9);
+        let critical = attacker.health.fall_distance > 0.0 && !from.on_ground && !in_water;
+        if critical {
+            damage *= CRITICAL_HIT;
+        }
+        if !self.damage(victim, damage, DamageCause::Attack(id)) {
+            return;
+        }
+
+        if critical {
+            if let Some(entity) = self.entities.get_mut(target) {
+                entity.animate(Animation::CriticalEffect);
+            }
+        }
+        // players move on their own, so they're the ones told they were hit
+        let distance = (dx * dx + dz * dz).sqrt();
+        let (x, z) = if distance > 0.0 {
+            (dx / distance * KNOCKBACK, dz / distance * KNOCKBACK)
+        } else {
+            (0.0, 0.0)
+        };
+        self.send(ProxyBoundPacket::EntityVelocity {
+            player: victim,
+            entity_id: target,
+            velocity: (x, KNOCKBACK, z),
+        });
+    }
+
+    pub(super) fn hurt_in_void(&mut self) {
+        let falling: Vec<_> = self
+            .players
+            .iter()
+            .filter(|(_, player)| {
+                self.entities
+                    .get(player.entity)
+                    .is_some_and(|entity| entity.y < VOID_DEPTH)
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for id in falling {
+            self.damage(id, VOID_DAMAGE, DamageCause::Void);
+        }
+    }
+
+    pub(super) fn sync_health(&mut self) {
+        for (id, player) in self.players.iter_mut() {
+            self.outgoing.extend(player.health.sync(*id));
+        }
+    }
+
+    /// Hurts a player who landed after falling some blocks.
+    pub(super) fn land(&mut self, id: u128, fall: f64) {
+        if fall > SAFE_FALL {
+            self.damage(id, (fall - SAFE_FALL).ceil() as f32, DamageCause::Fall);
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use makar_protocol::*;
+
+use super::Game;
+use crate::{
+    entity::EYE_HEIGHT,
+    inventory::{Tool, ToolKind, HOTBAR},
+    world::{self, blocks, Block},
+};
+
+/// Farthest from their eyes players dig blocks.
+const DIG_REACH: f64 = 6.0;
+/// Share of a block players have to have dug for it to break, their
+/// client running a little ahead of the server.
+const DIG_LENIENCY: f32 = 0.7;
+/// The effect of a block breaking, with its particles and sound.
+const BLOCK_BREAK_EFFECT: i32 = 2001;
+
+/// A block a player is digging.
+pub(super) struct Digging {
+    position: BlockPosition,
+    started: u64,
+    /// The cracks other players were last shown, -1 for none.
+    stage: i8,
+}
+
+impl Game {
+    /// Whether a block is within reach of a player, from a height above
+    /// their feet.
+    pub(super) fn within_reach(
+        &self,
+        id: u128,
+        position: BlockPosition,
+        height: f64,
+        reach: f64,
+    ) -> bool {
+        let Some(entity) = self
+            .players
+            .get(&id)
+            .and_then(|player| self.entities.get(player.entity))
+        else {
+            return false;
+        };
+        let distance = (entity.x - position.x as f64 - 0.5).powi(2)
+            + (entity.y + height - position.y as f64 - 0.5).powi(2)
+            + (entity.z - position.z as f64 - 0.5).powi(2);
+        distance <= reach * reach
+    }
+
+    fn dig_strength(&self, id: u128, position: BlockPosition) -> f32 {
+        let Some(player) = self.players.get(&id) else {
+            return 0.0;
+        };
+        let Some(entity) = self.entities.get(player.entity) else {
+            return 0.0;
+        };
+        let eyes = BlockPosition {
+            x: entity.x.floor() as i32,
+            y: (entity.y + EYE_HEIGHT).floor() as i32,
+            z: entity.z.floor() as i32,
+        };
+        let in_water = matches!(self.block_at(id, eyes).id, 8 | 9);
+        blocks::dig_strength(
+            self.block_at(id, position).id,
+            player.inventory.held_item().as_ref(),
+            entity.on_ground,
+            in_water,
+        )
+    }
+
+    /// Tells a player what a block really is after their client changed it.
+    pub(super) fn resend_block(&mut self, player: u128, position: BlockPosition) {
+        let block = self.block_at(player, position).state();
+        self.send(ProxyBoundPacket::BlockChange {
+            player,
+            position,
+            block,
+        });
+    }
+
+    /// Starts digging a block, creative players and blocks broken in a
+    /// single hit breaking right away.
+    pub(super) fn start_digging(&mut self, id: u128, position: BlockPosition) {
+        self.stop_digging(id);
+        let Some(player) = self.players.get(&id) else {
+            return;
+        };
+        let gamemode = player.gamemode;
+        let sword = player
+            .inventory
+            .held_item()
+            .and_then(|item| Tool::of(&item))
+            .is_some_and(|tool| tool.kind == ToolKind::Sword);
+        let allowed = matches!(gamemode, Gamemode::Survival | Gamemode::Creative)
+            && !player.health.is_dead()
+            && self.within_reach(id, position, EYE_HEIGHT, DIG_REACH)
+            && self.block_at(id, position) != Block::AIR;
+        if !allowed {
+            self.resend_block(id, position);
+            return;
+        }
+
+        // creative players don't break blocks with swords
+        if gamemode == Gamemode::Creative {
+            match sword {
+                true => self.resend_block(id, position),
+                false => self.break_block(id, position),
+            }
+            return;
+        }
+        let strength = self.dig_strength(id, position);
+        if strength >= 1.0 {
+            self.break_block(id, position);
+        } else if strength > 0.0 {
+            let started = self.scheduler.tick();
+            if let Some(player) = self.players.get_mut(&id) {
+                player.digging = Some(Digging {
+                    position,
+                    started,
+                    stage: -1,
+                });
+            }
+        } else {
+            self.resend_block(id, position);
+        }
+    }
+
+    pub(super) fn stop_digging(&mut self, id: u128) {
+        let Some(digging) = self
+            .players
+            .get_mut(&id)
+            .and_then(|player| player.digging.take())
+        else {
+            return;
+        };
+        if digging.stage >= 0 {
+            self.show_cracks(id, digging.position, -1);
+        }
+    }
+
+    /// Breaks the block a player says they're done digging, if they've been
+    /// at it long enough.
+    pub(super) fn finish_digging(&mut self, id: u128, position: BlockPosition) {
+        let started = self
+            .players
+            .get(&id)
+            .and_then(|player| player.digging.as_ref())
+            .filter(|digging| digging.position == position)
+            .map(|digging| digging.started);
+        self.stop_digging(id);
+        let dug = started.is_some_and(|started| {
+            let ticks = self.scheduler.tick() - started + 1;
+            ticks as f32 * self.dig_strength(id, position) >= DIG_LENIENCY
+        });
+        match dug {
+            true => self.break_block(id, position),
+            false => self.resend_block(id, position),
+        }
+    }
+
+    /// Breaks a block along with the other half of doors, beds and tall
+    /// plants, dropping its items and wearing out the tool in survival.
+    fn break_block(&mut self, id: u128, position: BlockPosition) {
+        let Some(player) = self.players.get(&id) else {
+            return;
+        };
+        let Some(world) = self.worlds.get_mut(&player.world) else {
+            return;
+        };
+        let block = world.get_block(position);
+        if block == Block::AIR {
+            return;
+        }
+        let survival = player.gamemode == Gamemode::Survival;
+        let held = player.inventory.held_item();
+
+        let mut broken = vec![(position, block)];
+        if let Some(other) = blocks::other_half(block, position) {
+            let other_block = world.get_block(other);
+            if other_block.id == block.id {
+                broken.push((other, other_block));
+            }
+        }
+        for (position, block) in broken {
+            world.set_block(position, Block::AIR);
+            if !survival {
+                continue;
+            }
+            for item in blocks::drops(block, held.as_ref()) {
+                let (x, y, z) = (position.x as f64, position.y as f64, position.z as f64);
+                self.entities
+                    .spawn_item(item, &player.world, x + 0.5, y + 0.5, z + 0.5);
+            }
+        }
+
+        // the player breaking the block already saw it break
+        let chunk = world::chunk_pos(position);
+        let data = blocks::effect_state(block);
+        let world = &player.world;
+        for (viewer, player) in &self.players {
+            if *viewer != id && player.sees(world, chunk) {
+                self.outgoing.push(ProxyBoundPacket::Effect {
+                    player: *viewer,
+                    effect: BLOCK_BREAK_EFFECT,
+                    position,
+                    data,
+                });
+            }
+        }
+
+        let instant = blocks::hardness(block.id) == Some(0.0);
+        if let (true, false, Some(held)) = (survival, instant, held) {
+            if let (Some(tool), Some(player)) = (Tool::of(&held), self.players.get_mut(&id)) {
+                let slot = HOTBAR.start + player.inventory.held() as usize;
+                player.inventory.set(slot, tool.wear(held));
+            }
+        }
+    }
+
+    fn show_cracks(&mut self, id: u128, position: BlockPosition, stage: i8) {
+        let Some(digger) = self.players.get(&id) else {
+            return;
+        };
+        let chunk = world::chunk_pos(position);
+        for (viewer, player) in &self.players {
+            if *viewer != id && player.sees(&digger.world, chunk) {
+                self.outgoing.push(ProxyBoundPacket::BlockBreakAnimation {
+                    player: *viewer,
+                    entity_id: digger.entity,
+                    position,
+                    stage,
+                });
+            }
+        }
+    }
+
+    /// Shows other players the cracks of the blocks being dug as they grow.
+    pub(super) fn update_digging(&mut self) {
+        let tick = self.scheduler.tick();
+        let digging: Vec<_> = self
+            .players
+            .iter()
+            .filter_map(|(id, player)| {
+                let digging = player.digging.as_ref()?;
+                Some((*id, digging.position, digging.started))
+            })
+            .collect();
+        for (id, position, started) in digging {
+            let progress = (tick - started) as f32 * self.dig_strength(id, position);
+            let stage = ((progress * 10.0) as i8).min(9);
+            let Some(digging) = self
+                .players
+                .get_mut(&id)
+                .and_then(|player| player.digging.as_mut())
+            else {
+                continue;
+            };
+            if digging.stage != stage {
+                digging.stage = stage;
+                self.show_cracks(id, position, stage);
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use makar_protocol::*;
+
+use super::Game;
+
+pub(super) fn abilities(gamemode: Gamemode) -> Abilities {
+    let creative = gamemode == Gamemode::Creative;
+    let spectator = gamemode == Gamemode::Spectator;
+    Abilities {
+        invulnerable: creative || spectator,
+        flying: spectator,
+        allow_flying: creative || spectator,
+        creative_mode: creative,
+        flying_speed: 0.05,
+        walking_speed: 0.1,
+    }
+}
+
+impl Game {
+    /// Switches a player's gamemode, along with what they're allowed to do
+    /// and how they're listed.
+    pub fn set_gamemode(&mut self, id: u128, gamemode: Gamemode) {
+        let Some(player) = self.players.get_mut(&id) else {
+            return;
+        };
+        player.gamemode = gamemode;
+        self.stop_digging(id);
+        self.tab_list.set_gamemode(id, gamemode);
+        self.send(ProxyBoundPacket::ChangeGameState {
+            player: id,
+            change: GameStateChange::ChangeGamemode(gamemode),
+        });
+        self.send(ProxyBoundPacket::PlayerAbilities {
+            player: id,
+            abilities: abilities(gamemode),
+        });
+    }
+
+    /// Rolls the credits for a player. Clients close them on their own and
+    /// ask to respawn, which living players are left alone for, so they stay
+    /// where they are.
+    pub fn show_credits(&mut self, player: u128) {
+        self.send(ProxyBoundPacket::ChangeGameState {
+            player,
+            change: GameStateChange::EnterCredits,
+        });
+    }
+
+    pub fn show_demo_message(&mut self, player: u128, message: DemoMessage) {
+        self.send(ProxyBoundPacket::ChangeGameState {
+            player,
+            change: GameStateChange::DemoMessage(message),
+        });
+    }
+}
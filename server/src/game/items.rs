@@ -0,0 +1,263 @@
+use makar_protocol::*;
+
+use super::{
+    placing::{ENTITY_HEIGHT, ENTITY_WIDTH},
+    Game,
+};
+use crate::{
+    entity::{Entity, EntityKind, EYE_HEIGHT},
+    inventory::HOTBAR,
+    world::{blocks, World},
+};
+
+/// Speed of the items players throw, in blocks per tick.
+const THROW_SPEED: f64 = 0.3;
+/// Ticks before players can pick up the items thrown or dropped when
+/// someone dies, so they don't get them back right away.
+pub(super) const THROWN_PICKUP_DELAY: u32 = 40;
+/// Ticks items lie around before despawning, five minutes.
+const DESPAWN_AGE: u32 = 6000;
+/// How far past their box players pick items up, sideways then up and
+/// down, as the vanilla server does.
+const PICKUP_RANGE: (f64, f64) = (1.0, 0.5);
+/// Width and height of the box of items.
+const ITEM_SIZE: f64 = 0.25;
+/// How much faster items fall every tick, in blocks per tick.
+const GRAVITY: f64 = 0.04;
+/// Share of their speed items keep every tick, in the air then sliding on
+/// the ground.
+const DRAG: f64 = 0.98;
+const GROUND_DRAG: f64 = 0.6 * 0.98;
+/// Speed under which items stop, so those lying around aren't updated.
+const MIN_SPEED: f64 = 0.003;
+/// Depth below which items fall out of the world.
+const VOID_DEPTH: f64 = -64.0;
+
+/// Moves an item the way it falls and slides, stopping against the blocks
+/// in its way, its client doing the same.
+fn fall(item: &mut Entity, world: &World) {
+    let solid = |x: f64, y: f64, z: f64| {
+        blocks::collides(world.get_block(BlockPosition {
+            x: x.floor() as i32,
+            y: y.floor() as i32,
+            z: z.floor() as i32,
+        }))
+    };
+    let (mut vx, mut vy, mut vz) = item.velocity;
+    vy -= GRAVITY;
+    if solid(item.x + vx, item.y, item.z) {
+        vx = 0.0;
+    }
+    if solid(item.x + vx, item.y, item.z + vz) {
+        vz = 0.0;
+    }
+    let (x, z) = (item.x + vx, item.z + vz);
+    let mut y = item.y + vy;
+    item.on_ground = vy < 0.0 && solid(x, y, z);
+    if item.on_ground {
+        y = y.floor() + 1.0;
+        vy = 0.0;
+    } else if vy > 0.0 && solid(x, y + ITEM_SIZE, z) {
+        y = item.y;
+        vy = 0.0;
+    }
+    let drag = if item.on_ground { GROUND_DRAG } else { DRAG };
+    let slow = |v: f64| if v.abs() < MIN_SPEED { 0.0 } else { v };
+    item.velocity = (slow(vx * drag), slow(vy * DRAG), slow(vz * drag));
+    item.teleport(x, y, z);
+}
+
+/// Whether a player is close enough to an item to pick it up.
+fn within_pickup_range(player: &Entity, item: &Entity) -> bool {
+    let reach = ENTITY_WIDTH / 2.0 + PICKUP_RANGE.0 + ITEM_SIZE / 2.0;
+    (item.x - player.x).abs() <= reach
+        && (item.z - player.z).abs() <= reach
+        && item.y + ITEM_SIZE >= player.y - PICKUP_RANGE.1
+        && item.y <= player.y + ENTITY_HEIGHT + PICKUP_RANGE.1
+}
+
+impl Game {
+    /// Throws items from a player's eyes, the way they look.
+    pub fn drop_item(&mut self, player: u128, item: ItemStack) {
+        let Some(entity) = self
+            .players
+            .get(&player)
+            .and_then(|player| self.entities.get(player.entity))
+        else {
+            return;
+        };
+        let (yaw, pitch) = (
+            (entity.yaw as f64).to_radians(),
+            (entity.pitch as f64).to_radians(),
+        );
+        let (x, y, z) = (entity.x, entity.y + EYE_HEIGHT - 0.3, entity.z);
+        let world = entity.world().to_string();
+        let item = self.entities.spawn_item(item, &world, x, y, z);
+        item.pickup_delay = THROWN_PICKUP_DELAY;
+        item.velocity = (
+            -yaw.sin() * pitch.cos() * THROW_SPEED,
+            -pitch.sin() * THROW_SPEED + 0.1,
+            yaw.cos() * pitch.cos() * THROW_SPEED,
+        );
+    }
+
+    pub(super) fn drop_held(&mut self, id: u128, stack: bool) {
+        let Some(player) = self.players.get_mut(&id) else {
+            return;
+        };
+        let slot = HOTBAR.start + player.inventory.held() as usize;
+        let Some(mut item) = player.inventory.get(slot) else {
+            return;
+        };
+        if player.gamemode == Gamemode::Spectator || player.health.is_dead() {
+            return;
+        }
+        let count = if stack { item.count } else { 1 };
+        let mut dropped = item.clone();
+        dropped.count = count;
+        item.count -= count;
+        player
+            .inventory
+            .set(slot, Some(item).filter(|item| item.count > 0));
+        self.drop_item(id, dropped);
+    }
+
+    /// Moves and ages the items lying around, despawning the old ones, and
+    /// lets the players close to the others pick them up.
+    pub(super) fn update_items(&mut self) {
+        let mut despawned = Vec::new();
+        for item in self.entities.iter_mut() {
+            if item.kind() != EntityKind::Item {
+                continue;
+            }
+            // items in chunks that aren't loaded stay where they are
+            let Some(world) = self.worlds.get(item.world()) else {
+                continue;
+            };
+            let (x, z) = item.chunk();
+            if world.chunk(x, z).is_none() {
+                continue;
+            }
+            fall(item, world);
+            item.age += 1;
+            item.pickup_delay = item.pickup_delay.saturating_sub(1);
+            if item.age >= DESPAWN_AGE || item.y < VOID_DEPTH {
+                despawned.push(item.id());
+            }
+        }
+        for id in despawned {
+            self.entities.remove(id);
+        }
+
+        let collectors: Vec<_> = self
+            .players
+            .iter()
+            .filter(|(_, player)| {
+                player.gamemode != Gamemode::Spectator && !player.health.is_dead()
+            })
+            .map(|(id, player)| (*id, player.entity))
+            .collect();
+        for (id, collector) in collectors {
+            self.pick_up_items(id, collector);
+        }
+    }
+
+    /// Adds the items around a player to their inventory, those that don't
+    /// fit staying on the ground.
+    fn pick_up_items(&mut self, id: u128, collector: i32) {
+        let Some(entity) = self.entities.get(collector) else {
+            return;
+        };
+        let items: Vec<_> = self
+            .entities
+            .iter()
+            .filter(|item| {
+                item.kind() == EntityKind::Item
+                    && item.pickup_delay == 0
+                    && item.world() == entity.world()
+                    && within_pickup_range(entity, item)
+            })
+            .filter_map(|item| Some((item.id(), item.item()?.clone())))
+            .collect();
+        for (item, stack) in items {
+            let Some(player) = self.players.get_mut(&id) else {
+                return;
+            };
+            match player.inventory.add(stack.clone()) {
+                None => {
+                    let packets = self.tracker.collect(item, collector);
+                    self.outgoing.extend(packets);
+                    self.entities.remove(item);
+                }
+                Some(left) if left.count < stack.count => {
+                    if let Some(item) = self.entities.get_mut(item) {
+                        item.set_item(left);
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        entity::Entities,
+        world::{generator::FlatGenerator, Dimension},
+    };
+
+    fn torch(entities: &mut Entities, x: f64, y: f64, z: f64) -> &mut Entity {
+        let stack = ItemStack {
+            id: 50,
+            count: 1,
+            damage: 0,
+            meta: ItemMeta::default(),
+        };
+        entities.spawn_item(stack, "world", x, y, z)
+    }
+
+    #[test]
+    fn items_fall_onto_the_ground() {
+        let mut world = World::new(Dimension::Overworld, Arc::new(FlatGenerator::default()));
+        world.load_chunk(0, 0);
+        let mut entities = Entities::default();
+        let item = torch(&mut entities, 8.5, 100.0, 8.5);
+        item.velocity = (0.1, 0.2, 0.0);
+        for _ in 0..200 {
+            fall(item, &world);
+        }
+        assert!(item.on_ground);
+        assert_eq!(item.velocity, (0.0, 0.0, 0.0));
+        assert!(item.x > 9.0);
+        let below = BlockPosition {
+            x: item.x as i32,
+            y: item.y as i32 - 1,
+            z: item.z as i32,
+        };
+        assert!(blocks::collides(world.get_block(below)));
+        assert!(!blocks::collides(world.get_block(BlockPosition {
+            y: below.y + 1,
+            ..below
+        })));
+    }
+
+    #[test]
+    fn picks_up_items_a_block_away() {
+        let mut entities = Entities::default();
+        let player = entities
+            .spawn(EntityKind::Player(1), "world", 0.5, 64.0, 0.5)
+            .id();
+        let near = torch(&mut entities, 1.8, 64.0, 0.5).id();
+        let far = torch(&mut entities, 2.5, 64.0, 0.5).id();
+        let above = torch(&mut entities, 0.5, 66.5, 0.5).id();
+        let player = entities.get(player).unwrap();
+        let within = |item| within_pickup_range(player, entities.get(item).unwrap());
+        assert!(within(near));
+        assert!(!within(far));
+        assert!(!within(above));
+    }
+}
@@ -0,0 +1,215 @@
+use makar_protocol::*;
+
+use super::Game;
+use crate::{
+    boss_bar::{Boss, BossBar},
+    title::Title,
+};
+
+/// Ticks between resending messages kept on the action bar, before they
+/// start fading out.
+const ACTION_BAR_REFRESH: u64 = 40;
+/// Ticks a message sent once stays on the action bar.
+const ACTION_BAR_DURATION: u64 = 60;
+
+impl Game {
+    /// Shows a message above a player's hotbar, over the one kept there for
+    /// a while.
+    pub fn send_action_bar(&mut self, player: u128, message: Chat) {
+        let tick = self.scheduler.tick();
+        let Some(player_data) = self.players.get_mut(&player) else {
+            return;
+        };
+        if let Some((_, refresh)) = &mut player_data.action_bar {
+            *refresh = tick + ACTION_BAR_DURATION;
+        }
+        self.send(ProxyBoundPacket::ChatMessage {
+            player,
+            json: message,
+            position: ChatPosition::ActionBar,
+        });
+    }
+
+    /// Keeps a message above a player's hotbar, or clears it when unset.
+    pub fn set_action_bar(&mut self, player: u128, message: Option<Chat>) {
+        let tick = self.scheduler.tick();
+        let Some(player_data) = self.players.get_mut(&player) else {
+            return;
+        };
+        let json = match message {
+            Some(message) => {
+                player_data.action_bar = Some((message.clone(), tick + ACTION_BAR_REFRESH));
+                message
+            }
+            None if player_data.action_bar.take().is_some() => Chat::default(),
+            None => return,
+        };
+        self.send(ProxyBoundPacket::ChatMessage {
+            player,
+            json,
+            position: ChatPosition::ActionBar,
+        });
+    }
+
+    pub fn boss_bar(&mut self, player: u128) -> Option<&mut BossBar> {
+        self.players.get_mut(&player)?.boss_bar.as_mut()
+    }
+
+    /// Shows a bar at the top of a player's screen, or hides it when unset.
+    pub fn set_boss_bar(&mut self, player: u128, bar: Option<BossBar>) {
+        if let Some(player) = self.players.get_mut(&player) {
+            player.boss_bar = bar;
+        }
+    }
+
+    /// Shows a title to a player right away, dropping their queued ones.
+    pub fn show_title(&mut self, player: u128, title: Title) {
+        let tick = self.scheduler.tick();
+        if let Some(player_data) = self.players.get_mut(&player) {
+            let actions = player_data.titles.show(tick, title);
+            self.send(ProxyBoundPacket::Title { player, actions });
+        }
+    }
+
+    /// Shows a title to every player, in their language.
+    pub fn broadcast_title(&mut self, title: impl Fn(&str) -> Title) {
+        let tick = self.scheduler.tick();
+        for (id, player) in self.players.iter_mut() {
+            let actions = player.titles.show(tick, title(&player.locale));
+            self.outgoing.push(ProxyBoundPacket::Title {
+                player: *id,
+                actions,
+            });
+        }
+    }
+
+    /// Shows a title to a player once the ones before it are gone.
+    pub fn queue_title(&mut self, player: u128, title: Title) {
+        if let Some(player) = self.players.get_mut(&player) {
+            player.titles.queue(title);
+        }
+    }
+
+    /// Queues a title for every second counting down to 1, each staying on
+    /// screen until the next one without fading, save for the last one
+    /// fading out.
+    pub fn countdown(&mut self, player: u128, seconds: u32, title: impl Fn(u32) -> Title) {
+        for second in (1..=seconds).rev() {
+            let title = Title {
+                fade_in: 0,
+                stay: 20,
+                ..title(second)
+            };
+            let title = match second {
+                1 => title,
+                _ => Title {
+                    fade_out: 0,
+                    ..title
+                },
+            };
+            self.queue_title(player, title);
+        }
+    }
+
+    /// Hides a player's title, dropping the queued ones.
+    pub fn clear_titles(&mut self, player: u128) {
+        if let Some(player_data) = self.players.get_mut(&player) {
+            let actions = player_data.titles.clear();
+            self.send(ProxyBoundPacket::Title { player, actions });
+        }
+    }
+
+    pub(super) fn welcome(&mut self, player: u128, locale: &str) {
+        let message = match locale {
+            "fr_FR" => "bonjour, bienvenue sur le serveur!",
+            _ => "hello, welcome to the server!",
+        };
+        self.send(ProxyBoundPacket::ChatMessage {
+            player,
+            json: Chat {
+                text: message.to_string(),
+                color: Some("blue".to_string()),
+                ..Default::default()
+            },
+            position: ChatPosition::System,
+        });
+
+        let message = match locale {
+            "fr_FR" => "Salut!",
+            _ => "Hey there!",
+        };
+        let title = Chat {
+            text: message.to_string(),
+            color: Some("aqua".to_string()),
+            ..Default::default()
+        };
+        self.show_title(
+            player,
+            Title {
+                title,
+                subtitle: None,
+                fade_in: 30,
+                stay: 1000,
+                fade_out: 30,
+            },
+        );
+    }
+
+    pub(super) fn refresh_action_bars(&mut self) {
+        let tick = self.scheduler.tick();
+        for (id, player) in self.players.iter_mut() {
+            let Some((message, refresh)) = &mut player.action_bar else {
+                continue;
+            };
+            if *refresh <= tick {
+                *refresh = tick + ACTION_BAR_REFRESH;
+                self.outgoing.push(ProxyBoundPacket::ChatMessage {
+                    player: *id,
+                    json: message.clone(),
+                    position: ChatPosition::ActionBar,
+                });
+            }
+        }
+    }
+
+    pub(super) fn show_queued_titles(&mut self) {
+        let tick = self.scheduler.tick();
+        for (id, player) in self.players.iter_mut() {
+            let actions = player.titles.next(tick);
+            if !actions.is_empty() {
+                self.outgoing.push(ProxyBoundPacket::Title {
+                    player: *id,
+                    actions,
+                });
+            }
+        }
+    }
+
+    /// Spawns, moves and destroys the withers showing players their boss
+    /// bar.
+    pub(super) fn update_boss_bars(&mut self) {
+        for (id, player) in self.players.iter_mut() {
+            let Some(entity) = self.entities.get(player.entity) else {
+                continue;
+            };
+            let (feet, yaw, pitch) = ((entity.x, entity.y, entity.z), entity.yaw, entity.pitch);
+            match (&player.boss_bar, &mut player.boss) {
+                (Some(bar), Some(boss)) => {
+                    let packets = boss.update(*id, bar, feet, yaw, pitch);
+                    self.outgoing.extend(packets);
+                }
+                (Some(bar), None) => {
+                    let entity_id = self.entities.reserve_id();
+                    let (boss, packet) = Boss::spawn(*id, entity_id, bar, feet, yaw, pitch);
+                    player.boss = Some(boss);
+                    self.outgoing.push(packet);
+                }
+                (None, Some(boss)) => {
+                    self.outgoing.push(boss.destroy(*id));
+                    player.boss = None;
+                }
+                (None, None) => {}
+            }
+        }
+    }
+}
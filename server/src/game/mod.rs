@@ -0,0 +1,657 @@
+mod chunks;
+mod combat;
+mod digging;
+mod gamemode;
+mod items;
+mod messages;
+mod placing;
+mod stats;
+mod windows;
+mod worlds;
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{error, warn};
+use makar_protocol::*;
+
+use crate::{
+    boss_bar::{Boss, BossBar},
+    entity::{Entities, EntityKind, Tracker, SKIN_PARTS},
+    health::Health,
+    inventory::{Inventory, Windows},
+    menu::Menu,
+    scheduler::Scheduler,
+    scoreboard::Scoreboard,
+    tab_list::TabList,
+    title::Titles,
+    view::View,
+    world::{Block, ChunkPool, World},
+};
+use chunks::{chunk_pos, EVICTION_PERIOD};
+use digging::Digging;
+use gamemode::abilities;
+pub use stats::TickStats;
+pub use worlds::WorldError;
+
+/// How long a tick lasts at 20 ticks per second.
+pub const TICK_DURATION: Duration = Duration::from_millis(50);
+/// Ticks between automatic saves of the world, every 45 seconds.
+const AUTOSAVE_PERIOD: u64 = 900;
+/// Ticks between the time updates keeping clients in sync, which advance
+/// the time on their own in between.
+const TIME_UPDATE_PERIOD: u64 = 20;
+
+/// Farthest players can move between two movement packets, in blocks.
+const MAX_MOVE: f64 = 10.0;
+/// Coordinates past which players are out of the world.
+const MAX_COORDINATE: f64 = 3.0e7;
+/// The name of the world games start with, where players join.
+pub const DEFAULT_WORLD: &str = "world";
+/// The locale of players until their client tells theirs.
+const DEFAULT_LOCALE: &str = "en_US";
+
+pub struct Player {
+    username: String,
+    locale: String,
+    gamemode: Gamemode,
+    world: String,
+    view: View,
+    /// The entity other players see.
+    entity: i32,
+    /// Where the player was teleported to, until their client confirms it.
+    teleport: Option<(f64, f64, f64)>,
+    /// The scoreboard shown to the player instead of the shared one.
+    scoreboard: Option<Scoreboard>,
+    /// The scoreboard the player was last sent.
+    sent_scoreboard: Scoreboard,
+    /// The message kept on the action bar, with the tick it's resent on.
+    action_bar: Option<(Chat, u64)>,
+    boss_bar: Option<BossBar>,
+    /// The wither showing the boss bar the player was last sent.
+    boss: Option<Boss>,
+    titles: Titles,
+    inventory: Inventory,
+    windows: Windows,
+    /// The menu shown in the open window.
+    menu: Option<Menu>,
+    digging: Option<Digging>,
+    health: Health,
+}
+
+impl Player {
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// The language the player's client is in, like `en_US`.
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    pub fn gamemode(&self) -> Gamemode {
+        self.gamemode
+    }
+
+    pub fn world(&self) -> &str {
+        &self.world
+    }
+
+    pub fn inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    pub fn health(&self) -> &Health {
+        &self.health
+    }
+
+    pub fn entity(&self) -> i32 {
+        self.entity
+    }
+
+    pub fn view(&self) -> &View {
+        &self.view
+    }
+
+    /// Whether the player has a chunk of a world.
+    fn sees(&self, world: &str, chunk: (i32, i32)) -> bool {
+        self.world == world && self.view.is_loaded(chunk)
+    }
+}
+
+/// A player waiting for the spawn area to load before joining.
+struct Joining {
+    id: u128,
+    username: String,
+    /// The settings their client sent in the meantime.
+    settings: Option<ServerBoundPacket>,
+}
+
+/// The state of the game, changed one tick at a time.
+pub struct Game {
+    /// The worlds hosted, by name.
+    worlds: HashMap<String, World>,
+    /// Workers generating the chunks of every world.
+    pool: Arc<ChunkPool>,
+    entities: Entities,
+    tracker: Tracker,
+    players: HashMap<u128, Player>,
+    joining: Vec<Joining>,
+    /// Players moving to another world once its spawn area is loaded.
+    arriving: HashMap<u128, String>,
+    tab_list: TabList,
+    /// The scoreboard of the players who don't have their own.
+    scoreboard: Scoreboard,
+    scheduler: Scheduler<Game>,
+    /// Packets waiting to be sent to the proxy.
+    outgoing: Vec<ProxyBoundPacket>,
+    stats: TickStats,
+    /// Farthest players can see in chunks, whatever their own settings.
+    view_distance: i32,
+}
+
+impl Game {
+    pub fn new(mut world: World, view_distance: i32) -> Self {
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+        let pool = Arc::new(ChunkPool::new(threads));
+        world.set_pool(pool.clone());
+        world.request_spawn_area();
+
+        let mut scheduler = Scheduler::default();
+        scheduler.run_repeating(AUTOSAVE_PERIOD, AUTOSAVE_PERIOD, |game: &mut Game| {
+            game.save()
+        });
+        scheduler.run_repeating(TIME_UPDATE_PERIOD, TIME_UPDATE_PERIOD, |game: &mut Game| {
+            game.send_time(|_| true)
+        });
+        scheduler.run_repeating(EVICTION_PERIOD, EVICTION_PERIOD, |game: &mut Game| {
+            game.evict_chunks()
+        });
+
+        Self {
+            worlds: HashMap::from([(DEFAULT_WORLD.to_string(), world)]),
+            pool,
+            entities: Entities::default(),
+            tracker: Tracker::default(),
+            players: HashMap::new(),
+            joining: Vec::new(),
+            arriving: HashMap::new(),
+            tab_list: TabList::default(),
+            scoreboard: Scoreboard::default(),
+            scheduler,
+            outgoing: Vec::new(),
+            stats: TickStats::default(),
+            view_distance,
+        }
+    }
+
+    fn block_at(&self, player: u128, position: BlockPosition) -> Block {
+        self.players
+            .get(&player)
+            .and_then(|player| self.worlds.get(&player.world))
+            .map_or(Block::AIR, |world| world.get_block(position))
+    }
+
+    pub fn entities(&self) -> &Entities {
+        &self.entities
+    }
+
+    pub fn entities_mut(&mut self) -> &mut Entities {
+        &mut self.entities
+    }
+
+    pub fn players(&self) -> &HashMap<u128, Player> {
+        &self.players
+    }
+
+    pub fn tab_list(&mut self) -> &mut TabList {
+        &mut self.tab_list
+    }
+
+    pub fn scoreboard(&mut self) -> &mut Scoreboard {
+        &mut self.scoreboard
+    }
+
+    /// The scoreboard of a single player, starting as a copy of the shared
+    /// one the first time it's used.
+    pub fn player_scoreboard(&mut self, player: u128) -> Option<&mut Scoreboard> {
+        let player = self.players.get_mut(&player)?;
+        Some(
+            player
+                .scoreboard
+                .get_or_insert_with(|| self.scoreboard.clone()),
+        )
+    }
+
+    /// Shows a player the shared scoreboard again.
+    pub fn reset_player_scoreboard(&mut self, player: u128) {
+        if let Some(player) = self.players.get_mut(&player) {
+            player.scoreboard = None;
+        }
+    }
+
+    pub fn scheduler(&mut self) -> &mut Scheduler<Game> {
+        &mut self.scheduler
+    }
+
+    pub fn stats(&self) -> &TickStats {
+        &self.stats
+    }
+
+    /// Queues a packet to send to the proxy at the end of the tick.
+    pub fn send(&mut self, packet: ProxyBoundPacket) {
+        self.outgoing.push(packet);
+    }
+
+    pub fn take_outgoing(&mut self) -> Vec<ProxyBoundPacket> {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    pub fn broadcast(&mut self, packet: impl Fn(u128) -> ProxyBoundPacket) {
+        let packets = self.players.keys().map(|player| packet(*player));
+        self.outgoing.extend(packets);
+    }
+
+    pub fn handle(&mut self, packet: ServerBoundPacket) {
+        match packet {
+            ServerBoundPacket::JoinGameRequest { id, username } => self.join(id, username),
+            ServerBoundPacket::LeaveGame { player: id } => {
+                self.joining.retain(|joining| joining.id != id);
+                self.arriving.remove(&id);
+                self.stop_digging(id);
+                if let Some(player) = self.players.remove(&id) {
+                    self.entities.remove(player.entity);
+                    self.tab_list.remove(id);
+                }
+            }
+            ServerBoundPacket::ClientSettings {
+                player,
+                locale,
+                view_distance,
+                skin_parts,
+            } => {
+                let Some(player_data) = self.players.get_mut(&player) else {
+                    if let Some(joining) = self.joining.iter_mut().find(|j| j.id == player) {
+                        joining.settings = Some(ServerBoundPacket::ClientSettings {
+                            player,
+                            locale,
+                            view_distance,
+                            skin_parts,
+                        });
+                    }
+                    return;
+                };
+                player_data.locale.clone_from(&locale);
+                let center = player_data.view.center();
+                if let Some(entity) = self.entities.get_mut(player_data.entity) {
+                    let skin_parts = MetadataValue::Byte(skin_parts as i8);
+                    entity.metadata.insert(SKIN_PARTS, skin_parts);
+                }
+                let distance = (view_distance as i32).clamp(2, self.view_distance.max(2));
+                self.move_view(player, center, distance);
+                self.welcome(player, &locale);
+            }
+            ServerBoundPacket::ChatMessage { player, message } => {
+                let author = match self.players.get(&player) {
+                    Some(v) => v.username.clone(),
+                    None => return,
+                };
+                self.broadcast(|player| ProxyBoundPacket::ChatMessage {
+                    player,
+                    json: Chat {
+                        text: format!("<{author}> {message}"),
+                        ..Default::default()
+                    },
+                    position: ChatPosition::Chat,
+                });
+            }
+            ServerBoundPacket::PlayerMovement {
+                player,
+                position,
+                look,
+                on_ground,
+            } => self.movement(player, position, look, on_ground),
+            ServerBoundPacket::SwingArm { player } => {
+                let entity = self.players.get(&player).map(|p| p.entity);
+                if let Some(entity) = entity.and_then(|id| self.entities.get_mut(id)) {
+                    entity.animate(Animation::SwingArm);
+                }
+            }
+            ServerBoundPacket::HeldItemChange { player, slot } => {
+                if let Some(player) = self.players.get_mut(&player) {
+                    player.inventory.set_held(slot);
+                }
+            }
+            ServerBoundPacket::ClickWindow {
+                player,
+                window_id,
+                slot,
+                button,
+                action,
+                mode,
+                item,
+            } => self.click_window(player, window_id, slot, button, action, mode, item),
+            ServerBoundPacket::ConfirmTransaction {
+                player,
+                window_id,
+                action,
+                accepted,
+            } => {
+                if let (Some(player), true) = (self.players.get_mut(&player), accepted) {
+                    player.windows.confirm(window_id, action);
+                }
+            }
+            // clients close whatever window they have open
+            ServerBoundPacket::CloseWindow { player, .. } => {
+                self.window_closed(player);
+            }
+            ServerBoundPacket::CreativeInventoryAction { player, slot, item } => {
+                self.creative_action(player, slot, item)
+            }
+            ServerBoundPacket::PlayerDigging {
+                player,
+                status,
+                position,
+                ..
+            } => match status {
+                DiggingStatus::Started => self.start_digging(player, position),
+                DiggingStatus::Cancelled => self.stop_digging(player),
+                DiggingStatus::Finished => self.finish_digging(player, position),
+                DiggingStatus::DropStack => self.drop_held(player, true),
+                DiggingStatus::DropItem => self.drop_held(player, false),
+            },
+            ServerBoundPacket::PlayerBlockPlacement {
+                player,
+                position,
+                face: Some(face),
+                cursor: (_, cursor_y, _),
+                ..
+            } => {
+                if !self.place_block(player, position, face, cursor_y) {
+                    self.undo_placement(player, position, face);
+                }
+            }
+            // using the held item on air, like eating
+            ServerBoundPacket::PlayerBlockPlacement { .. } => {}
+            ServerBoundPacket::UseEntity {
+                player,
+                target,
+                action: UseEntityAction::Attack,
+            } => self.attack(player, target),
+            ServerBoundPacket::UseEntity { .. } => {}
+            ServerBoundPacket::ClientStatus {
+                player,
+                action: ClientAction::Respawn,
+            } => self.respawn(player),
+            ServerBoundPacket::ClientStatus { .. } => {}
+        }
+    }
+
+    /// Moves a player somewhere else, their movements being ignored until
+    /// they get there.
+    pub fn teleport(&mut self, player: u128, x: f64, y: f64, z: f64, yaw: f32, pitch: f32) {
+        let Some(player_data) = self.players.get_mut(&player) else {
+            return;
+        };
+        player_data.teleport = Some((x, y, z));
+        player_data.health.fall_distance = 0.0;
+        let distance = player_data.view.distance();
+        if let Some(entity) = self.entities.get_mut(player_data.entity) {
+            entity.teleport(x, y, z);
+            (entity.yaw, entity.pitch, entity.head_yaw) = (yaw, pitch, yaw);
+        }
+        self.move_view(player, chunk_pos(x, z), distance);
+        self.send(ProxyBoundPacket::PlayerPositionAndLook {
+            player,
+            x,
+            y,
+            z,
+            yaw,
+            pitch,
+        });
+    }
+
+    /// Moves a player where their client says they are, unless they got
+    /// there too quickly.
+    fn movement(
+        &mut self,
+        id: u128,
+        position: Option<(f64, f64, f64)>,
+        look: Option<(f32, f32)>,
+        on_ground: bool,
+    ) {
+        let Some(player) = self.players.get_mut(&id) else {
+            return;
+        };
+        let Some(entity) = self.entities.get_mut(player.entity) else {
+            return;
+        };
+
+        // clients confirm teleports by sending their new position, what they
+        // sent before that being from where they were
+        if let Some((x, y, z)) = player.teleport {
+            match position {
+                Some(position)
+                    if position.0 == x && (position.1 - y).abs() < 0.1 && position.2 == z =>
+                {
+                    player.teleport = None
+                }
+                _ => return,
+            }
+        }
+
+        // dead players lie where they died until they respawn
+        if player.health.is_dead() {
+            return;
+        }
+        if let Some((yaw, pitch)) = look {
+            (entity.yaw, entity.pitch, entity.head_yaw) = (yaw, pitch, yaw);
+        }
+        entity.on_ground = on_ground;
+        let Some((x, y, z)) = position else {
+            return;
+        };
+
+        let valid = [x, y, z]
+            .iter()
+            .all(|v| v.is_finite() && v.abs() < MAX_COORDINATE);
+        let moved = (x - entity.x).powi(2) + (y - entity.y).powi(2) + (z - entity.z).powi(2);
+        if !valid || moved > MAX_MOVE * MAX_MOVE {
+            warn!("{} moved too quickly", player.username);
+            let (x, y, z, yaw, pitch) = (entity.x, entity.y, entity.z, entity.yaw, entity.pitch);
+            self.teleport(id, x, y, z, yaw, pitch);
+            return;
+        }
+
+        // players are hurt by how far they fell once they land, water
+        // breaking their fall
+        let feet = BlockPosition {
+            x: x.floor() as i32,
+            y: y.floor() as i32,
+            z: z.floor() as i32,
+        };
+        let in_water = self
+            .worlds
+            .get(&player.world)
+            .is_some_and(|world| matches!(world.get_block(feet).id, 8 | 9));
+        let mut fall = 0.0;
+        if on_ground || in_water {
+            fall = std::mem::take(&mut player.health.fall_distance);
+        } else if y < entity.y {
+            player.health.fall_distance += entity.y - y;
+        }
+        entity.teleport(x, y, z);
+        let distance = player.view.distance();
+        self.move_view(id, chunk_pos(x, z), distance);
+        if !in_water {
+            self.land(id, fall);
+        }
+    }
+
+    /// Runs the tasks due on this tick, then the systems updating the world
+    /// and what players see of it.
+    pub fn tick(&mut self) {
+        for mut task in self.scheduler.advance() {
+            task.run(self);
+            self.scheduler.reschedule(task);
+        }
+
+        self.advance_time();
+        self.update_weather();
+        self.flush_tab_list();
+        self.sync_scoreboards();
+        self.refresh_action_bars();
+        self.show_queued_titles();
+        self.render_menus();
+        self.hurt_in_void();
+        self.sync_health();
+        self.update_items();
+        self.sync_inventories();
+        self.update_digging();
+        self.broadcast_block_changes();
+        self.load_chunks();
+        self.stream_chunks();
+        let packets = self.tracker.update(&mut self.entities, &self.players);
+        self.outgoing.extend(packets);
+        self.update_boss_bars();
+    }
+
+    /// Runs a whole tick, handling the packets that came in since the last
+    /// one, and records how long it took.
+    pub fn run_tick(&mut self, packets: impl IntoIterator<Item = ServerBoundPacket>) {
+        let start = Instant::now();
+        for packet in packets {
+            self.handle(packet);
+        }
+        self.tick();
+
+        let duration = start.elapsed();
+        if duration > TICK_DURATION {
+            warn!(
+                "tick {} took {}ms, the server is overloaded",
+                self.scheduler.tick(),
+                duration.as_millis()
+            );
+        }
+        self.stats.record(start, duration);
+    }
+
+    /// Lets a player in once the spawn area of the default world is loaded.
+    fn join(&mut self, id: u128, username: String) {
+        if !self.worlds.contains_key(DEFAULT_WORLD) {
+            error!("{username} can't join, there's no {DEFAULT_WORLD} world");
+            return;
+        }
+        self.joining.push(Joining {
+            id,
+            username,
+            settings: None,
+        });
+        self.admit_players();
+    }
+
+    fn admit_players(&mut self) {
+        if self.joining.is_empty() {
+            return;
+        }
+        let Some(spawn) = self
+            .worlds
+            .get_mut(DEFAULT_WORLD)
+            .and_then(World::spawn_point)
+        else {
+            return;
+        };
+        for joining in std::mem::take(&mut self.joining) {
+            self.spawn_player(joining.id, joining.username, spawn);
+            if let Some(settings) = joining.settings {
+                self.handle(settings);
+            }
+        }
+    }
+
+    /// Takes a player who just joined past the terrain loading screen.
+    fn spawn_player(&mut self, player: u128, username: String, spawn: BlockPosition) {
+        let world = &self.worlds[DEFAULT_WORLD];
+        let (dimension, level_type) = (world.dimension(), world.level_type().to_string());
+        let (x, y, z) = (spawn.x as f64 + 0.5, spawn.y as f64, spawn.z as f64 + 0.5);
+        let entity = self
+            .entities
+            .spawn(EntityKind::Player(player), DEFAULT_WORLD, x, y, z)
+            .id();
+
+        self.send(ProxyBoundPacket::JoinGame {
+            player,
+            entity_id: entity,
+            gamemode: Gamemode::Survival,
+            dimension: dimension.id() as i8,
+            difficulty: Difficulty::Easy,
+            max_players: 20,
+            level_type,
+            reduced_debug_info: false,
+        });
+
+        self.send(ProxyBoundPacket::SpawnPosition {
+            player,
+            position: spawn,
+        });
+        self.send(ProxyBoundPacket::PlayerAbilities {
+            player,
+            abilities: abilities(Gamemode::Survival),
+        });
+        self.send(ProxyBoundPacket::HeldItemChange { player, slot: 0 });
+        let view = self.send_spawn_area(player, DEFAULT_WORLD, spawn, self.view_distance);
+
+        // players are listed before being spawned, the tracker spawning
+        // them at the end of the tick
+        self.tab_list.add(player, &username, Gamemode::Survival);
+        self.flush_tab_list();
+        self.players.insert(
+            player,
+            Player {
+                username,
+                locale: DEFAULT_LOCALE.to_string(),
+                gamemode: Gamemode::Survival,
+                world: DEFAULT_WORLD.to_string(),
+                view,
+                entity,
+                teleport: None,
+                scoreboard: None,
+                sent_scoreboard: Scoreboard::default(),
+                action_bar: None,
+                boss_bar: None,
+                boss: None,
+                titles: Titles::default(),
+                inventory: Inventory::default(),
+                windows: Windows::default(),
+                menu: None,
+                digging: None,
+                health: Health::default(),
+            },
+        );
+        let packets = self.tab_list.packets(player);
+        self.outgoing.extend(packets);
+        self.teleport(player, x, y, z, 0.0, 0.0);
+        self.send_world_state(player, DEFAULT_WORLD);
+    }
+
+    fn flush_tab_list(&mut self) {
+        let players: Vec<_> = self.players.keys().copied().collect();
+        let packets = self.tab_list.take_updates(&players);
+        self.outgoing.extend(packets);
+    }
+
+    fn sync_scoreboards(&mut self) {
+        for (id, player) in self.players.iter_mut() {
+            let target = player.scoreboard.as_ref().unwrap_or(&self.scoreboard);
+            let packets = player.sent_scoreboard.diff(target, *id);
+            if !packets.is_empty() {
+                player.sent_scoreboard = target.clone();
+                self.outgoing.extend(packets);
+            }
+        }
+    }
+}
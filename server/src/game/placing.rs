@@ -0,0 +1,159 @@
+use makar_protocol::*;
+
+use super::Game;
+use crate::{
+    entity::EntityKind,
+    inventory::HOTBAR,
+    world::{
+        self, blocks,
+        placement::{self, neighbour},
+    },
+};
+
+/// Farthest from their feet players place blocks against others.
+const PLACE_REACH: f64 = 8.0;
+/// Width and height of the box players and mobs take up, for the blocks
+/// placed in their way.
+pub(super) const ENTITY_WIDTH: f64 = 0.6;
+pub(super) const ENTITY_HEIGHT: f64 = 1.8;
+
+impl Game {
+    /// Places the block a player holds against a face of another one,
+    /// returning whether what their client predicted holds.
+    pub(super) fn place_block(
+        &mut self,
+        id: u128,
+        clicked: BlockPosition,
+        face: BlockFace,
+        cursor_y: u8,
+    ) -> bool {
+        let Some(player) = self.players.get(&id) else {
+            return true;
+        };
+        if !matches!(player.gamemode, Gamemode::Survival | Gamemode::Creative)
+            || player.health.is_dead()
+        {
+            return false;
+        }
+        let Some(item) = player.inventory.held_item() else {
+            return true;
+        };
+        let Some((yaw, pitch)) = self
+            .entities
+            .get(player.entity)
+            .map(|entity| (entity.yaw, entity.pitch))
+        else {
+            return false;
+        };
+        let Some(world) = self.worlds.get(&player.world) else {
+            return false;
+        };
+        if !self.within_reach(id, clicked, 0.0, PLACE_REACH) {
+            return false;
+        }
+        let clicked_block = world.get_block(clicked);
+        // clicking chests, doors and the like uses them instead
+        if blocks::is_interactive(clicked_block.id) {
+            return true;
+        }
+        let Some(placed) = placement::placement(&item, face, cursor_y, yaw, pitch) else {
+            return false;
+        };
+
+        // slabs placed on the free half of a slab fill it up
+        let onto_slab = match face {
+            BlockFace::Top => clicked_block.meta & 8 == 0,
+            BlockFace::Bottom => clicked_block.meta & 8 != 0,
+            _ => false,
+        };
+        let (target, block) = match placement::merge_slabs(clicked_block, placed.block) {
+            Some(double) if onto_slab => (clicked, double),
+            _ => {
+                let target = match blocks::is_replaceable(clicked_block) {
+                    true => clicked,
+                    false => neighbour(clicked, face),
+                };
+                let existing = world.get_block(target);
+                match placement::merge_slabs(existing, placed.block) {
+                    Some(double) => (target, double),
+                    None if blocks::is_replaceable(existing) => (target, placed.block),
+                    None => return false,
+                }
+            }
+        };
+        if placed.attached && (target == clicked || !blocks::is_full_cube(clicked_block)) {
+            return false;
+        }
+
+        let mut changes = vec![(target, block)];
+        if let Some(above) = placed.above {
+            changes.push((neighbour(target, BlockFace::Top), above));
+        }
+        for (index, (position, block)) in changes.iter().enumerate() {
+            let (x, z) = world::chunk_pos(*position);
+            let free = index == 0 || blocks::is_replaceable(world.get_block(*position));
+            if !(0..256).contains(&position.y) || world.chunk(x, z).is_none() || !free {
+                return false;
+            }
+            if blocks::collides(*block) && self.obstructed(&player.world, *position) {
+                return false;
+            }
+        }
+        let Some(world) = self.worlds.get_mut(&player.world) else {
+            return false;
+        };
+        for (position, block) in changes {
+            world.set_block(position, block);
+        }
+
+        let Some(player) = self.players.get_mut(&id) else {
+            return true;
+        };
+        if player.gamemode == Gamemode::Survival {
+            let slot = HOTBAR.start + player.inventory.held() as usize;
+            let left = ItemStack {
+                count: item.count - 1,
+                ..item
+            };
+            player
+                .inventory
+                .set(slot, Some(left).filter(|item| item.count > 0));
+        }
+        true
+    }
+
+    fn obstructed(&self, world: &str, position: BlockPosition) -> bool {
+        let (x, y, z) = (position.x as f64, position.y as f64, position.z as f64);
+        let half = ENTITY_WIDTH / 2.0;
+        self.entities
+            .iter()
+            .filter(|entity| entity.world() == world)
+            .filter(|entity| matches!(entity.kind(), EntityKind::Player(_) | EntityKind::Mob(_)))
+            .any(|entity| {
+                entity.x + half > x
+                    && entity.x - half < x + 1.0
+                    && entity.y + ENTITY_HEIGHT > y
+                    && entity.y < y + 1.0
+                    && entity.z + half > z
+                    && entity.z - half < z + 1.0
+            })
+    }
+
+    /// Tells a player their block wasn't placed, resending the blocks and
+    /// item their client changed.
+    pub(super) fn undo_placement(&mut self, id: u128, clicked: BlockPosition, face: BlockFace) {
+        self.resend_block(id, clicked);
+        self.resend_block(id, neighbour(clicked, face));
+        let Some(player) = self.players.get(&id) else {
+            return;
+        };
+        let held = player.inventory.held() as usize;
+        let item = player.inventory.held_item();
+        self.send(ProxyBoundPacket::SetSlot {
+            player: id,
+            window_id: 0,
+            slot: (HOTBAR.start + held) as i16,
+            item,
+        });
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Ticks whose duration is averaged in the statistics.
+const STATS_WINDOW: usize = 100;
+
+/// How long recent ticks took.
+#[derive(Default)]
+pub struct TickStats {
+    /// When recent ticks started, along with how long they took.
+    ticks: VecDeque<(Instant, Duration)>,
+}
+
+impl TickStats {
+    pub fn record(&mut self, start: Instant, duration: Duration) {
+        if self.ticks.len() == STATS_WINDOW {
+            self.ticks.pop_front();
+        }
+        self.ticks.push_back((start, duration));
+    }
+
+    /// Average time spent running a tick.
+    pub fn tick_time(&self) -> Duration {
+        match self.ticks.len() {
+            0 => Duration::ZERO,
+            n => {
+                self.ticks
+                    .iter()
+                    .map(|(_, duration)| *duration)
+                    .sum::<Duration>()
+                    / n as u32
+            }
+        }
+    }
+
+    /// Ticks run per second, at most 20 as ticks that took too long delay
+    /// the next ones.
+    pub fn tps(&self) -> f64 {
+        let (Some((first, _)), Some((last, _))) = (self.ticks.front(), self.ticks.back()) else {
+            return 20.0;
+        };
+        let elapsed = last.duration_since(*first).as_secs_f64();
+        if elapsed == 0.0 {
+            return 20.0;
+        }
+        ((self.ticks.len() - 1) as f64 / elapsed).min(20.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ticks starting `interval` apart, each taking `duration`.
+    fn stats(ticks: usize, interval: Duration, duration: Duration) -> TickStats {
+        let start = Instant::now();
+        let mut stats = TickStats::default();
+        for i in 0..ticks {
+            stats.record(start + interval * i as u32, duration);
+        }
+        stats
+    }
+
+    #[test]
+    fn averages_tick_times() {
+        let mut stats = TickStats::default();
+        assert_eq!(stats.tick_time(), Duration::ZERO);
+        let start = Instant::now();
+        for ms in [10, 20, 30] {
+            stats.record(start, Duration::from_millis(ms));
+        }
+        assert_eq!(stats.tick_time(), Duration::from_millis(20));
+    }
+
+    #[test]
+    fn forgets_old_ticks() {
+        let mut stats = stats(50, Duration::from_secs(1), Duration::from_secs(1));
+        let start = Instant::now() + Duration::from_secs(60);
+        for i in 0..STATS_WINDOW as u32 {
+            stats.record(
+                start + Duration::from_millis(50) * i,
+                Duration::from_millis(10),
+            );
+        }
+        assert_eq!(stats.tick_time(), Duration::from_millis(10));
+        assert_eq!(stats.tps(), 20.0);
+    }
+
+    #[test]
+    fn caps_tps() {
+        assert_eq!(TickStats::default().tps(), 20.0);
+        let fast = stats(10, Duration::from_millis(25), Duration::from_millis(1));
+        assert_eq!(fast.tps(), 20.0);
+    }
+
+    #[test]
+    fn slows_down_with_long_ticks() {
+        let slow = stats(11, Duration::from_millis(100), Duration::from_millis(100));
+        assert!((slow.tps() - 10.0).abs() < 1e-9);
+        assert_eq!(slow.tick_time(), Duration::from_millis(100));
+    }
+}
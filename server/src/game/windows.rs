@@ -0,0 +1,147 @@
+use makar_protocol::*;
+
+use super::Game;
+use crate::{
+    inventory::{Inventory, Window},
+    menu::Menu,
+};
+
+impl Game {
+    /// The items of a player, changes being sent at the end of the tick.
+    pub fn inventory(&mut self, player: u128) -> Option<&mut Inventory> {
+        Some(&mut self.players.get_mut(&player)?.inventory)
+    }
+
+    /// Opens a window over a player's inventory, closing the one they had
+    /// open.
+    pub fn open_window(&mut self, player: u128, window: Window) {
+        if self
+            .players
+            .get_mut(&player)
+            .is_some_and(|player| player.windows.window().is_some())
+        {
+            self.close_window(player);
+        }
+        if let Some(player_data) = self.players.get_mut(&player) {
+            let packets = player_data
+                .windows
+                .open(player, window, &player_data.inventory);
+            self.outgoing.extend(packets);
+        }
+    }
+
+    /// The window a player has open over their inventory, changes being
+    /// sent at the end of the tick.
+    pub fn window(&mut self, player: u128) -> Option<&mut Window> {
+        self.players.get_mut(&player)?.windows.window()
+    }
+
+    pub fn close_window(&mut self, player: u128) -> Option<Window> {
+        let window_id = self.players.get(&player)?.windows.id();
+        self.send(ProxyBoundPacket::CloseWindow { player, window_id });
+        self.window_closed(player)
+    }
+
+    /// Shows a menu in a window over a player's inventory, closing the one
+    /// they had open.
+    pub fn open_menu(&mut self, player: u128, menu: Menu) {
+        self.open_window(player, menu.window());
+        if let Some(player) = self.players.get_mut(&player) {
+            player.menu = Some(menu);
+        }
+    }
+
+    /// The menu a player has open, changes being sent at the end of the
+    /// tick.
+    pub fn menu(&mut self, player: u128) -> Option<&mut Menu> {
+        self.players.get_mut(&player)?.menu.as_mut()
+    }
+
+    /// Drops what a player was holding in the window they closed.
+    pub(super) fn window_closed(&mut self, id: u128) -> Option<Window> {
+        let player = self.players.get_mut(&id)?;
+        player.menu = None;
+        let (window, drops) = player.windows.close(&mut player.inventory);
+        for item in drops {
+            self.drop_item(id, item);
+        }
+        window
+    }
+
+    pub(super) fn render_menus(&mut self) {
+        for player in self.players.values_mut() {
+            let (Some(menu), Some(window)) = (&player.menu, player.windows.window()) else {
+                continue;
+            };
+            for (slot, item) in menu.render().into_iter().enumerate() {
+                window.set(slot, item);
+            }
+        }
+    }
+
+    pub(super) fn sync_inventories(&mut self) {
+        for (id, player) in self.players.iter_mut() {
+            let packets = player.windows.sync(*id, &player.inventory);
+            self.outgoing.extend(packets);
+        }
+    }
+
+    /// Clicks a slot of the window a player has open, menus keeping their
+    /// items whatever the client predicted.
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn click_window(
+        &mut self,
+        id: u128,
+        window_id: u8,
+        slot: i16,
+        button: u8,
+        action: i16,
+        mode: ClickMode,
+        item: Option<ItemStack>,
+    ) {
+        let Some(player) = self.players.get_mut(&id) else {
+            return;
+        };
+        if let Some(menu) = &mut player.menu {
+            let Some(packets) = player
+                .windows
+                .reject(id, &player.inventory, window_id, action)
+            else {
+                return;
+            };
+            self.outgoing.extend(packets);
+            if let Some((handler, click)) = menu.click(id, slot, mode, button) {
+                handler(self, &click);
+            }
+            return;
+        }
+        let creative = player.gamemode == Gamemode::Creative;
+        let (packets, drops) = player.windows.click(
+            id,
+            &mut player.inventory,
+            creative,
+            window_id,
+            slot,
+            button,
+            action,
+            mode,
+            item,
+        );
+        self.outgoing.extend(packets);
+        for item in drops {
+            self.drop_item(id, item);
+        }
+    }
+
+    pub(super) fn creative_action(&mut self, id: u128, slot: i16, item: Option<ItemStack>) {
+        let Some(player) = self.players.get_mut(&id) else {
+            return;
+        };
+        if player.gamemode != Gamemode::Creative {
+            return;
+        }
+        if let Some(item) = player.windows.creative(&mut player.inventory, slot, item) {
+            self.drop_item(id, item);
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use makar_protocol::*;
+
+use log::info;
+
+use super::Game;
+use crate::world::{Dimension, World};
+
+#[derive(Debug, thiserror::Error)]
+pub enum WorldError {
+    #[error("players are still in {0}")]
+    InUse(String),
+}
+
+impl Game {
+    pub fn world(&self, name: &str) -> Option<&World> {
+        self.worlds.get(name)
+    }
+
+    pub fn world_mut(&mut self, name: &str) -> Option<&mut World> {
+        self.worlds.get_mut(name)
+    }
+
+    pub fn worlds(&self) -> impl Iterator<Item = (&str, &World)> {
+        self.worlds
+            .iter()
+            .map(|(name, world)| (name.as_str(), world))
+    }
+
+    /// Hosts another world, players being moved there with
+    /// [`Game::change_world`]. Returns the world it replaced, which can't
+    /// have players left in it.
+    pub fn add_world(
+        &mut self,
+        name: impl Into<String>,
+        mut world: World,
+    ) -> Result<Option<World>, WorldError> {
+        let name = name.into();
+        if self.players.values().any(|player| player.world == name) {
+            return Err(WorldError::InUse(name));
+        }
+        world.set_pool(self.pool.clone());
+        world.request_spawn_area();
+        Ok(self.worlds.insert(name, world))
+    }
+
+    /// Saves the chunks that changed in every world in the background.
+    pub fn save(&mut self) {
+        for (name, world) in self.worlds.iter_mut() {
+            match world.save() {
+                0 => {}
+                saved => info!("saving {saved} chunks of {name}"),
+            }
+        }
+    }
+
+    /// Blocks until the chunks being saved are written, before the game
+    /// stops.
+    pub fn flush(&mut self) {
+        for world in self.worlds.values_mut() {
+            world.flush();
+        }
+    }
+
+    /// Moves a player to the spawn point of a world, returning whether it
+    /// exists. Players arrive once the spawn area of the world is loaded.
+    ///
+    /// Clients only drop their chunks and entities when respawning in
+    /// another dimension, so players going to a world of the dimension
+    /// they're in respawn in another one first.
+    pub fn change_world(&mut self, id: u128, name: &str) -> bool {
+        let Some(player) = self.players.get(&id) else {
+            return false;
+        };
+        if !self.worlds.contains_key(&player.world) {
+            return false;
+        }
+        let Some(spawn) = self.worlds.get_mut(name).map(World::spawn_point) else {
+            return false;
+        };
+        self.arriving.remove(&id);
+        let Some(spawn) = spawn else {
+            self.arriving.insert(id, name.to_string());
+            return true;
+        };
+        let player = &self.players[&id];
+        let (from, to) = (&self.worlds[&player.world], &self.worlds[name]);
+        let (gamemode, dimension) = (player.gamemode, to.dimension());
+        let respawn = |dimension: Dimension| ProxyBoundPacket::Respawn {
+            player: id,
+            dimension: dimension.id(),
+            difficulty: Difficulty::Easy,
+            gamemode,
+            level_type: to.level_type().to_string(),
+        };
+        if from.dimension() == dimension {
+            let other = match dimension {
+                Dimension::Overworld => Dimension::Nether,
+                _ => Dimension::Overworld,
+            };
+            self.outgoing.push(respawn(other));
+        }
+        self.outgoing.push(respawn(dimension));
+
+        // clients close their window and forget their inventory and health
+        self.stop_digging(id);
+        self.window_closed(id);
+        let distance = self.players[&id].view.distance();
+        let view = self.send_spawn_area(id, name, spawn, distance);
+        let Some(player) = self.players.get_mut(&id) else {
+            return false;
+        };
+        player.world = name.to_string();
+        player.view = view;
+        // the wither showing the boss bar was left behind
+        player.boss = None;
+        player.health.resend();
+        let packets = player.windows.resync(id, &player.inventory);
+        self.outgoing.extend(packets);
+        let slot = player.inventory.held();
+        if let Some(entity) = self.entities.get_mut(player.entity) {
+            entity.set_world(name);
+        }
+        self.tracker.reset_viewer(id);
+
+        self.send(ProxyBoundPacket::HeldItemChange { player: id, slot });
+        self.send(ProxyBoundPacket::SpawnPosition {
+            player: id,
+            position: spawn,
+        });
+        let (x, y, z) = (spawn.x as f64 + 0.5, spawn.y as f64, spawn.z as f64 + 0.5);
+        self.teleport(id, x, y, z, 0.0, 0.0);
+        self.send_world_state(id, name);
+        true
+    }
+
+    /// Moves the players whose world changes were waiting for the spawn area
+    /// of their new world to load.
+    pub(super) fn admit_arrivals(&mut self) {
+        for (id, name) in std::mem::take(&mut self.arriving) {
+            self.change_world(id, &name);
+        }
+    }
+
+    /// Tells a player arriving in a world its time and weather.
+    pub(super) fn send_world_state(&mut self, player: u128, world: &str) {
+        let Some(world) = self.worlds.get(world) else {
+            return;
+        };
+        self.outgoing.push(world.time_packet(player));
+        let changes = world.current_weather();
+        self.outgoing.extend(
+            changes
+                .into_iter()
+                .map(|change| ProxyBoundPacket::ChangeGameState { player, change }),
+        );
+    }
+
+    /// Advances the time of every world, telling their players right away
+    /// when it was set.
+    pub(super) fn advance_time(&mut self) {
+        let changed: Vec<_> = self
+            .worlds
+            .iter_mut()
+            .filter_map(|(name, world)| world.advance_time().then(|| name.clone()))
+            .collect();
+        if !changed.is_empty() {
+            self.send_time(|world| changed.iter().any(|name| name == world));
+        }
+    }
+
+    pub(super) fn send_time(&mut self, worlds: impl Fn(&str) -> bool) {
+        for (id, player) in &self.players {
+            if let Some(world) = self.worlds.get(&player.world) {
+                if worlds(&player.world) {
+                    self.outgoing.push(world.time_packet(*id));
+                }
+            }
+        }
+    }
+
+    pub(super) fn update_weather(&mut self) {
+        for (name, world) in self.worlds.iter_mut() {
+            for change in world.update_weather() {
+                for (id, player) in &self.players {
+                    if player.world == *name {
+                        self.outgoing.push(ProxyBoundPacket::ChangeGameState {
+                            player: *id,
+                            change,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use makar_protocol::{Chat, ProxyBoundPacket};
+
+pub const MAX_HEALTH: f32 = 20.0;
+/// Hunger and saturation shown to players, who don't get hungry.
+const FOOD: i32 = 20;
+const SATURATION: f32 = 5.0;
+/// Ticks after a hit during which only harder hits hurt, and only by how
+/// much harder they are.
+const INVULNERABILITY: u64 = 10;
+
+/// What hurt a player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageCause {
+    /// Hit by another player.
+    Attack(u128),
+    Fall,
+    /// Below the bottom of the world, which hurts creative players too.
+    Void,
+    Generic,
+}
+
+impl DamageCause {
+    fn translation(&self) -> &'static str {
+        match self {
+            DamageCause::Attack(_) => "death.attack.player",
+            DamageCause::Fall => "death.attack.fall",
+            DamageCause::Void => "death.attack.outOfWorld",
+            DamageCause::Generic => "death.attack.generic",
+        }
+    }
+
+    /// The message telling how a player died, translated by clients, along
+    /// with who killed them.
+    pub fn death_message(&self, victim: &str, killer: Option<&str>) -> Chat {
+        let name = |name: &str| Chat {
+            text: name.to_string(),
+            ..Default::default()
+        };
+        Chat {
+            translate: Some(self.translation().to_string()),
+            with: [Some(victim), killer]
+                .into_iter()
+                .flatten()
+                .map(name)
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// The health of a player, with how recently they were hit.
+#[derive(Debug, Clone)]
+pub struct Health {
+    health: f32,
+    /// Until when the player is invulnerable, with how much the hit before
+    /// hurt.
+    invulnerable: Option<(u64, f32)>,
+    /// How far the player fell since they were last on the ground.
+    pub(crate) fall_distance: f64,
+    /// The health the client was last sent.
+    sent: Option<f32>,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            health: MAX_HEALTH,
+            invulnerable: None,
+            fall_distance: 0.0,
+            sent: None,
+        }
+    }
+}
+
+impl Health {
+    pub fn get(&self) -> f32 {
+        self.health
+    }
+
+    pub fn set(&mut self, health: f32) {
+        self.health = health.clamp(0.0, MAX_HEALTH);
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health <= 0.0
+    }
+
+    /// Hurts the player, returning how much, and whether it was a new hit
+    /// rather than a harder one while they were invulnerable.
+    pub(crate) fn hurt(&mut self, tick: u64, amount: f32) -> Option<(f32, bool)> {
+        if self.is_dead() || amount <= 0.0 {
+            return None;
+        }
+        let (dealt, new_hit) = match &mut self.invulnerable {
+            Some((until, last)) if tick < *until => {
+                if amount <= *last {
+                    return None;
+                }
+                let dealt = amount - *last;
+                *last = amount;
+                (dealt, false)
+            }
+            invulnerable => {
+                *invulnerable = Some((tick + INVULNERABILITY, amount));
+                (amount, true)
+            }
+        };
+        self.set(self.health - dealt);
+        Some((dealt, new_hit))
+    }
+
+    /// Brings the player back to life, as if they just joined.
+    pub(crate) fn reset(&mut self) {
+        *self = Self {
+            sent: self.sent,
+            ..Self::default()
+        };
+    }
+
+    /// Makes the health be sent again, to a client that forgot it.
+    pub(crate) fn resend(&mut self) {
+        self.sent = None;
+    }
+
+    /// The packet telling the client the player's health when it changed.
+    pub(crate) fn sync(&mut self, player: u128) -> Option<ProxyBoundPacket> {
+        if self.sent == Some(self.health) {
+            return None;
+        }
+        self.sent = Some(self.health);
+        Some(ProxyBoundPacket::UpdateHealth {
+            player,
+            health: self.health,
+            food: FOOD,
+            saturation: SATURATION,
+        })
+    }
+}
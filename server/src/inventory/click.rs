@@ -0,0 +1,1165 @@
+use std::ops::Range;
+
+use makar_protocol::{ClickMode, ItemStack, ProxyBoundPacket, WindowKind};
+
+use super::{
+    armor_slot, craft, max_stack_size, same_item, Inventory, Window, ARMOR, CRAFTING_GRID,
+    CRAFTING_RESULT, HOTBAR, MAIN,
+};
+
+/// The slot of clicks outside of the window.
+const OUTSIDE: i16 = -999;
+/// Window ids wrap around after this one, as the vanilla server does.
+const MAX_WINDOW_ID: u8 = 100;
+
+/// Items being dragged over slots, clients sending a click to start, for
+/// every slot and to end.
+#[derive(Default)]
+struct Drag {
+    started: bool,
+    /// 0 to split the items evenly, 1 to put one in each slot, 2 to fill
+    /// them in creative mode.
+    mode: u8,
+    slots: Vec<usize>,
+}
+
+/// A click on the slots a player sees: the ones of the open window, then
+/// those of their inventory but the crafting grid and armor, or their whole
+/// inventory when no window is open. Clicks go the way they do on vanilla
+/// clients, which predict them.
+struct Clicker<'a> {
+    window: Option<&'a mut Window>,
+    inventory: &'a mut Inventory,
+    drag: &'a mut Drag,
+    creative: bool,
+    drops: Vec<ItemStack>,
+}
+
+impl Clicker<'_> {
+    fn len(&self) -> usize {
+        match &self.window {
+            Some(window) => window.slots().len() + HOTBAR.end - MAIN.start,
+            None => HOTBAR.end,
+        }
+    }
+
+    /// The slot of the inventory shown in a slot, if it isn't the window's.
+    fn inventory_slot(&self, slot: usize) -> Option<usize> {
+        match &self.window {
+            Some(window) => slot
+                .checked_sub(window.slots().len())
+                .map(|slot| MAIN.start + slot),
+            None => Some(slot),
+        }
+    }
+
+    fn get(&self, slot: usize) -> Option<ItemStack> {
+        match (self.inventory_slot(slot), &self.window) {
+            (Some(slot), _) => self.inventory.get(slot),
+            (None, Some(window)) => window.get(slot),
+            (None, None) => None,
+        }
+    }
+
+    fn set(&mut self, slot: usize, item: Option<ItemStack>) {
+        match (self.inventory_slot(slot), &mut self.window) {
+            (Some(slot), _) => self.inventory.set(slot, item),
+            (None, Some(window)) => window.set(slot, item),
+            (None, None) => {}
+        }
+    }
+
+    fn is_result(&self, slot: usize) -> bool {
+        match (self.inventory_slot(slot), &self.window) {
+            (Some(slot), _) => slot == CRAFTING_RESULT,
+            (None, Some(window)) => window.is_result(slot),
+            (None, None) => false,
+        }
+    }
+
+    /// Whether the slot is part of the player's inventory, armor included.
+    fn is_inventory(&self, slot: usize) -> bool {
+        self.inventory_slot(slot)
+            .is_some_and(|slot| slot >= ARMOR.start)
+    }
+
+    fn accepts(&self, slot: usize, item: &ItemStack) -> bool {
+        match self.inventory_slot(slot) {
+            Some(slot) if ARMOR.contains(&slot) => armor_slot(item.id) == Some(slot),
+            _ => !self.is_result(slot),
+        }
+    }
+
+    /// Most items a slot holds, whatever the item.
+    fn limit(&self, slot: usize) -> u8 {
+        match self.inventory_slot(slot) {
+            Some(slot) if ARMOR.contains(&slot) => 1,
+            _ => 64,
+        }
+    }
+
+    /// Whether items can be added to the stack in a slot, if any.
+    fn can_add(&self, slot: usize, item: &ItemStack) -> bool {
+        match self.get(slot) {
+            Some(stack) => same_item(&stack, item) && stack.count <= max_stack_size(item.id),
+            None => true,
+        }
+    }
+
+    /// Where the player crafts, if anywhere: the result slot, then the
+    /// slots of the grid and its width.
+    fn crafting(&self) -> Option<(usize, Range<usize>, usize)> {
+        match &self.window {
+            Some(window) if window.kind() == WindowKind::CraftingTable => {
+                Some((0, window.crafting_grid(), 3))
+            }
+            Some(_) => None,
+            None => Some((CRAFTING_RESULT, CRAFTING_GRID, 2)),
+        }
+    }
+
+    /// Shows what the items in the crafting grid make in the result slot.
+    fn update_result(&mut self) {
+        let Some((result, grid, width)) = self.crafting() else {
+            return;
+        };
+        let items: Vec<_> = grid.map(|slot| self.get(slot)).collect();
+        self.set(result, craft(&items, width));
+    }
+
+    /// Uses up an item of each slot of the crafting grid once the result
+    /// was taken.
+    fn take_result(&mut self) {
+        let Some((_, grid, _)) = self.crafting() else {
+            return;
+        };
+        for slot in grid {
+            if let Some(mut item) = self.get(slot) {
+                item.count -= 1;
+                self.set(slot, Some(item));
+            }
+        }
+        self.update_result();
+    }
+
+    fn click(&mut self, slot: i16, button: u8, mode: ClickMode) -> Option<ItemStack> {
+        let clicked = self.click_slot(slot, button, mode);
+        self.update_result();
+        clicked
+    }
+
+    fn click_slot(&mut self, slot: i16, button: u8, mode: ClickMode) -> Option<ItemStack> {
+        if mode == ClickMode::Drag {
+            self.drag(slot, button);
+            return None;
+        }
+        if self.drag.started {
+            *self.drag = Drag::default();
+            return None;
+        }
+        if slot == OUTSIDE && matches!(mode, ClickMode::Click | ClickMode::ShiftClick) {
+            self.drop_cursor(button);
+            return None;
+        }
+        let slot = match usize::try_from(slot) {
+            Ok(slot) if slot < self.len() => slot,
+            _ => return None,
+        };
+        match mode {
+            ClickMode::Click if button < 2 => self.pick_or_place(slot, button),
+            ClickMode::ShiftClick if button < 2 => self.shift_click(slot),
+            ClickMode::NumberKey if button < 9 => {
+                self.swap_hotbar(slot, button as usize);
+                None
+            }
+            ClickMode::MiddleClick if self.creative && self.inventory.cursor().is_none() => {
+                let item = self.get(slot).map(|item| ItemStack {
+                    count: max_stack_size(item.id),
+                    ..item
+                });
+                if item.is_some() {
+                    self.inventory.set_cursor(item);
+                }
+                None
+            }
+            ClickMode::Drop if self.inventory.cursor().is_none() => {
+                if let Some(mut item) = self.get(slot) {
+                    let result = self.is_result(slot);
+                    let count = if button == 0 && !result {
+                        1
+                    } else {
+                        item.count
+                    };
+                    self.drops.push(ItemStack {
+                        count,
+                        ..item.clone()
+                    });
+                    item.count -= count;
+                    self.set(slot, Some(item));
+                    if result {
+                        self.take_result();
+                    }
+                }
+                None
+            }
+            ClickMode::DoubleClick => {
+                self.gather(slot, button);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn drop_cursor(&mut self, button: u8) {
+        let Some(mut cursor) = self.inventory.cursor() else {
+            return;
+        };
+        match button {
+            0 => {
+                self.drops.push(cursor);
+                self.inventory.set_cursor(None);
+            }
+            1 => {
+                self.drops.push(ItemStack {
+                    count: 1,
+                    ..cursor.clone()
+                });
+                cursor.count -= 1;
+                self.inventory.set_cursor(Some(cursor));
+            }
+            _ => {}
+        }
+    }
+
+    /// Picks items up, puts them down, or swaps them with the ones held,
+    /// right clicks handling half or one of them. Results are crafted
+    /// whole. Returns what was in the slot.
+    fn pick_or_place(&mut self, slot: usize, button: u8) -> Option<ItemStack> {
+        let stack = self.get(slot);
+        match (stack.clone(), self.inventory.cursor()) {
+            (None, Some(mut cursor)) if self.accepts(slot, &cursor) => {
+                let count = if button == 0 { cursor.count } else { 1 };
+                let count = count.min(self.limit(slot));
+                self.set(
+                    slot,
+                    Some(ItemStack {
+                        count,
+                        ..cursor.clone()
+                    }),
+                );
+                cursor.count -= count;
+                self.inventory.set_cursor(Some(cursor));
+            }
+            (None, _) => {}
+            (Some(mut stack), None) => {
+                let result = self.is_result(slot);
+                let count = if button == 0 || result {
+                    stack.count
+                } else {
+                    stack.count.div_ceil(2)
+                };
+                self.inventory.set_cursor(Some(ItemStack {
+                    count,
+                    ..stack.clone()
+                }));
+                stack.count -= count;
+                self.set(slot, Some(stack));
+                if result {
+                    self.take_result();
+                }
+            }
+            (Some(mut stack), Some(mut cursor)) if self.accepts(slot, &cursor) => {
+                if same_item(&stack, &cursor) {
+                    let count = if button == 0 { cursor.count } else { 1 };
+                    let count = count
+                        .min(self.limit(slot).saturating_sub(stack.count))
+                        .min(max_stack_size(cursor.id).saturating_sub(stack.count));
+                    stack.count += count;
+                    cursor.count -= count;
+                    self.set(slot, Some(stack));
+                    self.inventory.set_cursor(Some(cursor));
+                } else if cursor.count <= self.limit(slot) {
+                    self.set(slot, Some(cursor));
+                    self.inventory.set_cursor(Some(stack));
+                }
+            }
+            // slots the items held can't go in, like crafting results, add
+            // their items to them
+            (Some(stack), Some(mut cursor)) => {
+                let max = max_stack_size(cursor.id);
+                if same_item(&stack, &cursor) && max > 1 && stack.count + cursor.count <= max {
+                    cursor.count += stack.count;
+                    self.set(slot, None);
+                    self.inventory.set_cursor(Some(cursor));
+                    if self.is_result(slot) {
+                        self.take_result();
+                    }
+                }
+            }
+        }
+        stack
+    }
+
+    /// Moves items between the window and the inventory, or between parts
+    /// of the inventory, until none of the items clicked can move. Returns
+    /// what was in the slot.
+    fn shift_click(&mut self, slot: usize) -> Option<ItemStack> {
+        if self.is_result(slot) {
+            return self.craft_all(slot);
+        }
+        let moved = self.transfer(slot)?;
+        while self.get(slot).is_some_and(|item| item.id == moved.id) {
+            if self.transfer(slot).is_none() {
+                break;
+            }
+        }
+        Some(moved)
+    }
+
+    /// Crafts the result of a slot into the inventory, again and again
+    /// while the same item is crafted and fits whole. Returns what was in
+    /// the slot.
+    fn craft_all(&mut self, slot: usize) -> Option<ItemStack> {
+        let crafted = self.get(slot)?;
+        while let Some(mut item) = self.get(slot).filter(|item| same_item(item, &crafted)) {
+            let (target, reverse) = self.transfer_target(slot, &item);
+            if !self.fits(&item, target.clone()) {
+                break;
+            }
+            self.merge(&mut item, target, reverse);
+            self.take_result();
+        }
+        Some(crafted)
+    }
+
+    /// Whether all the items fit in some slots.
+    fn fits(&self, item: &ItemStack, slots: Range<usize>) -> bool {
+        let max = max_stack_size(item.id);
+        let room: u32 = slots
+            .map(|slot| match self.get(slot) {
+                None => max as u32,
+                Some(stack) if same_item(&stack, item) => max.saturating_sub(stack.count) as u32,
+                Some(_) => 0,
+            })
+            .sum();
+        room >= item.count as u32
+    }
+
+    /// Where shift clicking a slot moves its items, and whether the slots
+    /// are filled starting from the last.
+    fn transfer_target(&self, slot: usize, item: &ItemStack) -> (Range<usize>, bool) {
+        let Some(window) = &self.window else {
+            let inventory = MAIN.start..HOTBAR.end;
+            return match slot {
+                CRAFTING_RESULT => (inventory, true),
+                _ if slot < MAIN.start => (inventory, false),
+                // only actual armor, not pumpkins
+                _ if (298..=317).contains(&item.id) => match armor_slot(item.id) {
+                    Some(armor) if self.inventory.get(armor).is_none() => (armor..armor + 1, false),
+                    _ if MAIN.contains(&slot) => (HOTBAR, false),
+                    _ => (MAIN, false),
+                },
+                _ if MAIN.contains(&slot) => (HOTBAR, false),
+                _ => (MAIN, false),
+            };
+        };
+        let size = window.slots().len();
+        let inventory = size..size + HOTBAR.end - MAIN.start;
+        let hotbar = inventory.end - HOTBAR.len()..inventory.end;
+        match window.kind() {
+            WindowKind::CraftingTable => match slot {
+                0 => (inventory, true),
+                _ if slot < size => (inventory, false),
+                _ if slot < hotbar.start => (hotbar, false),
+                _ => (size..hotbar.start, false),
+            },
+            _ if slot < size => (inventory, true),
+            _ => (0..size, false),
+        }
+    }
+
+    /// Moves the items of a slot where shift clicking does, returning them
+    /// if any moved.
+    fn transfer(&mut self, slot: usize) -> Option<ItemStack> {
+        let item = self.get(slot)?;
+        let (target, reverse) = self.transfer_target(slot, &item);
+        let mut left = item.clone();
+        if !self.merge(&mut left, target, reverse) {
+            return None;
+        }
+        self.set(slot, Some(left));
+        Some(item)
+    }
+
+    /// Adds items to the stacks of the same item in some slots, then to the
+    /// first empty one, returning whether any were added.
+    fn merge(&mut self, item: &mut ItemStack, slots: Range<usize>, reverse: bool) -> bool {
+        let slots: Vec<_> = match reverse {
+            true => slots.rev().collect(),
+            false => slots.collect(),
+        };
+        let max = max_stack_size(item.id);
+        let mut merged = false;
+        if max > 1 {
+            for &slot in &slots {
+                if item.count == 0 {
+                    break;
+                }
+                let Some(mut stack) = self.get(slot) else {
+                    continue;
+                };
+                if same_item(&stack, item) && stack.count < max {
+                    let count = item.count.min(max - stack.count);
+                    stack.count += count;
+                    item.count -= count;
+                    self.set(slot, Some(stack));
+                    merged = true;
+                }
+            }
+        }
+        if item.count > 0 {
+            if let Some(slot) = slots.into_iter().find(|slot| self.get(*slot).is_none()) {
+                self.set(slot, Some(item.clone()));
+                item.count = 0;
+                merged = true;
+            }
+        }
+        merged
+    }
+
+    /// Swaps the items of a slot with those of a hotbar slot.
+    fn swap_hotbar(&mut self, slot: usize, hotbar: usize) {
+        let hotbar = HOTBAR.start + hotbar;
+        let held = self.inventory.get(hotbar);
+        // results are only crafted into empty slots
+        if self.is_result(slot) {
+            if let (Some(item), None) = (self.get(slot), held) {
+                self.inventory.set(hotbar, Some(item));
+                self.set(slot, None);
+                self.take_result();
+            }
+            return;
+        }
+        let in_inventory = self.is_inventory(slot);
+        // items that can't go in the slot move to an empty one instead
+        let swaps = match &held {
+            Some(held) => in_inventory && self.accepts(slot, held),
+            None => true,
+        };
+        let empty = match swaps {
+            true => None,
+            false => self.inventory.first_empty(),
+        };
+        match (self.get(slot), held.clone()) {
+            (Some(item), _) if swaps || empty.is_some() => {
+                self.inventory.set(hotbar, Some(item));
+                match held {
+                    Some(held) if !swaps => {
+                        self.set(slot, None);
+                        self.inventory.add(held);
+                    }
+                    _ => self.set(slot, held),
+                }
+            }
+            (None, Some(held)) if self.accepts(slot, &held) => {
+                self.inventory.set(hotbar, None);
+                self.set(slot, Some(held));
+            }
+            _ => {}
+        }
+    }
+
+    /// Fills the stack held with the items of the same kind, from the
+    /// first slot or the last one, taking from the stacks that aren't full
+    /// first.
+    fn gather(&mut self, slot: usize, button: u8) {
+        let Some(mut cursor) = self.inventory.cursor() else {
+            return;
+        };
+        if self.get(slot).is_some() {
+            return;
+        }
+        let max = max_stack_size(cursor.id);
+        let slots: Vec<_> = match button {
+            0 => (0..self.len()).collect(),
+            _ => (0..self.len()).rev().collect(),
+        };
+        for full in [false, true] {
+            for &slot in &slots {
+                if cursor.count >= max {
+                    break;
+                }
+                let Some(mut stack) = self.get(slot) else {
+                    continue;
+                };
+                if !same_item(&stack, &cursor) || self.is_result(slot) {
+                    continue;
+                }
+                if !full && stack.count == max {
+                    continue;
+                }
+                let count = (max - cursor.count).min(stack.count);
+                stack.count -= count;
+                cursor.count += count;
+                self.set(slot, Some(stack));
+            }
+        }
+        self.inventory.set_cursor(Some(cursor));
+    }
+
+    fn drag(&mut self, slot: i16, button: u8) {
+        let (stage, mode) = (button & 3, button >> 2 & 3);
+        let Some(cursor) = self.inventory.cursor() else {
+            *self.drag = Drag::default();
+            return;
+        };
+        match (self.drag.started, stage) {
+            (false, 0) if mode < 2 || mode == 2 && self.creative => {
+                *self.drag = Drag {
+                    started: true,
+                    mode,
+                    slots: Vec::new(),
+                };
+            }
+            (true, 1) => {
+                let slot = match usize::try_from(slot) {
+                    Ok(slot) if slot < self.len() => slot,
+                    _ => return,
+                };
+                let fits = self.can_add(slot, &cursor)
+                    && self.accepts(slot, &cursor)
+                    && cursor.count as usize > self.drag.slots.len();
+                if fits && !self.drag.slots.contains(&slot) {
+                    self.drag.slots.push(slot);
+                }
+            }
+            (true, 2) => {
+                let drag = std::mem::take(self.drag);
+                self.spread(&cursor, &drag);
+            }
+            _ => *self.drag = Drag::default(),
+        }
+    }
+
+    /// Puts the items held in the slots they were dragged over.
+    fn spread(&mut self, cursor: &ItemStack, drag: &Drag) {
+        if drag.slots.is_empty() {
+            return;
+        }
+        let max = max_stack_size(cursor.id) as u32;
+        let mut left = cursor.count as i32;
+        for &slot in &drag.slots {
+            let fits = self.can_add(slot, cursor)
+                && self.accepts(slot, cursor)
+                && cursor.count as usize >= drag.slots.len();
+            if !fits {
+                continue;
+            }
+            let existing = self.get(slot).map_or(0, |item| item.count as u32);
+            let added = match drag.mode {
+                0 => cursor.count as u32 / drag.slots.len() as u32,
+                1 => 1,
+                _ => max,
+            };
+            let count = (existing + added).min(max).min(self.limit(slot) as u32);
+            left -= count as i32 - existing as i32;
+            self.set(
+                slot,
+                Some(ItemStack {
+                    count: count as u8,
+                    ..cursor.clone()
+                }),
+            );
+        }
+        let cursor = (left > 0).then_some(ItemStack {
+            count: left as u8,
+            ..cursor.clone()
+        });
+        self.inventory.set_cursor(cursor);
+    }
+}
+
+/// The window a player has open over their inventory, and what they were
+/// sent of it and of their inventory.
+pub(crate) struct Windows {
+    /// The window open besides the inventory, with its id.
+    open: Option<(u8, Window)>,
+    last_id: u8,
+    drag: Drag,
+    /// A rejected click the client has yet to acknowledge, clicks in its
+    /// window being ignored until then.
+    rejected: Option<(u8, i16)>,
+    sent_inventory: Vec<Option<ItemStack>>,
+    sent_window: Vec<Option<ItemStack>>,
+    sent_cursor: Option<ItemStack>,
+}
+
+impl Default for Windows {
+    fn default() -> Self {
+        Self {
+            open: None,
+            last_id: 0,
+            drag: Drag::default(),
+            rejected: None,
+            sent_inventory: vec![None; HOTBAR.end],
+            sent_window: Vec::new(),
+            sent_cursor: None,
+        }
+    }
+}
+
+impl Windows {
+    /// The id of the open window, 0 being the inventory.
+    pub fn id(&self) -> u8 {
+        self.open.as_ref().map_or(0, |(id, _)| *id)
+    }
+
+    pub fn window(&mut self) -> Option<&mut Window> {
+        self.open.as_mut().map(|(_, window)| window)
+    }
+
+    /// Opens a window, which must replace none.
+    pub fn open(
+        &mut self,
+        player: u128,
+        window: Window,
+        inventory: &Inventory,
+    ) -> Vec<ProxyBoundPacket> {
+        self.last_id = self.last_id % MAX_WINDOW_ID + 1;
+        let packet = ProxyBoundPacket::OpenWindow {
+            player,
+            window_id: self.last_id,
+            kind: window.kind(),
+            title: window.title().clone(),
+        };
+        self.open = Some((self.last_id, window));
+        self.drag = Drag::default();
+        self.rejected = None;
+        let mut packets = vec![packet];
+        packets.extend(self.resync(player, inventory));
+        packets
+    }
+
+    /// Closes the open window, the player dropping the items held and left
+    /// in the crafting grid, which clients clear.
+    pub fn close(&mut self, inventory: &mut Inventory) -> (Option<Window>, Vec<ItemStack>) {
+        let mut drops: Vec<_> = inventory.cursor().into_iter().collect();
+        inventory.set_cursor(None);
+        self.sent_cursor = None;
+        self.drag = Drag::default();
+        self.rejected = None;
+        self.sent_window.clear();
+
+        let window = match self.open.take() {
+            Some((_, mut window)) => {
+                for slot in window.crafting_grid() {
+                    drops.extend(window.get(slot));
+                    window.set(slot, None);
+                }
+                if window.is_result(0) {
+                    window.set(0, None);
+                }
+                Some(window)
+            }
+            None => {
+                for slot in CRAFTING_GRID {
+                    drops.extend(inventory.get(slot));
+                    inventory.set(slot, None);
+                    self.sent_inventory[slot] = None;
+                }
+                inventory.set(CRAFTING_RESULT, None);
+                self.sent_inventory[CRAFTING_RESULT] = None;
+                None
+            }
+        };
+        (window, drops)
+    }
+
+    /// Clicks a slot of the open window, rejecting the click and sending
+    /// the whole window again when the client predicted another outcome.
+    /// Returns the items dropped along with the packets.
+    #[allow(clippy::too_many_arguments)]
+    pub fn click(
+        &mut self,
+        player: u128,
+        inventory: &mut Inventory,
+        creative: bool,
+        window_id: u8,
+        slot: i16,
+        button: u8,
+        action: i16,
+        mode: ClickMode,
+        item: Option<ItemStack>,
+    ) -> (Vec<ProxyBoundPacket>, Vec<ItemStack>) {
+        let ignored = self.rejected.is_some_and(|(id, _)| id == window_id);
+        if window_id != self.id() || ignored {
+            return (Vec::new(), Vec::new());
+        }
+        let mut clicker = Clicker {
+            window: self.open.as_mut().map(|(_, window)| window),
+            inventory,
+            drag: &mut self.drag,
+            creative,
+            drops: Vec::new(),
+        };
+        let clicked = clicker.click(slot, button, mode);
+        let drops = clicker.drops;
+
+        let accepted = clicked == item;
+        let mut packets = vec![ProxyBoundPacket::ConfirmTransaction {
+            player,
+            window_id,
+            action,
+            accepted,
+        }];
+        if !accepted {
+            self.rejected = Some((window_id, action));
+            packets.extend(self.resync(player, inventory));
+        }
+        (packets, drops)
+    }
+
+    /// Rejects a click whatever it does, sending the whole window again for
+    /// the client to undo it. Returns `None` for clicks that are ignored.
+    pub fn reject(
+        &mut self,
+        player: u128,
+        inventory: &Inventory,
+        window_id: u8,
+        action: i16,
+    ) -> Option<Vec<ProxyBoundPacket>> {
+        let ignored = self.rejected.is_some_and(|(id, _)| id == window_id);
+        if window_id != self.id() || ignored {
+            return None;
+        }
+        self.rejected = Some((window_id, action));
+        let mut packets = vec![ProxyBoundPacket::ConfirmTransaction {
+            player,
+            window_id,
+            action,
+            accepted: false,
+        }];
+        packets.extend(self.resync(player, inventory));
+        Some(packets)
+    }
+
+    /// Takes the acknowledgement of a rejected click.
+    pub fn confirm(&mut self, window_id: u8, action: i16) {
+        if self.rejected == Some((window_id, action)) {
+            self.rejected = None;
+        }
+    }
+
+    /// Sets a slot of the inventory, as players in creative mode do
+    /// whatever window they have open, returning the item to drop for slot
+    /// -1.
+    pub fn creative(
+        &mut self,
+        inventory: &mut Inventory,
+        slot: i16,
+        item: Option<ItemStack>,
+    ) -> Option<ItemStack> {
+        let valid = match &item {
+            Some(item) => item.damage >= 0 && (1..=64).contains(&item.count),
+            None => true,
+        };
+        match usize::try_from(slot) {
+            _ if !valid => None,
+            Ok(slot) if (1..HOTBAR.end).contains(&slot) => {
+                inventory.set(slot, item.clone());
+                // the client already has it
+                self.sent_inventory[slot] = item;
+                None
+            }
+            Ok(_) => None,
+            Err(_) => item,
+        }
+    }
+
+    /// Sends the whole window and the item held.
+    pub fn resync(&mut self, player: u128, inventory: &Inventory) -> Vec<ProxyBoundPacket> {
+        let slots = inventory.slots();
+        let (window_id, items) = match &self.open {
+            Some((id, window)) => {
+                self.sent_window = window.slots().to_vec();
+                self.sent_inventory[MAIN.start..].clone_from_slice(&slots[MAIN.start..]);
+                let items = window.slots().iter().chain(&slots[MAIN.start..]);
+                (*id, items.cloned().collect())
+            }
+            None => {
+                self.sent_inventory = slots.to_vec();
+                (0, slots.to_vec())
+            }
+        };
+        self.sent_cursor = inventory.cursor();
+        vec![
+            ProxyBoundPacket::WindowItems {
+                player,
+                window_id,
+                items,
+            },
+            ProxyBoundPacket::SetSlot {
+                player,
+                window_id: -1,
+                slot: -1,
+                item: inventory.cursor(),
+            },
+        ]
+    }
+
+    /// Sends the slots that changed since they were last sent. Clients only
+    /// take the slots of the open window, so changes to the crafting grid
+    /// and armor wait for it to close.
+    pub fn sync(&mut self, player: u128, inventory: &Inventory) -> Vec<ProxyBoundPacket> {
+        let Self {
+            open,
+            sent_inventory,
+            sent_window,
+            sent_cursor,
+            ..
+        } = self;
+        let mut packets = Vec::new();
+        let mut set_slot = |window_id: u8, slot: usize, item| {
+            packets.push(ProxyBoundPacket::SetSlot {
+                player,
+                window_id: window_id as i8,
+                slot: slot as i16,
+                item,
+            })
+        };
+
+        let (window_id, offset, slots) = match open {
+            Some((id, window)) => {
+                for (slot, (item, sent)) in window.slots().iter().zip(sent_window).enumerate() {
+                    if item != sent {
+                        *sent = item.clone();
+                        set_slot(*id, slot, item.clone());
+                    }
+                }
+                (*id, window.slots().len(), MAIN.start..HOTBAR.end)
+            }
+            None => (0, MAIN.start, 0..HOTBAR.end),
+        };
+        for slot in slots {
+            let item = inventory.get(slot);
+            if item != sent_inventory[slot] {
+                sent_inventory[slot] = item.clone();
+                set_slot(window_id, offset + slot - MAIN.start, item);
+            }
+        }
+
+        if inventory.cursor() != *sent_cursor {
+            *sent_cursor = inventory.cursor();
+            packets.push(ProxyBoundPacket::SetSlot {
+                player,
+                window_id: -1,
+                slot: -1,
+                item: inventory.cursor(),
+            });
+        }
+        packets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use makar_protocol::{Chat, ItemMeta};
+
+    use super::*;
+
+    const STONE: i16 = 1;
+    const HELMET: i16 = 298;
+    const PLANKS: i16 = 5;
+    const STICK: i16 = 280;
+    const IRON: i16 = 265;
+    const IRON_AXE: i16 = 258;
+
+    fn item(id: i16, count: u8) -> Option<ItemStack> {
+        Some(ItemStack {
+            id,
+            count,
+            damage: 0,
+            meta: ItemMeta::default(),
+        })
+    }
+
+    #[derive(Default)]
+    struct Player {
+        inventory: Inventory,
+        window: Option<Window>,
+        drag: Drag,
+    }
+
+    impl Player {
+        fn with_chest() -> Self {
+            Self {
+                window: Some(Window::new(WindowKind::Chest(1), Chat::default())),
+                ..Default::default()
+            }
+        }
+
+        /// Clicks a slot, returning what was in it.
+        fn click(&mut self, slot: i16, button: u8, mode: ClickMode) -> Option<ItemStack> {
+            self.click_and_drop(slot, button, mode).0
+        }
+
+        /// Clicks a slot, returning what was in it and the items dropped.
+        fn click_and_drop(
+            &mut self,
+            slot: i16,
+            button: u8,
+            mode: ClickMode,
+        ) -> (Option<ItemStack>, Vec<ItemStack>) {
+            let mut clicker = Clicker {
+                window: self.window.as_mut(),
+                inventory: &mut self.inventory,
+                drag: &mut self.drag,
+                creative: false,
+                drops: Vec::new(),
+            };
+            let clicked = clicker.click(slot, button, mode);
+            (clicked, clicker.drops)
+        }
+
+        fn window(&self, slot: usize) -> Option<ItemStack> {
+            self.window.as_ref().and_then(|window| window.get(slot))
+        }
+    }
+
+    #[test]
+    fn picks_up_and_puts_down() {
+        let mut player = Player::default();
+        player.inventory.set(9, item(STONE, 7));
+
+        // right clicks take half, rounded up, and put one back
+        assert_eq!(player.click(9, 1, ClickMode::Click), item(STONE, 7));
+        assert_eq!(player.inventory.cursor(), item(STONE, 4));
+        assert_eq!(player.inventory.get(9), item(STONE, 3));
+        player.click(10, 1, ClickMode::Click);
+        assert_eq!(player.inventory.get(10), item(STONE, 1));
+
+        player.click(9, 0, ClickMode::Click);
+        assert_eq!(player.inventory.get(9), item(STONE, 6));
+        assert_eq!(player.inventory.cursor(), None);
+    }
+
+    #[test]
+    fn shift_clicks_within_the_inventory() {
+        let mut player = Player::default();
+        player.inventory.set(9, item(STONE, 64));
+        player.inventory.set(10, item(HELMET, 1));
+        player.inventory.set(37, item(STONE, 60));
+
+        // the main inventory goes to the hotbar, stacks being filled first
+        assert_eq!(player.click(9, 0, ClickMode::ShiftClick), item(STONE, 64));
+        assert_eq!(player.inventory.get(37), item(STONE, 64));
+        assert_eq!(player.inventory.get(36), item(STONE, 60));
+        assert_eq!(player.inventory.get(9), None);
+
+        // armor is put on
+        player.click(10, 0, ClickMode::ShiftClick);
+        assert_eq!(player.inventory.get(ARMOR.start), item(HELMET, 1));
+
+        // and the hotbar goes to the main inventory
+        player.click(36, 0, ClickMode::ShiftClick);
+        assert_eq!(player.inventory.get(9), item(STONE, 60));
+    }
+
+    #[test]
+    fn shift_clicks_between_windows_and_the_inventory() {
+        let mut player = Player::with_chest();
+        player.window.as_mut().unwrap().set(0, item(STONE, 10));
+
+        // from the window to the end of the hotbar
+        player.click(0, 0, ClickMode::ShiftClick);
+        assert_eq!(player.window(0), None);
+        assert_eq!(player.inventory.get(HOTBAR.end - 1), item(STONE, 10));
+
+        // and back to the start of the window, the main inventory being
+        // shown after the window's 9 slots
+        let slot = 9 + (HOTBAR.end - 1 - MAIN.start) as i16;
+        player.click(slot, 0, ClickMode::ShiftClick);
+        assert_eq!(player.window(0), item(STONE, 10));
+        assert_eq!(player.inventory.get(HOTBAR.end - 1), None);
+    }
+
+    #[test]
+    fn drags_items_over_slots() {
+        let mut player = Player::default();
+        player.inventory.set_cursor(item(STONE, 10));
+        player.inventory.set(38, item(STONE, 2));
+
+        // left drags split the items evenly
+        for (slot, button) in [(OUTSIDE, 0), (36, 1), (37, 1), (38, 1), (OUTSIDE, 2)] {
+            player.click(slot, button, ClickMode::Drag);
+        }
+        assert_eq!(player.inventory.get(36), item(STONE, 3));
+        assert_eq!(player.inventory.get(37), item(STONE, 3));
+        assert_eq!(player.inventory.get(38), item(STONE, 5));
+        assert_eq!(player.inventory.cursor(), item(STONE, 1));
+
+        // right drags put one in each slot
+        player.inventory.set_cursor(item(STONE, 10));
+        for (slot, button) in [(OUTSIDE, 4), (9, 5), (10, 5), (OUTSIDE, 6)] {
+            player.click(slot, button, ClickMode::Drag);
+        }
+        assert_eq!(player.inventory.get(9), item(STONE, 1));
+        assert_eq!(player.inventory.get(10), item(STONE, 1));
+        assert_eq!(player.inventory.cursor(), item(STONE, 8));
+    }
+
+    #[test]
+    fn cancels_drags_interrupted_by_other_clicks() {
+        let mut player = Player::default();
+        player.inventory.set_cursor(item(STONE, 10));
+        player.click(OUTSIDE, 0, ClickMode::Drag);
+        player.click(36, 1, ClickMode::Drag);
+        player.click(37, 0, ClickMode::Click);
+        player.click(OUTSIDE, 2, ClickMode::Drag);
+        assert_eq!(player.inventory.get(36), None);
+        assert_eq!(player.inventory.get(37), None);
+        assert_eq!(player.inventory.cursor(), item(STONE, 10));
+    }
+
+    #[test]
+    fn double_clicks_collect_partial_stacks_first() {
+        let mut player = Player::default();
+        player.inventory.set_cursor(item(STONE, 1));
+        player.inventory.set(9, item(STONE, 64));
+        player.inventory.set(40, item(STONE, 10));
+        player.inventory.set(41, item(HELMET, 1));
+
+        player.click(36, 0, ClickMode::DoubleClick);
+        assert_eq!(player.inventory.cursor(), item(STONE, 64));
+        assert_eq!(player.inventory.get(40), None);
+        assert_eq!(player.inventory.get(9), item(STONE, 11));
+        assert_eq!(player.inventory.get(41), item(HELMET, 1));
+    }
+
+    #[test]
+    fn swaps_with_number_keys() {
+        let mut player = Player::default();
+        player.inventory.set(9, item(STONE, 5));
+        player.inventory.set(38, item(HELMET, 1));
+
+        player.click(9, 2, ClickMode::NumberKey);
+        assert_eq!(player.inventory.get(9), item(HELMET, 1));
+        assert_eq!(player.inventory.get(38), item(STONE, 5));
+
+        // onto an empty hotbar slot
+        player.click(9, 0, ClickMode::NumberKey);
+        assert_eq!(player.inventory.get(9), None);
+        assert_eq!(player.inventory.get(36), item(HELMET, 1));
+    }
+
+    #[test]
+    fn swaps_window_slots_with_number_keys() {
+        let mut player = Player::with_chest();
+        player.window.as_mut().unwrap().set(4, item(STONE, 5));
+        player.inventory.set(36, item(HELMET, 1));
+
+        // items from the hotbar can't go in the window's slots
+        player.click(4, 0, ClickMode::NumberKey);
+        assert_eq!(player.inventory.get(36), item(STONE, 5));
+        assert_eq!(player.window(4), None);
+        assert_eq!(player.inventory.get(37), item(HELMET, 1));
+    }
+
+    #[test]
+    fn drops_items_outside_the_window() {
+        let mut player = Player::default();
+        player.inventory.set_cursor(item(STONE, 5));
+
+        let (_, drops) = player.click_and_drop(OUTSIDE, 1, ClickMode::Click);
+        assert_eq!(drops, [item(STONE, 1).unwrap()]);
+        assert_eq!(player.inventory.cursor(), item(STONE, 4));
+        let (_, drops) = player.click_and_drop(OUTSIDE, 0, ClickMode::Click);
+        assert_eq!(drops, [item(STONE, 4).unwrap()]);
+        assert_eq!(player.inventory.cursor(), None);
+    }
+
+    #[test]
+    fn drops_items_from_slots() {
+        let mut player = Player::default();
+        player.inventory.set(9, item(STONE, 5));
+
+        let (_, drops) = player.click_and_drop(9, 0, ClickMode::Drop);
+        assert_eq!(drops, [item(STONE, 1).unwrap()]);
+        let (_, drops) = player.click_and_drop(9, 1, ClickMode::Drop);
+        assert_eq!(drops, [item(STONE, 4).unwrap()]);
+        assert_eq!(player.inventory.get(9), None);
+    }
+
+    #[test]
+    fn crafts_in_the_inventory_grid() {
+        let mut player = Player::default();
+        player.inventory.set_cursor(item(PLANKS, 2));
+        player.click(1, 1, ClickMode::Click);
+        player.click(3, 1, ClickMode::Click);
+        assert_eq!(player.inventory.get(CRAFTING_RESULT), item(STICK, 4));
+
+        // right clicks take the whole result too
+        assert_eq!(player.click(0, 1, ClickMode::Click), item(STICK, 4));
+        assert_eq!(player.inventory.cursor(), item(STICK, 4));
+        assert_eq!(player.inventory.get(1), None);
+        assert_eq!(player.inventory.get(CRAFTING_RESULT), None);
+    }
+
+    #[test]
+    fn adds_results_to_the_items_held() {
+        let mut player = Player::default();
+        player.inventory.set_cursor(item(PLANKS, 4));
+        player.click(1, 1, ClickMode::Click);
+        player.click(1, 1, ClickMode::Click);
+        player.click(3, 0, ClickMode::Click);
+        player.inventory.set_cursor(item(STICK, 60));
+
+        // only whole results are taken
+        player.click(0, 0, ClickMode::Click);
+        assert_eq!(player.inventory.cursor(), item(STICK, 64));
+        assert_eq!(player.inventory.get(1), item(PLANKS, 1));
+        player.click(0, 0, ClickMode::Click);
+        assert_eq!(player.inventory.cursor(), item(STICK, 64));
+        assert_eq!(player.inventory.get(1), item(PLANKS, 1));
+    }
+
+    #[test]
+    fn shift_clicks_results_until_the_grid_runs_out() {
+        let mut player = Player::default();
+        player.inventory.set_cursor(item(PLANKS, 5));
+        player.click(3, 1, ClickMode::Click);
+        player.click(3, 1, ClickMode::Click);
+        player.click(1, 0, ClickMode::Click);
+
+        assert_eq!(player.click(0, 0, ClickMode::ShiftClick), item(STICK, 4));
+        assert_eq!(player.inventory.get(44), item(STICK, 8));
+        assert_eq!(player.inventory.get(1), item(PLANKS, 1));
+        assert_eq!(player.inventory.get(3), None);
+        assert_eq!(player.inventory.get(CRAFTING_RESULT), None);
+    }
+
+    #[test]
+    fn crafts_in_crafting_tables() {
+        let mut player = Player {
+            window: Some(Window::new(WindowKind::CraftingTable, Chat::default())),
+            ..Default::default()
+        };
+        let window = player.window.as_mut().unwrap();
+        for slot in [1, 2, 4] {
+            window.set(slot, item(IRON, 1));
+        }
+        window.set(5, item(STICK, 1));
+        player.inventory.set(36, item(STONE, 1));
+        player.inventory.set_cursor(item(STICK, 1));
+        player.click(8, 0, ClickMode::Click);
+        assert_eq!(player.window(0), item(IRON_AXE, 1));
+
+        // results only go to empty hotbar slots
+        player.click(0, 0, ClickMode::NumberKey);
+        assert_eq!(player.window(0), item(IRON_AXE, 1));
+        player.click(0, 1, ClickMode::NumberKey);
+        assert_eq!(player.inventory.get(37), item(IRON_AXE, 1));
+        assert_eq!(player.window(0), None);
+        assert_eq!(player.window(1), None);
+    }
+}
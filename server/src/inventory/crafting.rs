@@ -0,0 +1,205 @@
+use std::sync::OnceLock;
+
+use makar_protocol::{ItemMeta, ItemStack};
+
+const PLANKS: i16 = 5;
+const STICK: i16 = 280;
+
+/// An item a recipe takes, of any damage when it's unset.
+#[derive(Debug, Clone, Copy)]
+struct Ingredient {
+    id: i16,
+    damage: Option<i16>,
+}
+
+impl Ingredient {
+    fn any(id: i16) -> Self {
+        Self { id, damage: None }
+    }
+
+    fn matches(&self, item: &ItemStack) -> bool {
+        item.id == self.id && self.damage.unwrap_or(item.damage) == item.damage
+    }
+}
+
+enum Shape {
+    /// Rows of the grid, with the ingredient of each character but spaces,
+    /// placed anywhere in the grid and possibly mirrored.
+    Shaped {
+        rows: &'static [&'static str],
+        keys: Vec<(char, Ingredient)>,
+    },
+    /// Ingredients placed anywhere in the grid.
+    Shapeless(Vec<Ingredient>),
+}
+
+struct Recipe {
+    shape: Shape,
+    result: ItemStack,
+}
+
+fn item(id: i16, count: u8, damage: i16) -> ItemStack {
+    ItemStack {
+        id,
+        count,
+        damage,
+        meta: ItemMeta::default(),
+    }
+}
+
+/// Tools by material: swords, shovels, pickaxes, axes then hoes.
+const TOOLS: [(i16, [i16; 5]); 5] = [
+    (PLANKS, [268, 269, 270, 271, 290]),
+    (4, [272, 273, 274, 275, 291]),
+    (265, [267, 256, 257, 258, 292]),
+    (266, [283, 284, 285, 286, 294]),
+    (264, [276, 277, 278, 279, 293]),
+];
+const TOOL_SHAPES: [&[&str]; 5] = [
+    &["X", "X", "S"],
+    &["X", "S", "S"],
+    &["XXX", " S ", " S "],
+    &["XX", "XS", " S"],
+    &["XX", " S", " S"],
+];
+
+/// A few of the vanilla recipes: planks, sticks, torches, the blocks
+/// needed early on and tools.
+fn recipes() -> &'static [Recipe] {
+    static RECIPES: OnceLock<Vec<Recipe>> = OnceLock::new();
+    RECIPES.get_or_init(|| {
+        let shaped = |rows, keys: &[(char, i16)], result| Recipe {
+            shape: Shape::Shaped {
+                rows,
+                keys: keys
+                    .iter()
+                    .map(|(key, id)| (*key, Ingredient::any(*id)))
+                    .collect(),
+            },
+            result,
+        };
+        // oak, spruce, birch and jungle logs, then acacia and dark oak ones
+        let logs = (0..4).map(|damage| (17, damage, damage));
+        let logs = logs.chain((0..2).map(|damage| (162, damage, damage + 4)));
+        let mut recipes: Vec<_> = logs
+            .map(|(id, damage, planks)| Recipe {
+                shape: Shape::Shapeless(vec![Ingredient {
+                    id,
+                    damage: Some(damage),
+                }]),
+                result: item(PLANKS, 4, planks),
+            })
+            .collect();
+        recipes.extend([
+            shaped(&["P", "P"], &[('P', PLANKS)], item(STICK, 4, 0)),
+            shaped(&["C", "S"], &[('C', 263), ('S', STICK)], item(50, 4, 0)),
+            shaped(&["PP", "PP"], &[('P', PLANKS)], item(58, 1, 0)),
+            shaped(&["PPP", "P P", "PPP"], &[('P', PLANKS)], item(54, 1, 0)),
+            shaped(&["CCC", "C C", "CCC"], &[('C', 4)], item(61, 1, 0)),
+        ]);
+        for (material, tools) in TOOLS {
+            for (rows, tool) in TOOL_SHAPES.into_iter().zip(tools) {
+                let keys = [('X', material), ('S', STICK)];
+                recipes.push(shaped(rows, &keys, item(tool, 1, 0)));
+            }
+        }
+        recipes
+    })
+}
+
+/// What the items in a square crafting grid make, if anything.
+pub fn craft(grid: &[Option<ItemStack>], width: usize) -> Option<ItemStack> {
+    let filled: Vec<_> = (0..grid.len()).filter(|i| grid[*i].is_some()).collect();
+    let (first, last) = (filled.first()?, filled.last()?);
+    let rows = first / width..last / width + 1;
+    let columns = filled.iter().map(|i| i % width);
+    let columns = columns.clone().min()?..columns.max()? + 1;
+    let at = |x: usize, y: usize| grid[(rows.start + y) * width + columns.start + x].as_ref();
+
+    let recipe = recipes().iter().find(|recipe| match &recipe.shape {
+        Shape::Shaped {
+            rows: pattern,
+            keys,
+        } => {
+            if pattern.len() != rows.len() || pattern[0].len() != columns.len() {
+                return false;
+            }
+            let matches = |mirrored: bool| {
+                pattern.iter().enumerate().all(|(y, row)| {
+                    row.chars().enumerate().all(|(x, key)| {
+                        let x = if mirrored { columns.len() - 1 - x } else { x };
+                        match (keys.iter().find(|(k, _)| *k == key), at(x, y)) {
+                            (Some((_, ingredient)), Some(item)) => ingredient.matches(item),
+                            (None, None) => true,
+                            _ => false,
+                        }
+                    })
+                })
+            };
+            matches(false) || matches(true)
+        }
+        Shape::Shapeless(ingredients) => {
+            let mut left: Vec<_> = filled.iter().filter_map(|i| grid[*i].as_ref()).collect();
+            left.len() == ingredients.len()
+                && ingredients.iter().all(|ingredient| {
+                    match left.iter().position(|item| ingredient.matches(item)) {
+                        Some(i) => {
+                            left.swap_remove(i);
+                            true
+                        }
+                        None => false,
+                    }
+                })
+        }
+    })?;
+    Some(recipe.result.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A grid from the ids of the items in its slots, 0 for empty ones.
+    fn grid(ids: &[i16]) -> Vec<Option<ItemStack>> {
+        ids.iter()
+            .map(|id| (*id != 0).then(|| item(*id, 1, 0)))
+            .collect()
+    }
+
+    #[test]
+    fn crafts_shaped_recipes_anywhere_in_the_grid() {
+        let sticks = Some(item(STICK, 4, 0));
+        assert_eq!(craft(&grid(&[5, 0, 5, 0]), 2), sticks);
+        assert_eq!(craft(&grid(&[0, 5, 0, 5]), 2), sticks);
+        assert_eq!(craft(&grid(&[0, 0, 0, 0, 0, 5, 0, 0, 5]), 3), sticks);
+        assert_eq!(craft(&grid(&[5, 5, 0, 0]), 2), None);
+    }
+
+    #[test]
+    fn crafts_mirrored_recipes() {
+        let axe = Some(item(258, 1, 0));
+        let (i, s) = (265, STICK);
+        assert_eq!(craft(&grid(&[i, i, 0, i, s, 0, 0, s, 0]), 3), axe);
+        assert_eq!(craft(&grid(&[0, i, i, 0, s, i, 0, s, 0]), 3), axe);
+        assert_eq!(craft(&grid(&[i, i, 0, s, i, 0, 0, s, 0]), 3), None);
+    }
+
+    #[test]
+    fn needs_every_slot_of_a_shape() {
+        let p = PLANKS;
+        let chest = Some(item(54, 1, 0));
+        assert_eq!(craft(&grid(&[p, p, p, p, 0, p, p, p, p]), 3), chest);
+        assert_eq!(craft(&grid(&[p, p, p, p, p, p, p, p, p]), 3), None);
+        assert_eq!(craft(&grid(&[p, p, p, p, 0, p, p, p, 0]), 3), None);
+    }
+
+    #[test]
+    fn crafts_shapeless_recipes_by_damage() {
+        let mut logs = vec![None; 4];
+        logs[3] = Some(item(162, 1, 1));
+        assert_eq!(craft(&logs, 2), Some(item(PLANKS, 4, 5)));
+        logs[0] = Some(item(17, 1, 2));
+        assert_eq!(craft(&logs, 2), None);
+        assert_eq!(craft(&vec![None; 9], 3), None);
+    }
+}
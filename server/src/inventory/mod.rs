@@ -0,0 +1,178 @@
+use std::ops::Range;
+
+use makar_protocol::ItemStack;
+
+mod click;
+mod crafting;
+mod tool;
+mod window;
+
+pub(crate) use click::Windows;
+pub use crafting::craft;
+pub use tool::{Tool, ToolKind};
+pub use window::Window;
+
+/// Slots of a player's inventory, numbered like in its window.
+pub const CRAFTING_RESULT: usize = 0;
+pub const CRAFTING_GRID: Range<usize> = 1..5;
+/// Helmet, chestplate, leggings and boots.
+pub const ARMOR: Range<usize> = 5..9;
+pub const MAIN: Range<usize> = 9..36;
+pub const HOTBAR: Range<usize> = 36..45;
+const SIZE: usize = 45;
+
+/// Items that don't stack, by id.
+const UNSTACKABLE: &[(i16, i16)] = &[
+    (256, 259),
+    (261, 261),
+    (267, 279),
+    (282, 286),
+    (290, 294),
+    (298, 317),
+    (326, 329),
+    (333, 333),
+    (335, 335),
+    (342, 343),
+    (346, 346),
+    (354, 355),
+    (359, 359),
+    (373, 373),
+    (386, 386),
+    (398, 398),
+    (403, 403),
+    (407, 408),
+    (413, 413),
+    (417, 419),
+    (422, 422),
+    (2256, 2267),
+];
+/// Items stacking up to 16, by id.
+const STACKS_OF_16: &[(i16, i16)] = &[
+    (323, 323),
+    (325, 325),
+    (332, 332),
+    (344, 344),
+    (368, 368),
+    (387, 387),
+    (416, 416),
+    (425, 425),
+];
+
+/// Most items of a kind a slot holds.
+pub fn max_stack_size(id: i16) -> u8 {
+    let within =
+        |ranges: &[(i16, i16)]| ranges.iter().any(|(from, to)| (*from..=*to).contains(&id));
+    if within(UNSTACKABLE) {
+        1
+    } else if within(STACKS_OF_16) {
+        16
+    } else {
+        64
+    }
+}
+
+/// The armor slot an item is worn in.
+pub fn armor_slot(id: i16) -> Option<usize> {
+    match id {
+        298..=317 => Some(ARMOR.start + (id - 298) as usize % 4),
+        // pumpkins and heads
+        86 | 397 => Some(ARMOR.start),
+        _ => None,
+    }
+}
+
+/// Whether two stacks are of the same item, and can be merged.
+pub fn same_item(a: &ItemStack, b: &ItemStack) -> bool {
+    a.id == b.id && a.damage == b.damage && a.meta == b.meta
+}
+
+/// The items a player carries, and the one held by their cursor while their
+/// inventory is open.
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+    /// The hotbar slot held, from 0 to 8.
+    held: u8,
+    cursor: Option<ItemStack>,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self {
+            slots: vec![None; SIZE],
+            held: 0,
+            cursor: None,
+        }
+    }
+}
+
+impl Inventory {
+    pub fn get(&self, slot: usize) -> Option<ItemStack> {
+        self.slots.get(slot).cloned().flatten()
+    }
+
+    pub fn set(&mut self, slot: usize, item: Option<ItemStack>) {
+        if let Some(slot) = self.slots.get_mut(slot) {
+            *slot = item.filter(|item| item.count > 0);
+        }
+    }
+
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    pub fn held(&self) -> u8 {
+        self.held
+    }
+
+    pub fn set_held(&mut self, held: u8) {
+        self.held = held.min(8);
+    }
+
+    pub fn held_item(&self) -> Option<ItemStack> {
+        self.get(HOTBAR.start + self.held as usize)
+    }
+
+    pub fn cursor(&self) -> Option<ItemStack> {
+        self.cursor.clone()
+    }
+
+    pub fn set_cursor(&mut self, item: Option<ItemStack>) {
+        self.cursor = item.filter(|item| item.count > 0);
+    }
+
+    /// Adds items to the stacks of the same item, then to the first empty
+    /// slot, the hotbar first, returning those that didn't fit.
+    pub fn add(&mut self, mut item: ItemStack) -> Option<ItemStack> {
+        let max = max_stack_size(item.id);
+        for slot in HOTBAR.chain(MAIN) {
+            let Some(stack) = &mut self.slots[slot] else {
+                continue;
+            };
+            if same_item(stack, &item) && stack.count < max {
+                let moved = item.count.min(max - stack.count);
+                stack.count += moved;
+                item.count -= moved;
+                if item.count == 0 {
+                    return None;
+                }
+            }
+        }
+        match self.first_empty() {
+            Some(slot) => {
+                self.slots[slot] = Some(item);
+                None
+            }
+            None => Some(item),
+        }
+    }
+
+    /// The first empty slot of the hotbar or main inventory.
+    pub fn first_empty(&self) -> Option<usize> {
+        HOTBAR.chain(MAIN).find(|slot| self.slots[*slot].is_none())
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.fill(None);
+        self.cursor = None;
+    }
+}
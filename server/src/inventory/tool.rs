@@ -0,0 +1,88 @@
+use makar_protocol::ItemStack;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolKind {
+    Pickaxe,
+    Shovel,
+    Axe,
+    Sword,
+    Shears,
+}
+
+/// What an item digs blocks with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tool {
+    pub kind: ToolKind,
+    /// The hardest blocks the tool harvests, from 0 for wood and gold to 3
+    /// for diamond.
+    pub level: u8,
+    /// How much faster than a hand the tool digs the blocks it's made for.
+    pub speed: f32,
+    /// Uses before the tool breaks.
+    pub durability: i16,
+}
+
+/// Wood, stone, iron, diamond and gold, with their level, speed and
+/// durability.
+const MATERIALS: [(u8, f32, i16); 5] = [
+    (0, 2.0, 59),
+    (1, 4.0, 131),
+    (2, 6.0, 250),
+    (3, 8.0, 1561),
+    (0, 12.0, 32),
+];
+
+impl Tool {
+    pub fn of(item: &ItemStack) -> Option<Self> {
+        use ToolKind::*;
+        let (kind, material) = match item.id {
+            256 => (Shovel, 2),
+            257 => (Pickaxe, 2),
+            258 => (Axe, 2),
+            267 => (Sword, 2),
+            268..=271 => ([Sword, Shovel, Pickaxe, Axe][item.id as usize - 268], 0),
+            272..=275 => ([Sword, Shovel, Pickaxe, Axe][item.id as usize - 272], 1),
+            276..=279 => ([Sword, Shovel, Pickaxe, Axe][item.id as usize - 276], 3),
+            283..=286 => ([Sword, Shovel, Pickaxe, Axe][item.id as usize - 283], 4),
+            359 => {
+                return Some(Self {
+                    kind: Shears,
+                    level: 0,
+                    speed: 1.0,
+                    durability: 238,
+                })
+            }
+            _ => return None,
+        };
+        let (level, speed, durability) = MATERIALS[material];
+        Some(Self {
+            kind,
+            level,
+            speed,
+            durability,
+        })
+    }
+
+    /// How much more than a hand the tool hurts entities.
+    pub fn attack_damage(&self) -> f32 {
+        let bonus = match self.kind {
+            ToolKind::Sword => 4.0,
+            ToolKind::Axe => 3.0,
+            ToolKind::Pickaxe => 2.0,
+            ToolKind::Shovel => 1.0,
+            ToolKind::Shears => return 0.0,
+        };
+        // the level of a material is how much harder its tools hit
+        bonus + self.level as f32
+    }
+
+    /// Wears the tool out after using it on a block, swords wearing twice as
+    /// fast, returning `None` once it breaks.
+    pub fn wear(&self, mut item: ItemStack) -> Option<ItemStack> {
+        item.damage += match self.kind {
+            ToolKind::Sword => 2,
+            _ => 1,
+        };
+        (item.damage <= self.durability).then_some(item)
+    }
+}
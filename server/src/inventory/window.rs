@@ -0,0 +1,63 @@
+use makar_protocol::{Chat, ItemStack, WindowKind};
+
+/// A window opened over the inventory of a player, like a chest, with its
+/// own slots.
+#[derive(Debug, Clone)]
+pub struct Window {
+    kind: WindowKind,
+    title: Chat,
+    slots: Vec<Option<ItemStack>>,
+}
+
+impl Window {
+    pub fn new(kind: WindowKind, title: Chat) -> Self {
+        let size = match kind {
+            WindowKind::Chest(rows) => rows.clamp(1, 6) as usize * 9,
+            // the result, then the grid
+            WindowKind::CraftingTable => 10,
+            WindowKind::Dispenser => 9,
+            WindowKind::Hopper => 5,
+        };
+        Self {
+            kind,
+            title,
+            slots: vec![None; size],
+        }
+    }
+
+    pub fn kind(&self) -> WindowKind {
+        self.kind
+    }
+
+    pub fn title(&self) -> &Chat {
+        &self.title
+    }
+
+    pub fn get(&self, slot: usize) -> Option<ItemStack> {
+        self.slots.get(slot).cloned().flatten()
+    }
+
+    pub fn set(&mut self, slot: usize, item: Option<ItemStack>) {
+        if let Some(slot) = self.slots.get_mut(slot) {
+            *slot = item.filter(|item| item.count > 0);
+        }
+    }
+
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    /// Whether a slot only holds what's crafted, items being taken from it
+    /// but never put in.
+    pub fn is_result(&self, slot: usize) -> bool {
+        self.kind == WindowKind::CraftingTable && slot == 0
+    }
+
+    /// The slots items are left in while crafting.
+    pub fn crafting_grid(&self) -> std::ops::Range<usize> {
+        match self.kind {
+            WindowKind::CraftingTable => 1..10,
+            _ => 0..0,
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use std::{
+    collections::HashMap, error::Error, net::SocketAddr, path::PathBuf, sync::Arc, thread,
+    time::Instant,
+};
+
+use makar_protocol::*;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpListener, TcpStream,
+    },
+    sync::mpsc::{self, error::TryRecvError},
+    task::{self, JoinHandle, JoinSet},
+};
+
+use log::{error, info, warn};
+
+pub mod boss_bar;
+pub mod entity;
+pub mod game;
+pub mod health;
+pub mod inventory;
+pub mod menu;
+pub mod scheduler;
+pub mod scoreboard;
+pub mod tab_list;
+pub mod title;
+pub mod view;
+pub mod world;
+
+use game::{Game, TICK_DURATION};
+use world::{
+    generator::{ChunkGenerator, FlatGenerator},
+    Dimension, World,
+};
+
+/// What the connections to proxies tell the game.
+enum Incoming {
+    /// A proxy connected, the packets for its players going through the
+    /// sender.
+    Connected(usize, mpsc::UnboundedSender<Vec<ProxyBoundPacket>>),
+    Packet(usize, ServerBoundPacket),
+    Disconnected(usize),
+}
+
+async fn connection_task(
+    socket: TcpStream,
+    proxy: usize,
+    incoming: mpsc::Sender<Incoming>,
+) -> Result<(), Box<dyn Error>> {
+    let (reader, writer) = socket.into_split();
+    let (outgoing, packets) = mpsc::unbounded_channel();
+    if incoming
+        .send(Incoming::Connected(proxy, outgoing))
+        .await
+        .is_err()
+    {
+        return Ok(());
+    }
+
+    // reads aren't cancel safe, so writes happen on their own task
+    let mut writer = tokio::spawn(write_task(writer, packets));
+    let res = tokio::select! {
+        res = read_task(reader, proxy, &incoming) => res,
+        // the writer only stops once it couldn't write
+        res = &mut writer => res?,
+    };
+    writer.abort();
+    let _ = incoming.send(Incoming::Disconnected(proxy)).await;
+    res.map_err(|e| e as Box<dyn Error>)
+}
+
+async fn read_task(
+    mut reader: OwnedReadHalf,
+    proxy: usize,
+    incoming: &mpsc::Sender<Incoming>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut size = [0u8; 4];
+    loop {
+        reader.read_exact(&mut size).await?;
+        let size = u32::from_be_bytes(size);
+
+        let mut buffer = vec![0u8; size as usize];
+        reader.read_exact(&mut buffer).await?;
+
+        let packet = ServerBoundPacket::deserialize(&buffer)?;
+        if incoming
+            .send(Incoming::Packet(proxy, packet))
+            .await
+            .is_err()
+        {
+            return Ok(());
+        }
+    }
+}
+
+/// Writes the packets of each tick until the game stops.
+async fn write_task(
+    mut writer: OwnedWriteHalf,
+    mut packets: mpsc::UnboundedReceiver<Vec<ProxyBoundPacket>>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    while let Some(packets) = packets.recv().await {
+        for packet in packets {
+            writer.write_all(&packet.serialize()?).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Runs the game 20 times per second for every proxy until the server
+/// stops, each tick handling the packets that came in since the last one,
+/// then saves it.
+fn serve(config: &ServerConfig, mut incoming: mpsc::Receiver<Incoming>) {
+    let generator = config.generator.clone();
+    let world = match &config.world {
+        Some(directory) => World::open(directory, Dimension::Overworld, generator),
+        None => World::new(Dimension::Overworld, generator),
+    };
+    let mut game = Game::new(world, config.view_distance);
+    (config.setup)(&mut game);
+
+    let mut proxies = HashMap::new();
+    // the proxy each player joined through
+    let mut owners = HashMap::new();
+    let mut next = Instant::now();
+    loop {
+        thread::sleep(next.saturating_duration_since(Instant::now()));
+
+        let mut packets = Vec::new();
+        let stopped = loop {
+            let (proxy, packet) = match incoming.try_recv() {
+                Ok(Incoming::Connected(proxy, outgoing)) => {
+                    proxies.insert(proxy, outgoing);
+                    continue;
+                }
+                Ok(Incoming::Packet(proxy, packet)) => (proxy, packet),
+                Ok(Incoming::Disconnected(proxy)) => {
+                    proxies.remove(&proxy);
+                    // the players of a proxy that went away leave with it
+                    owners.retain(|player, owner| {
+                        if *owner == proxy {
+                            packets.push(ServerBoundPacket::LeaveGame { player: *player });
+                        }
+                        *owner != proxy
+                    });
+                    continue;
+                }
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            };
+            match &packet {
+                ServerBoundPacket::JoinGameRequest { id, .. } => {
+                    owners.insert(*id, proxy);
+                }
+                ServerBoundPacket::LeaveGame { player } => {
+                    owners.remove(player);
+                }
+                _ => {}
+            }
+            packets.push(packet);
+        };
+        game.run_tick(packets);
+
+        let mut sorted: HashMap<usize, Vec<ProxyBoundPacket>> = HashMap::new();
+        for packet in game.take_outgoing() {
+            if let Some(proxy) = owners.get(&packet.player()) {
+                sorted.entry(*proxy).or_default().push(packet);
+            }
+        }
+        for (proxy, packets) in sorted {
+            if let Some(outgoing) = proxies.get(&proxy) {
+                // proxies that can't be written to are about to disconnect
+                let _ = outgoing.send(packets);
+            }
+        }
+        if stopped {
+            break;
+        }
+        // an overloaded server runs fewer ticks rather than catching up
+        next = (next + TICK_DURATION).max(Instant::now());
+    }
+    game.save();
+    game.flush();
+}
+
+pub struct ServerConfig {
+    pub address: String,
+    /// Directory of an Anvil world to serve, chunks being generated in
+    /// memory when unset.
+    pub world: Option<PathBuf>,
+    /// Creates the chunks that were never saved.
+    pub generator: Arc<dyn ChunkGenerator>,
+    /// Farthest players can see in chunks, whatever their own settings.
+    pub view_distance: i32,
+    /// Called on the game before it runs its first tick, to set it up and
+    /// schedule the tasks running it.
+    pub setup: Arc<dyn Fn(&mut Game) + Send + Sync>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            address: "127.0.0.1:25566".to_string(),
+            world: None,
+            generator: Arc::new(FlatGenerator::default()),
+            view_distance: 10,
+            setup: Arc::new(|_| {}),
+        }
+    }
+}
+
+pub struct ServerHandle {
+    address: SocketAddr,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl ServerHandle {
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Waits until the server stops accepting proxies.
+    pub async fn wait(mut self) -> Result<(), Box<dyn Error>> {
+        for task in self.tasks.drain(..) {
+            task.await?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Starts the game and accepts proxy connections, returning once the
+/// server is ready.
+pub async fn run(config: ServerConfig) -> Result<ServerHandle, Box<dyn Error>> {
+    let listener = TcpListener::bind(&config.address).await?;
+    let address = listener.local_addr()?;
+    info!("accepting connections on {address}");
+
+    // ticks block on the game, so they run on a thread of their own, until
+    // the channel closes once the accept task and its connections are gone
+    let (incoming, rx) = mpsc::channel(256);
+    let config = Arc::new(config);
+    task::spawn_blocking(move || serve(&config, rx));
+
+    let tasks = vec![tokio::spawn(accept_task(listener, incoming))];
+    Ok(ServerHandle { address, tasks })
+}
+
+async fn accept_task(listener: TcpListener, incoming: mpsc::Sender<Incoming>) {
+    // connections are aborted along with this task, and once the last one is
+    // gone the game stops
+    let mut connections = JoinSet::new();
+    for proxy in 0.. {
+        let socket = tokio::select! {
+            res = listener.accept() => match res {
+                Ok((socket, _)) => socket,
+                Err(e) => {
+                    warn!("couldn't accept new connection: {e}");
+                    continue;
+                }
+            },
+            Some(_) = connections.join_next() => continue,
+        };
+        let incoming = incoming.clone();
+        connections.spawn(async move {
+            match connection_task(socket, proxy, incoming).await {
+                Ok(_) => {}
+                Err(e) => {
+                    error!("connection task ended unexpectingly: {e}");
+                }
+            };
+        });
+    }
+}
@@ -0,0 +1,247 @@
+use std::sync::Arc;
+
+use makar_protocol::{Chat, ClickMode, ItemMeta, ItemStack, WindowKind};
+
+use crate::{game::Game, inventory::Window};
+
+/// Item of the buttons turning pages.
+const ARROW: i16 = 262;
+const SLOTS_PER_ROW: usize = 9;
+
+type ClickHandler = Arc<dyn Fn(&mut Game, &MenuClick) + Send + Sync>;
+
+/// A click on an item of a menu.
+#[derive(Debug, Clone, Copy)]
+pub struct MenuClick {
+    pub player: u128,
+    /// The index of the item in the menu, whatever its page.
+    pub index: usize,
+    pub mode: ClickMode,
+    pub button: u8,
+}
+
+/// An item of a menu, with what clicking it does.
+#[derive(Clone)]
+pub struct MenuItem {
+    item: ItemStack,
+    on_click: Option<ClickHandler>,
+}
+
+impl MenuItem {
+    pub fn new(id: i16) -> Self {
+        Self {
+            item: ItemStack {
+                id,
+                count: 1,
+                damage: 0,
+                meta: ItemMeta::default(),
+            },
+            on_click: None,
+        }
+    }
+
+    pub fn damage(mut self, damage: i16) -> Self {
+        self.item.damage = damage;
+        self
+    }
+
+    pub fn count(mut self, count: u8) -> Self {
+        self.item.count = count.max(1);
+        self
+    }
+
+    /// Names the item, with legacy formatting codes.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.item.meta.name = Some(name.into());
+        self
+    }
+
+    /// Adds lines below the name, with legacy formatting codes.
+    pub fn lore<L: Into<String>>(mut self, lines: impl IntoIterator<Item = L>) -> Self {
+        self.item
+            .meta
+            .lore
+            .extend(lines.into_iter().map(Into::into));
+        self
+    }
+
+    /// Makes the item shine like an enchanted one.
+    pub fn glint(mut self) -> Self {
+        self.item.meta.glint = true;
+        self
+    }
+
+    pub fn on_click(mut self, f: impl Fn(&mut Game, &MenuClick) + Send + Sync + 'static) -> Self {
+        self.on_click = Some(Arc::new(f));
+        self
+    }
+
+    pub fn item(&self) -> &ItemStack {
+        &self.item
+    }
+}
+
+/// What a slot of a menu shows.
+enum Shown {
+    Item(usize),
+    Previous,
+    Next,
+}
+
+/// A chest whose items players click rather than take, split in pages
+/// when they don't fit.
+///
+/// Menus with more items than slots keep their bottom row for the arrows
+/// turning pages. Changes to the menu a player has open are sent at the
+/// end of the tick.
+#[derive(Clone)]
+pub struct Menu {
+    title: Chat,
+    rows: u8,
+    items: Vec<Option<MenuItem>>,
+    page: usize,
+}
+
+impl Menu {
+    /// A menu of 1 to 6 rows, those of a single row having no room for
+    /// arrows.
+    pub fn new(title: Chat, rows: u8) -> Self {
+        Self {
+            title,
+            rows: rows.clamp(1, 6),
+            items: Vec::new(),
+            page: 0,
+        }
+    }
+
+    pub fn title(&self) -> &Chat {
+        &self.title
+    }
+
+    pub fn rows(&self) -> u8 {
+        self.rows
+    }
+
+    pub fn get(&self, index: usize) -> Option<&MenuItem> {
+        self.items.get(index)?.as_ref()
+    }
+
+    /// Sets an item, adding pages for it if needed.
+    pub fn set(&mut self, index: usize, item: Option<MenuItem>) {
+        if index >= self.items.len() {
+            if item.is_none() {
+                return;
+            }
+            self.items.resize(index + 1, None);
+        }
+        self.items[index] = item;
+        while let Some(None) = self.items.last() {
+            self.items.pop();
+        }
+        self.page = self.page.min(self.pages() - 1);
+    }
+
+    /// Adds an item after the last one.
+    pub fn push(&mut self, item: MenuItem) {
+        self.set(self.items.len(), Some(item));
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.page = 0;
+    }
+
+    fn slots(&self) -> usize {
+        self.rows as usize * SLOTS_PER_ROW
+    }
+
+    fn per_page(&self) -> usize {
+        match self.items.len() > self.slots() && self.rows > 1 {
+            true => self.slots() - SLOTS_PER_ROW,
+            false => self.slots(),
+        }
+    }
+
+    pub fn pages(&self) -> usize {
+        self.items.len().div_ceil(self.per_page()).max(1)
+    }
+
+    /// The page shown, from 0.
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
+    pub fn set_page(&mut self, page: usize) {
+        self.page = page.min(self.pages() - 1);
+    }
+
+    fn shown(&self, slot: usize) -> Option<Shown> {
+        let per_page = self.per_page();
+        match slot.checked_sub(per_page) {
+            None => Some(Shown::Item(self.page * per_page + slot)),
+            Some(0) if self.page > 0 => Some(Shown::Previous),
+            Some(arrow) if arrow == SLOTS_PER_ROW - 1 && self.page + 1 < self.pages() => {
+                Some(Shown::Next)
+            }
+            Some(_) => None,
+        }
+    }
+
+    fn arrow(&self, name: &str, page: usize) -> ItemStack {
+        let lore = format!("Page {} of {}", page + 1, self.pages());
+        MenuItem::new(ARROW).name(name).lore([lore]).item
+    }
+
+    /// The items of the window showing the menu.
+    pub(crate) fn render(&self) -> Vec<Option<ItemStack>> {
+        (0..self.slots())
+            .map(|slot| match self.shown(slot)? {
+                Shown::Item(index) => self.get(index).map(|item| item.item.clone()),
+                Shown::Previous => Some(self.arrow("Previous page", self.page - 1)),
+                Shown::Next => Some(self.arrow("Next page", self.page + 1)),
+            })
+            .collect()
+    }
+
+    pub(crate) fn window(&self) -> Window {
+        let mut window = Window::new(WindowKind::Chest(self.rows), self.title.clone());
+        for (slot, item) in self.render().into_iter().enumerate() {
+            window.set(slot, item);
+        }
+        window
+    }
+
+    /// Turns pages for clicks on arrows, returning what clicking an item
+    /// does.
+    pub(crate) fn click(
+        &mut self,
+        player: u128,
+        slot: i16,
+        mode: ClickMode,
+        button: u8,
+    ) -> Option<(ClickHandler, MenuClick)> {
+        let slot = usize::try_from(slot)
+            .ok()
+            .filter(|slot| *slot < self.slots())?;
+        match self.shown(slot)? {
+            Shown::Item(index) => {
+                let handler = self.get(index)?.on_click.clone()?;
+                let click = MenuClick {
+                    player,
+                    index,
+                    mode,
+                    button,
+                };
+                Some((handler, click))
+            }
+            Shown::Previous => {
+                self.page -= 1;
+                None
+            }
+            Shown::Next => {
+                self.page += 1;
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::{BTreeMap, HashSet};
+
+/// Identifies a scheduled task so that it can be cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TaskId(u64);
+
+type Callback<T> = Box<dyn FnMut(&mut T) + Send>;
+
+pub struct Task<T> {
+    id: TaskId,
+    /// Ticks between runs, `None` for tasks that run once.
+    period: Option<u64>,
+    callback: Callback<T>,
+}
+
+impl<T> Task<T> {
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    pub fn run(&mut self, state: &mut T) {
+        (self.callback)(state)
+    }
+}
+
+/// Tasks to run on future ticks, handed a mutable reference to the state
+/// owning the scheduler when they're due.
+///
+/// The owner drives it every tick: it takes the due tasks with
+/// [`Scheduler::advance`], runs them, then gives them back with
+/// [`Scheduler::reschedule`] so that repeating ones run again.
+pub struct Scheduler<T> {
+    tick: u64,
+    next_id: u64,
+    /// Tasks by the tick they're due on, in scheduling order.
+    tasks: BTreeMap<(u64, TaskId), Task<T>>,
+    /// Tasks taken by [`Scheduler::advance`] and not given back yet.
+    running: HashSet<TaskId>,
+    /// Running tasks that were cancelled.
+    cancelled: HashSet<TaskId>,
+}
+
+impl<T> Default for Scheduler<T> {
+    fn default() -> Self {
+        Self {
+            tick: 0,
+            next_id: 0,
+            tasks: BTreeMap::new(),
+            running: HashSet::new(),
+            cancelled: HashSet::new(),
+        }
+    }
+}
+
+impl<T> Scheduler<T> {
+    /// The tick being run.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    fn schedule(&mut self, delay: u64, period: Option<u64>, callback: Callback<T>) -> TaskId {
+        let id = TaskId(self.next_id);
+        self.next_id += 1;
+        // a task can't run on the tick it was scheduled on as that tick's
+        // tasks were already taken
+        let due = self.tick + delay.max(1);
+        self.tasks.insert(
+            (due, id),
+            Task {
+                id,
+                period,
+                callback,
+            },
+        );
+        id
+    }
+
+    /// Runs a task once, `delay` ticks from now.
+    pub fn run_later(&mut self, delay: u64, f: impl FnMut(&mut T) + Send + 'static) -> TaskId {
+        self.schedule(delay, None, Box::new(f))
+    }
+
+    /// Runs a task `delay` ticks from now, then every `period` ticks until
+    /// it's cancelled.
+    pub fn run_repeating(
+        &mut self,
+        delay: u64,
+        period: u64,
+        f: impl FnMut(&mut T) + Send + 'static,
+    ) -> TaskId {
+        self.schedule(delay, Some(period.max(1)), Box::new(f))
+    }
+
+    /// Cancels a task, returning whether it was still scheduled.
+    pub fn cancel(&mut self, id: TaskId) -> bool {
+        match self.tasks.keys().find(|(_, task)| *task == id).copied() {
+            Some(key) => {
+                self.tasks.remove(&key);
+                true
+            }
+            // running tasks can't be taken back, they just won't be rescheduled
+            None if self.running.contains(&id) => self.cancelled.insert(id),
+            None => false,
+        }
+    }
+
+    /// Moves on to the next tick, returning the tasks due on it.
+    pub fn advance(&mut self) -> Vec<Task<T>> {
+        self.tick += 1;
+
+        let later = self.tasks.split_off(&(self.tick + 1, TaskId(0)));
+        let due: Vec<_> = std::mem::replace(&mut self.tasks, later)
+            .into_values()
+            .collect();
+        self.running = due.iter().map(Task::id).collect();
+        self.cancelled.clear();
+        due
+    }
+
+    /// Schedules the next run of a task that just ran, if it repeats and
+    /// wasn't cancelled.
+    pub fn reschedule(&mut self, task: Task<T>) {
+        self.running.remove(&task.id);
+        if self.cancelled.remove(&task.id) {
+            return;
+        }
+        if let Some(period) = task.period {
+            self.tasks.insert((self.tick + period, task.id), task);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Log = Vec<&'static str>;
+
+    fn log(entry: &'static str) -> impl FnMut(&mut Log) + Send {
+        move |log: &mut Log| log.push(entry)
+    }
+
+    /// Runs the next tick, returning what its tasks logged.
+    fn run(scheduler: &mut Scheduler<Log>) -> Log {
+        let mut log = Log::new();
+        for mut task in scheduler.advance() {
+            task.run(&mut log);
+            scheduler.reschedule(task);
+        }
+        log
+    }
+
+    #[test]
+    fn runs_tasks_in_order() {
+        let mut scheduler = Scheduler::default();
+        scheduler.run_later(2, log("b"));
+        scheduler.run_later(1, log("a"));
+        scheduler.run_later(2, log("c"));
+        // runs on the next tick like a delay of 1
+        scheduler.run_later(0, log("d"));
+
+        assert_eq!(run(&mut scheduler), ["a", "d"]);
+        assert_eq!(run(&mut scheduler), ["b", "c"]);
+        assert_eq!(run(&mut scheduler), Log::new());
+        assert_eq!(scheduler.tick(), 3);
+    }
+
+    #[test]
+    fn repeats_tasks() {
+        let mut scheduler = Scheduler::default();
+        scheduler.run_repeating(2, 3, log("a"));
+        let runs: Vec<_> = (1..=9)
+            .filter(|_| !run(&mut scheduler).is_empty())
+            .collect();
+        assert_eq!(runs, [2, 5, 8]);
+    }
+
+    #[test]
+    fn cancels_tasks() {
+        let mut scheduler = Scheduler::default();
+        let id = scheduler.run_later(1, log("a"));
+        scheduler.run_repeating(1, 1, log("b"));
+        assert!(scheduler.cancel(id));
+        assert!(!scheduler.cancel(id));
+        assert_eq!(run(&mut scheduler), ["b"]);
+    }
+
+    #[test]
+    fn cancels_running_tasks() {
+        let mut scheduler = Scheduler::default();
+        let id = scheduler.run_repeating(1, 1, log("a"));
+        let mut log = Log::new();
+        for mut task in scheduler.advance() {
+            task.run(&mut log);
+            // like a task cancelling itself
+            assert!(scheduler.cancel(id));
+            scheduler.reschedule(task);
+        }
+        assert_eq!(log, ["a"]);
+        assert_eq!(run(&mut scheduler), Log::new());
+        assert!(!scheduler.cancel(id));
+    }
+}
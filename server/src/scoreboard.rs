@@ -0,0 +1,269 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use makar_protocol::{
+    DisplaySlot, ObjectiveAction, ObjectiveKind, ProxyBoundPacket, TeamAction, TeamInfo,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Objective {
+    /// At most 32 characters.
+    pub display_name: String,
+    pub kind: ObjectiveKind,
+    /// Scores by entry, usually player names, of at most 40 characters.
+    scores: BTreeMap<String, i32>,
+}
+
+impl Objective {
+    pub fn score(&self, entry: &str) -> Option<i32> {
+        self.scores.get(entry).copied()
+    }
+
+    pub fn set_score(&mut self, entry: &str, value: i32) {
+        self.scores.insert(entry.to_string(), value);
+    }
+
+    pub fn add_score(&mut self, entry: &str, amount: i32) -> i32 {
+        let score = self.scores.entry(entry.to_string()).or_default();
+        *score += amount;
+        *score
+    }
+
+    pub fn remove_score(&mut self, entry: &str) -> Option<i32> {
+        self.scores.remove(entry)
+    }
+
+    pub fn scores(&self) -> impl Iterator<Item = (&str, i32)> {
+        self.scores
+            .iter()
+            .map(|(entry, value)| (entry.as_str(), *value))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Team {
+    pub info: TeamInfo,
+    members: BTreeSet<String>,
+}
+
+impl Team {
+    pub fn members(&self) -> impl Iterator<Item = &str> {
+        self.members.iter().map(String::as_str)
+    }
+}
+
+/// Objectives with their scores, the slots showing them, and teams.
+///
+/// Changes are made to the state directly, players being sent what changed
+/// since their last update by [`Scoreboard::diff`]. Names of objectives and
+/// teams are at most 16 characters, and teams can't be named like the ones
+/// sorting the tab list.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scoreboard {
+    objectives: BTreeMap<String, Objective>,
+    display: BTreeMap<DisplaySlot, String>,
+    teams: BTreeMap<String, Team>,
+}
+
+impl Scoreboard {
+    /// Adds an objective, replacing the one with the same name.
+    pub fn add_objective(
+        &mut self,
+        name: &str,
+        display_name: &str,
+        kind: ObjectiveKind,
+    ) -> &mut Objective {
+        let objective = Objective {
+            display_name: display_name.to_string(),
+            kind,
+            scores: BTreeMap::new(),
+        };
+        self.objectives.insert(name.to_string(), objective);
+        self.objectives
+            .get_mut(name)
+            .expect("objective was just added")
+    }
+
+    pub fn objective(&self, name: &str) -> Option<&Objective> {
+        self.objectives.get(name)
+    }
+
+    pub fn objective_mut(&mut self, name: &str) -> Option<&mut Objective> {
+        self.objectives.get_mut(name)
+    }
+
+    /// Removes an objective, clearing the slots that showed it.
+    pub fn remove_objective(&mut self, name: &str) -> Option<Objective> {
+        self.display.retain(|_, objective| objective != name);
+        self.objectives.remove(name)
+    }
+
+    /// Shows an objective in a slot, or nothing when unset.
+    pub fn display(&mut self, slot: DisplaySlot, objective: Option<&str>) {
+        match objective {
+            Some(objective) => self.display.insert(slot, objective.to_string()),
+            None => self.display.remove(&slot),
+        };
+    }
+
+    /// Adds a team without members, replacing the one with the same name.
+    pub fn add_team(&mut self, name: &str, info: TeamInfo) -> &mut Team {
+        let team = Team {
+            info,
+            members: BTreeSet::new(),
+        };
+        self.teams.insert(name.to_string(), team);
+        self.teams.get_mut(name).expect("team was just added")
+    }
+
+    pub fn team(&self, name: &str) -> Option<&Team> {
+        self.teams.get(name)
+    }
+
+    pub fn team_mut(&mut self, name: &str) -> Option<&mut Team> {
+        self.teams.get_mut(name)
+    }
+
+    pub fn remove_team(&mut self, name: &str) -> Option<Team> {
+        self.teams.remove(name)
+    }
+
+    /// The team of a member, usually a player name.
+    pub fn member_team(&self, member: &str) -> Option<&str> {
+        self.teams
+            .iter()
+            .find(|(_, team)| team.members.contains(member))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Moves a member to a team, or out of any when unset, returning false
+    /// when the team doesn't exist.
+    pub fn set_team(&mut self, member: &str, team: Option<&str>) -> bool {
+        if team.is_some_and(|team| !self.teams.contains_key(team)) {
+            return false;
+        }
+        for team in self.teams.values_mut() {
+            team.members.remove(member);
+        }
+        if let Some(team) = team.and_then(|team| self.teams.get_mut(team)) {
+            team.members.insert(member.to_string());
+        }
+        true
+    }
+
+    /// Packets taking a player from the scoreboard they were sent, `self`,
+    /// to `target`.
+    pub fn diff(&self, target: &Scoreboard, player: u128) -> Vec<ProxyBoundPacket> {
+        let mut packets = Vec::new();
+
+        // members leave their team before joining another, as clients
+        // refuse to remove members from a team they already left
+        for (name, old) in &self.teams {
+            let action = match target.teams.get(name) {
+                None => TeamAction::Remove,
+                Some(team) => {
+                    let removed: Vec<_> = old.members.difference(&team.members).cloned().collect();
+                    if removed.is_empty() {
+                        continue;
+                    }
+                    TeamAction::RemoveMembers(removed)
+                }
+            };
+            packets.push(ProxyBoundPacket::Teams {
+                player,
+                name: name.clone(),
+                action,
+            });
+        }
+        for (name, team) in &target.teams {
+            let mut team_packet = |action| {
+                packets.push(ProxyBoundPacket::Teams {
+                    player,
+                    name: name.clone(),
+                    action,
+                })
+            };
+            let Some(old) = self.teams.get(name) else {
+                team_packet(TeamAction::Create {
+                    info: team.info.clone(),
+                    members: team.members.iter().cloned().collect(),
+                });
+                continue;
+            };
+            if old.info != team.info {
+                team_packet(TeamAction::Update(team.info.clone()));
+            }
+            let added: Vec<_> = team.members.difference(&old.members).cloned().collect();
+            if !added.is_empty() {
+                team_packet(TeamAction::AddMembers(added));
+            }
+        }
+
+        for name in self.objectives.keys() {
+            if !target.objectives.contains_key(name) {
+                packets.push(ProxyBoundPacket::ScoreboardObjective {
+                    player,
+                    name: name.clone(),
+                    action: ObjectiveAction::Remove,
+                });
+            }
+        }
+        for (name, objective) in &target.objectives {
+            let old = self.objectives.get(name);
+            let (display_name, kind) = (objective.display_name.clone(), objective.kind);
+            let action = match old {
+                None => Some(ObjectiveAction::Create { display_name, kind }),
+                Some(old) if (&old.display_name, old.kind) != (&display_name, kind) => {
+                    Some(ObjectiveAction::Update { display_name, kind })
+                }
+                Some(_) => None,
+            };
+            if let Some(action) = action {
+                packets.push(ProxyBoundPacket::ScoreboardObjective {
+                    player,
+                    name: name.clone(),
+                    action,
+                });
+            }
+
+            let old_scores = old.map(|old| &old.scores);
+            let mut score_packet = |entry: &String, value| {
+                packets.push(ProxyBoundPacket::UpdateScore {
+                    player,
+                    entry: entry.clone(),
+                    objective: name.clone(),
+                    value,
+                })
+            };
+            for entry in old_scores.into_iter().flat_map(BTreeMap::keys) {
+                if !objective.scores.contains_key(entry) {
+                    score_packet(entry, None);
+                }
+            }
+            for (entry, value) in &objective.scores {
+                if old_scores.and_then(|scores| scores.get(entry)) != Some(value) {
+                    score_packet(entry, Some(*value));
+                }
+            }
+        }
+
+        for slot in self.display.keys() {
+            if !target.display.contains_key(slot) {
+                packets.push(ProxyBoundPacket::DisplayScoreboard {
+                    player,
+                    slot: *slot,
+                    objective: String::new(),
+                });
+            }
+        }
+        for (slot, objective) in &target.display {
+            if self.display.get(slot) != Some(objective) {
+                packets.push(ProxyBoundPacket::DisplayScoreboard {
+                    player,
+                    slot: *slot,
+                    objective: objective.clone(),
+                });
+            }
+        }
+        packets
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+
+use makar_protocol::{
+    Chat, Gamemode, NameTagVisibility, PlayerListAction, PlayerListEntry, ProxyBoundPacket,
+    TeamAction, TeamInfo,
+};
+
+/// Fake entries get uuids of their own version so that they can't collide
+/// with players'.
+const FAKE_UUID: u128 = 0xF << 76;
+
+#[derive(Clone)]
+enum Update {
+    List(PlayerListAction),
+    Team(String, TeamAction),
+    HeaderFooter,
+}
+
+struct Entry {
+    name: String,
+    gamemode: Gamemode,
+    ping: i32,
+    display_name: Option<Chat>,
+    team: Option<String>,
+}
+
+impl Entry {
+    fn to_protocol(&self, uuid: u128) -> PlayerListEntry {
+        PlayerListEntry {
+            uuid,
+            name: self.name.clone(),
+            gamemode: self.gamemode,
+            ping: self.ping,
+            display_name: self.display_name.clone(),
+        }
+    }
+}
+
+fn sort_team(name: &str, members: Vec<String>) -> TeamAction {
+    TeamAction::Create {
+        info: TeamInfo {
+            display_name: name.to_string(),
+            prefix: String::new(),
+            suffix: String::new(),
+            friendly_fire: true,
+            see_invisible_teammates: false,
+            name_tag_visibility: NameTagVisibility::Always,
+            color: None,
+        },
+        members,
+    }
+}
+
+/// The player list everyone sees: its header and footer, and entries for
+/// players and fake ones filling the layout.
+///
+/// Clients sort entries by the name of their team, then by their own name.
+#[derive(Default)]
+pub struct TabList {
+    header: Option<Chat>,
+    footer: Option<Chat>,
+    entries: BTreeMap<u128, Entry>,
+    /// Members of the teams sorting entries, by team name.
+    teams: BTreeMap<String, Vec<String>>,
+    next_fake: u128,
+    /// Changes not sent to players yet.
+    updates: Vec<Update>,
+}
+
+impl TabList {
+    pub fn set_header(&mut self, header: Option<Chat>) {
+        self.header = header;
+        self.header_footer_changed();
+    }
+
+    pub fn set_footer(&mut self, footer: Option<Chat>) {
+        self.footer = footer;
+        self.header_footer_changed();
+    }
+
+    fn header_footer_changed(&mut self) {
+        // both are sent at once
+        if !matches!(self.updates.last(), Some(Update::HeaderFooter)) {
+            self.updates.push(Update::HeaderFooter);
+        }
+    }
+
+    /// Lists a player under the name they logged in with.
+    pub fn add(&mut self, uuid: u128, name: &str, gamemode: Gamemode) {
+        let entry = Entry {
+            name: name.to_string(),
+            gamemode,
+            ping: 0,
+            display_name: None,
+            team: None,
+        };
+        self.updates.push(Update::List(PlayerListAction::Add(vec![
+            entry.to_protocol(uuid)
+        ])));
+        self.entries.insert(uuid, entry);
+    }
+
+    /// Lists an entry that isn't a player, returning its uuid. Names are at
+    /// most 16 characters and have to be unique, players being listed under
+    /// their display name when it's set.
+    pub fn add_fake(&mut self, name: &str, display_name: Option<Chat>, ping: i32) -> u128 {
+        let uuid = FAKE_UUID | self.next_fake;
+        self.next_fake += 1;
+        self.add(uuid, name, Gamemode::Survival);
+        self.set_display_name(uuid, display_name);
+        self.set_ping(uuid, ping);
+        uuid
+    }
+
+    pub fn remove(&mut self, uuid: u128) -> bool {
+        if !self.entries.contains_key(&uuid) {
+            return false;
+        }
+        self.set_team(uuid, None);
+        self.entries.remove(&uuid);
+        self.updates
+            .push(Update::List(PlayerListAction::Remove(vec![uuid])));
+        true
+    }
+
+    pub fn set_display_name(&mut self, uuid: u128, display_name: Option<Chat>) {
+        let Some(entry) = self.entries.get_mut(&uuid) else {
+            return;
+        };
+        if entry.display_name != display_name {
+            entry.display_name = display_name.clone();
+            let action = PlayerListAction::UpdateDisplayName(vec![(uuid, display_name)]);
+            self.updates.push(Update::List(action));
+        }
+    }
+
+    /// Sets the latency shown by an entry's bars, in milliseconds, negative
+    /// values showing no connection.
+    pub fn set_ping(&mut self, uuid: u128, ping: i32) {
+        let Some(entry) = self.entries.get_mut(&uuid) else {
+            return;
+        };
+        if entry.ping != ping {
+            entry.ping = ping;
+            let action = PlayerListAction::UpdateLatency(vec![(uuid, ping)]);
+            self.updates.push(Update::List(action));
+        }
+    }
+
+    pub fn set_gamemode(&mut self, uuid: u128, gamemode: Gamemode) {
+        let Some(entry) = self.entries.get_mut(&uuid) else {
+            return;
+        };
+        if entry.gamemode != gamemode {
+            entry.gamemode = gamemode;
+            let action = PlayerListAction::UpdateGamemode(vec![(uuid, gamemode)]);
+            self.updates.push(Update::List(action));
+        }
+    }
+
+    /// Moves an entry to the team sorting it, whose name is at most 16
+    /// characters. Entries without a team come first.
+    pub fn set_team(&mut self, uuid: u128, team: Option<&str>) {
+        let Some(entry) = self.entries.get_mut(&uuid) else {
+            return;
+        };
+        if entry.team.as_deref() == team {
+            return;
+        }
+        let name = entry.name.clone();
+
+        if let Some(old) = std::mem::replace(&mut entry.team, team.map(str::to_string)) {
+            let members = self.teams.get_mut(&old).expect("team should exist");
+            members.retain(|member| *member != name);
+            let action = if members.is_empty() {
+                self.teams.remove(&old);
+                TeamAction::Remove
+            } else {
+                TeamAction::RemoveMembers(vec![name.clone()])
+            };
+            self.updates.push(Update::Team(old, action));
+        }
+        if let Some(team) = team {
+            let action = match self.teams.get_mut(team) {
+                Some(members) => {
+                    members.push(name.clone());
+                    TeamAction::AddMembers(vec![name])
+                }
+                None => {
+                    self.teams.insert(team.to_string(), vec![name.clone()]);
+                    sort_team(team, vec![name])
+                }
+            };
+            self.updates.push(Update::Team(team.to_string(), action));
+        }
+    }
+
+    fn packet(&self, update: Update, player: u128) -> ProxyBoundPacket {
+        match update {
+            Update::List(action) => ProxyBoundPacket::PlayerListItem { player, action },
+            Update::Team(name, action) => ProxyBoundPacket::Teams {
+                player,
+                name,
+                action,
+            },
+            Update::HeaderFooter => ProxyBoundPacket::PlayerListHeaderFooter {
+                player,
+                header: self.header.clone().unwrap_or_default(),
+                footer: self.footer.clone().unwrap_or_default(),
+            },
+        }
+    }
+
+    /// Packets showing the whole list to a player who just joined.
+    pub fn packets(&self, player: u128) -> Vec<ProxyBoundPacket> {
+        let mut updates = vec![Update::List(PlayerListAction::Add(
+            self.entries
+                .iter()
+                .map(|(uuid, entry)| entry.to_protocol(*uuid))
+                .collect(),
+        ))];
+        updates.extend(
+            self.teams.iter().map(|(name, members)| {
+                Update::Team(name.clone(), sort_team(name, members.clone()))
+            }),
+        );
+        if self.header.is_some() || self.footer.is_some() {
+            updates.push(Update::HeaderFooter);
+        }
+        updates
+            .into_iter()
+            .map(|update| self.packet(update, player))
+            .collect()
+    }
+
+    /// Packets sending the changes made since last time to players.
+    pub fn take_updates(&mut self, players: &[u128]) -> Vec<ProxyBoundPacket> {
+        let updates = std::mem::take(&mut self.updates);
+        updates
+            .into_iter()
+            .flat_map(|update| players.iter().map(move |player| (update.clone(), *player)))
+            .map(|(update, player)| self.packet(update, player))
+            .collect()
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::VecDeque;
+
+use makar_protocol::{Chat, TitleAction};
+
+/// Times clients use until they're told otherwise.
+const DEFAULT_TIMES: (u32, u32, u32) = (10, 70, 20);
+
+/// Text shown in the middle of the screen, times being in ticks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Title {
+    pub title: Chat,
+    pub subtitle: Option<Chat>,
+    pub fade_in: u32,
+    pub stay: u32,
+    pub fade_out: u32,
+}
+
+impl Default for Title {
+    fn default() -> Self {
+        let (fade_in, stay, fade_out) = DEFAULT_TIMES;
+        Self {
+            title: Chat::default(),
+            subtitle: None,
+            fade_in,
+            stay,
+            fade_out,
+        }
+    }
+}
+
+impl Title {
+    /// Ticks the title is on screen for.
+    pub fn duration(&self) -> u64 {
+        (self.fade_in + self.stay + self.fade_out) as u64
+    }
+}
+
+/// The titles of a player, shown one after the other.
+pub(crate) struct Titles {
+    queue: VecDeque<Title>,
+    /// The tick the title on screen is gone on.
+    until: u64,
+    /// What the client keeps from one title to the next.
+    subtitle: Chat,
+    times: (u32, u32, u32),
+}
+
+impl Default for Titles {
+    fn default() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            until: 0,
+            subtitle: Chat::default(),
+            times: DEFAULT_TIMES,
+        }
+    }
+}
+
+impl Titles {
+    /// Shows a title right away, dropping the queued ones.
+    pub fn show(&mut self, tick: u64, title: Title) -> Vec<TitleAction> {
+        self.queue.clear();
+        self.display(tick, title)
+    }
+
+    pub fn queue(&mut self, title: Title) {
+        self.queue.push_back(title);
+    }
+
+    pub fn clear(&mut self) -> Vec<TitleAction> {
+        self.queue.clear();
+        self.until = 0;
+        vec![TitleAction::Hide]
+    }
+
+    /// Shows the next queued title once the one on screen is gone.
+    pub fn next(&mut self, tick: u64) -> Vec<TitleAction> {
+        if tick < self.until {
+            return Vec::new();
+        }
+        match self.queue.pop_front() {
+            Some(title) => self.display(tick, title),
+            None => Vec::new(),
+        }
+    }
+
+    /// Shows a title, only telling the client what changed since the last
+    /// one as it keeps the times and subtitle.
+    fn display(&mut self, tick: u64, title: Title) -> Vec<TitleAction> {
+        let mut actions = Vec::new();
+        self.until = tick + title.duration();
+
+        // times apply to the titles shown after them
+        let times = (title.fade_in, title.stay, title.fade_out);
+        if times != self.times {
+            self.times = times;
+            actions.push(TitleAction::SetTimes {
+                fade_in: title.fade_in,
+                stay: title.stay,
+                fade_out: title.fade_out,
+            });
+        }
+        let subtitle = title.subtitle.unwrap_or_default();
+        if subtitle != self.subtitle {
+            self.subtitle = subtitle.clone();
+            actions.push(TitleAction::SetSubtitle(subtitle));
+        }
+        actions.push(TitleAction::SetTitle(title.title));
+        actions
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::world::World;
+
+/// Chunks around `center` up to `radius` away, walking a square spiral
+/// outwards so that the closest ones come first.
+pub fn spiral((x, z): (i32, i32), radius: i32) -> Vec<(i32, i32)> {
+    let mut chunks = Vec::with_capacity(((radius * 2 + 1) * (radius * 2 + 1)) as usize);
+    chunks.push((x, z));
+    for ring in 1..=radius {
+        // each side of the ring stops before the corner the next one starts on
+        for i in -ring..ring {
+            chunks.push((x + ring, z + i));
+        }
+        for i in -ring..ring {
+            chunks.push((x - i, z + ring));
+        }
+        for i in -ring..ring {
+            chunks.push((x - ring, z - i));
+        }
+        for i in -ring..ring {
+            chunks.push((x + i, z - ring));
+        }
+    }
+    chunks
+}
+
+/// The chunks a player has loaded, following them as they move.
+pub struct View {
+    center: (i32, i32),
+    distance: i32,
+    loaded: HashSet<(i32, i32)>,
+    /// Chunks in range the player doesn't have yet, closest first.
+    queue: VecDeque<(i32, i32)>,
+}
+
+impl View {
+    pub fn new(center: (i32, i32), distance: i32) -> Self {
+        Self {
+            center,
+            distance,
+            loaded: HashSet::new(),
+            queue: spiral(center, distance).into(),
+        }
+    }
+
+    pub fn center(&self) -> (i32, i32) {
+        self.center
+    }
+
+    pub fn distance(&self) -> i32 {
+        self.distance
+    }
+
+    /// Whether a chunk is close enough to be seen.
+    pub fn contains(&self, (x, z): (i32, i32)) -> bool {
+        (x - self.center.0).abs() <= self.distance && (z - self.center.1).abs() <= self.distance
+    }
+
+    /// Whether a chunk was sent to the player.
+    pub fn is_loaded(&self, chunk: (i32, i32)) -> bool {
+        self.loaded.contains(&chunk)
+    }
+
+    /// Records chunks sent to the player outside of [`View::next_chunks`].
+    pub fn mark_loaded(&mut self, chunks: &[(i32, i32)]) {
+        self.loaded.extend(chunks);
+        self.queue.retain(|chunk| !chunks.contains(chunk));
+    }
+
+    /// Moves the view, returning the chunks the player should unload.
+    pub fn update(&mut self, center: (i32, i32), distance: i32) -> Vec<(i32, i32)> {
+        if (center, distance) == (self.center, self.distance) {
+            return Vec::new();
+        }
+        self.center = center;
+        self.distance = distance;
+
+        let mut unloaded: Vec<_> = self
+            .loaded
+            .iter()
+            .filter(|chunk| !self.contains(**chunk))
+            .copied()
+            .collect();
+        unloaded.sort_unstable();
+        for chunk in &unloaded {
+            self.loaded.remove(chunk);
+        }
+        self.queue = spiral(center, distance)
+            .into_iter()
+            .filter(|chunk| !self.loaded.contains(chunk))
+            .collect();
+        unloaded
+    }
+
+    /// Takes up to `budget` queued chunks that are loaded in the world,
+    /// closest first, marking them as sent. Chunks still being generated
+    /// stay queued, a few chunks past the budget being requested so that
+    /// generation keeps up.
+    pub fn next_chunks(&mut self, world: &mut World, budget: usize) -> Vec<(i32, i32)> {
+        let mut chunks = Vec::new();
+        let mut i = 0;
+        while i < self.queue.len() && i < budget * 4 && chunks.len() < budget {
+            let (x, z) = self.queue[i];
+            if world.request_chunk(x, z) {
+                self.queue.remove(i);
+                self.loaded.insert((x, z));
+                chunks.push((x, z));
+            } else {
+                i += 1;
+            }
+        }
+        chunks
+    }
+}
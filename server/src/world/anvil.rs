@@ -0,0 +1,501 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{
+    read::{GzDecoder, ZlibDecoder},
+    write::ZlibEncoder,
+    Compression,
+};
+
+use makar_protocol::{ChunkColumn, ChunkSection};
+
+use super::{
+    nbt::{self, NbtError, Tag},
+    Chunk,
+};
+
+const SECTOR: usize = 4096;
+const GZIP: u8 = 1;
+const ZLIB: u8 = 2;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AnvilError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Nbt(#[from] NbtError),
+    #[error("unknown compression type {0}")]
+    UnknownCompression(u8),
+    #[error("chunk data outside of its region file")]
+    OutOfBounds,
+    #[error("missing or invalid {0} tag")]
+    InvalidTag(&'static str),
+    #[error("chunk too large to be saved")]
+    TooLarge,
+}
+
+/// A region file, holding 32x32 chunks in 4KiB sectors after a table of
+/// their locations and a table of their timestamps.
+pub struct Region {
+    file: File,
+    /// Offset of each chunk in sectors, shifted left by 8 and ored with its
+    /// size in sectors.
+    locations: Vec<u32>,
+    timestamps: Vec<u32>,
+    used: Vec<bool>,
+}
+
+fn index(x: i32, z: i32) -> usize {
+    ((x & 31) + (z & 31) * 32) as usize
+}
+
+impl Region {
+    /// Opens a region file, creating it when it doesn't exist.
+    pub fn open(path: &Path) -> Result<Self, AnvilError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let mut header = vec![0u8; SECTOR * 2];
+        let len = file.metadata()?.len() as usize;
+        if len < header.len() {
+            file.set_len(header.len() as u64)?;
+        } else {
+            file.read_exact(&mut header)?;
+        }
+
+        let mut table = header
+            .chunks_exact(4)
+            .map(|entry| u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]));
+        let locations: Vec<u32> = table.by_ref().take(1024).collect();
+        let timestamps = table.collect();
+
+        let mut used = vec![false; len.max(header.len()).div_ceil(SECTOR)];
+        used[0] = true;
+        used[1] = true;
+        for location in &locations {
+            let (offset, count) = ((location >> 8) as usize, (location & 0xFF) as usize);
+            for sector in offset..(offset + count).min(used.len()) {
+                used[sector] = true;
+            }
+        }
+
+        Ok(Self {
+            file,
+            locations,
+            timestamps,
+            used,
+        })
+    }
+
+    /// Reads the NBT of a chunk, `None` if it was never saved.
+    pub fn read_chunk(&mut self, x: i32, z: i32) -> Result<Option<Tag>, AnvilError> {
+        let location = self.locations[index(x, z)];
+        let (offset, count) = ((location >> 8) as usize, (location & 0xFF) as usize);
+        if offset == 0 {
+            return Ok(None);
+        }
+        if offset + count > self.used.len() {
+            return Err(AnvilError::OutOfBounds);
+        }
+
+        let mut header = [0u8; 5];
+        self.file.seek(SeekFrom::Start((offset * SECTOR) as u64))?;
+        self.file.read_exact(&mut header)?;
+        // the length counts the compression type byte
+        let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        if len == 0 || len + 4 > count * SECTOR {
+            return Err(AnvilError::OutOfBounds);
+        }
+
+        let mut compressed = vec![0u8; len - 1];
+        self.file.read_exact(&mut compressed)?;
+        let mut data = Vec::new();
+        match header[4] {
+            GZIP => GzDecoder::new(&compressed[..]).read_to_end(&mut data)?,
+            ZLIB => ZlibDecoder::new(&compressed[..]).read_to_end(&mut data)?,
+            compression => return Err(AnvilError::UnknownCompression(compression)),
+        };
+        Ok(Some(nbt::read(&data)?))
+    }
+
+    /// Writes the NBT of a chunk, reusing its sectors when it still fits in
+    /// them.
+    pub fn write_chunk(&mut self, x: i32, z: i32, tag: &Tag) -> Result<(), AnvilError> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&nbt::write(tag))?;
+        let compressed = encoder.finish()?;
+
+        let count = (compressed.len() + 5).div_ceil(SECTOR);
+        if count > 0xFF {
+            return Err(AnvilError::TooLarge);
+        }
+
+        let i = index(x, z);
+        let (old_offset, old_count) = (
+            (self.locations[i] >> 8) as usize,
+            (self.locations[i] & 0xFF) as usize,
+        );
+        if old_offset != 0 && (old_offset < 2 || old_offset + old_count > self.used.len()) {
+            return Err(AnvilError::OutOfBounds);
+        }
+        let offset = if old_offset != 0 && count <= old_count {
+            for sector in old_offset + count..old_offset + old_count {
+                self.used[sector] = false;
+            }
+            old_offset
+        } else {
+            for sector in old_offset..old_offset + old_count {
+                self.used[sector] = false;
+            }
+            self.allocate(count)
+        };
+
+        let mut data = Vec::with_capacity(count * SECTOR);
+        data.extend_from_slice(&(compressed.len() as u32 + 1).to_be_bytes());
+        data.push(ZLIB);
+        data.extend_from_slice(&compressed);
+        data.resize(count * SECTOR, 0);
+        self.file.seek(SeekFrom::Start((offset * SECTOR) as u64))?;
+        self.file.write_all(&data)?;
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs() as u32);
+        self.locations[i] = ((offset as u32) << 8) | count as u32;
+        self.timestamps[i] = timestamp;
+        self.file.seek(SeekFrom::Start((i * 4) as u64))?;
+        self.file.write_all(&self.locations[i].to_be_bytes())?;
+        self.file.seek(SeekFrom::Start((SECTOR + i * 4) as u64))?;
+        self.file.write_all(&timestamp.to_be_bytes())?;
+        Ok(())
+    }
+
+    /// Finds `count` free consecutive sectors, growing the file if needed.
+    fn allocate(&mut self, count: usize) -> usize {
+        let mut start = 0;
+        for sector in 0..self.used.len() {
+            if self.used[sector] {
+                start = sector + 1;
+            } else if sector + 1 - start == count {
+                break;
+            }
+        }
+        if start + count > self.used.len() {
+            self.used.resize(start + count, false);
+        }
+        self.used[start..start + count].fill(true);
+        start
+    }
+}
+
+fn section_from_nbt(tag: &Tag, has_sky: bool) -> Result<(usize, ChunkSection), AnvilError> {
+    let array = |name: &'static str, len: usize| {
+        tag.get(name)
+            .and_then(Tag::as_bytes)
+            .filter(|array| array.len() == len)
+            .ok_or(AnvilError::InvalidTag(name))
+    };
+
+    let y = tag
+        .get("Y")
+        .and_then(Tag::as_i64)
+        .filter(|y| (0..16).contains(y))
+        .ok_or(AnvilError::InvalidTag("Y"))?;
+    let blocks = array("Blocks", 4096)?;
+    let data = array("Data", 2048)?;
+    let add = array("Add", 2048).ok();
+
+    let nibble = |array: &[u8], i: usize| ((array[i >> 1] >> ((i & 1) * 4)) & 0xF) as u16;
+    let blocks = (0..4096)
+        .map(|i| {
+            let id = blocks[i] as u16 | add.map_or(0, |add| nibble(add, i) << 8);
+            (id << 4) | nibble(data, i)
+        })
+        .collect();
+
+    Ok((
+        y as usize,
+        ChunkSection {
+            blocks,
+            block_light: array("BlockLight", 2048)?.to_vec(),
+            sky_light: match has_sky {
+                true => Some(array("SkyLight", 2048)?.to_vec()),
+                false => None,
+            },
+        },
+    ))
+}
+
+fn section_to_nbt(y: usize, section: &ChunkSection) -> Tag {
+    let mut blocks = vec![0u8; 4096];
+    let mut add = vec![0u8; 2048];
+    let mut data = vec![0u8; 2048];
+    for (i, state) in section.blocks.iter().enumerate() {
+        let (id, meta) = (state >> 4, (state & 0xF) as u8);
+        let shift = (i & 1) * 4;
+        blocks[i] = id as u8;
+        add[i >> 1] |= ((id >> 8) as u8 & 0xF) << shift;
+        data[i >> 1] |= meta << shift;
+    }
+
+    let mut tags = BTreeMap::from([
+        ("Y".to_string(), Tag::Byte(y as i8)),
+        ("Blocks".to_string(), Tag::ByteArray(blocks)),
+        ("Data".to_string(), Tag::ByteArray(data)),
+        (
+            "BlockLight".to_string(),
+            Tag::ByteArray(section.block_light.clone()),
+        ),
+        (
+            "SkyLight".to_string(),
+            Tag::ByteArray(section.sky_light.clone().unwrap_or_else(|| vec![0; 2048])),
+        ),
+    ]);
+    if add.iter().any(|b| *b != 0) {
+        tags.insert("Add".to_string(), Tag::ByteArray(add));
+    }
+    Tag::Compound(tags)
+}
+
+/// Reads a chunk from the `Level` compound of its NBT.
+pub fn chunk_from_nbt(level: &Tag, has_sky: bool) -> Result<Chunk, AnvilError> {
+    let coordinate = |name: &'static str| {
+        level
+            .get(name)
+            .and_then(Tag::as_i64)
+            .map(|v| v as i32)
+            .ok_or(AnvilError::InvalidTag(name))
+    };
+
+    let mut column = ChunkColumn {
+        x: coordinate("xPos")?,
+        z: coordinate("zPos")?,
+        sections: vec![None; 16],
+        biomes: level
+            .get("Biomes")
+            .and_then(Tag::as_bytes)
+            .filter(|biomes| biomes.len() == 256)
+            .map_or_else(|| vec![1; 256], <[u8]>::to_vec),
+    };
+    for section in level.get("Sections").and_then(Tag::as_list).unwrap_or(&[]) {
+        let (y, section) = section_from_nbt(section, has_sky)?;
+        column.sections[y] = Some(section);
+    }
+    Ok(Chunk::from_protocol(column, has_sky))
+}
+
+/// Writes a chunk into a `Level` compound, keeping the tags of `level` this
+/// server doesn't know about such as entities and tile entities.
+pub fn chunk_to_nbt(chunk: &Chunk, level: Option<Tag>) -> Tag {
+    let mut tags = match level {
+        Some(Tag::Compound(tags)) => tags,
+        _ => BTreeMap::from([
+            ("Entities".to_string(), Tag::List(Vec::new())),
+            ("TileEntities".to_string(), Tag::List(Vec::new())),
+            ("LastUpdate".to_string(), Tag::Long(0)),
+            ("InhabitedTime".to_string(), Tag::Long(0)),
+        ]),
+    };
+
+    let column = chunk.to_protocol();
+    let sections = column
+        .sections
+        .iter()
+        .enumerate()
+        .filter_map(|(y, section)| section.as_ref().map(|section| section_to_nbt(y, section)))
+        .collect();
+    let height_map = (0..256)
+        .map(|i| chunk.height(i & 0xF, i >> 4) as i32)
+        .collect();
+
+    tags.extend([
+        ("xPos".to_string(), Tag::Int(chunk.x)),
+        ("zPos".to_string(), Tag::Int(chunk.z)),
+        ("V".to_string(), Tag::Byte(1)),
+        ("TerrainPopulated".to_string(), Tag::Byte(1)),
+        ("LightPopulated".to_string(), Tag::Byte(1)),
+        ("Sections".to_string(), Tag::List(sections)),
+        ("Biomes".to_string(), Tag::ByteArray(column.biomes)),
+        ("HeightMap".to_string(), Tag::IntArray(height_map)),
+    ]);
+    Tag::Compound(tags)
+}
+
+/// The region files of a world, opened as their chunks get loaded.
+pub struct Anvil {
+    directory: PathBuf,
+    regions: HashMap<(i32, i32), Region>,
+}
+
+impl Anvil {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            regions: HashMap::new(),
+        }
+    }
+
+    fn region(&mut self, x: i32, z: i32, create: bool) -> Result<Option<&mut Region>, AnvilError> {
+        let key = (x >> 5, z >> 5);
+        if !self.regions.contains_key(&key) {
+            let path = self.directory.join(format!("r.{}.{}.mca", key.0, key.1));
+            if !create && !path.exists() {
+                return Ok(None);
+            }
+            fs::create_dir_all(&self.directory)?;
+            self.regions.insert(key, Region::open(&path)?);
+        }
+        Ok(self.regions.get_mut(&key))
+    }
+
+    /// Reads a chunk, `None` if it was never saved.
+    pub fn load_chunk(
+        &mut self,
+        x: i32,
+        z: i32,
+        has_sky: bool,
+    ) -> Result<Option<Chunk>, AnvilError> {
+        let tag = match self.region(x, z, false)? {
+            Some(region) => region.read_chunk(x, z)?,
+            None => return Ok(None),
+        };
+        match tag {
+            Some(tag) => {
+                let level = tag.get("Level").ok_or(AnvilError::InvalidTag("Level"))?;
+                chunk_from_nbt(level, has_sky).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn save_chunk(&mut self, chunk: &Chunk) -> Result<(), AnvilError> {
+        let region = self
+            .region(chunk.x, chunk.z, true)?
+            .expect("region should be created");
+        // a chunk that can't be read back is overwritten from scratch
+        let level = match region.read_chunk(chunk.x, chunk.z) {
+            Ok(Some(Tag::Compound(mut tags))) => tags.remove("Level"),
+            _ => None,
+        };
+        let tag = Tag::Compound(BTreeMap::from([(
+            "Level".to_string(),
+            chunk_to_nbt(chunk, level),
+        )]));
+        region.write_chunk(chunk.x, chunk.z, &tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+    use crate::world::Block;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("makar-{}-{name}", process::id()))
+    }
+
+    fn chunk(has_sky: bool) -> Chunk {
+        let mut chunk = Chunk::new(-3, 7, has_sky);
+        chunk.set_block(0, 0, 0, Block::new(7, 0));
+        chunk.set_block(1, 64, 2, Block::new(35, 14));
+        // needs the Add array
+        chunk.set_block(15, 255, 15, Block::new(300, 5));
+        chunk.set_block_light(1, 65, 2, 12);
+        chunk.set_biome(4, 9, 6);
+        if has_sky {
+            chunk.set_sky_light(1, 70, 2, 9);
+        }
+        chunk
+    }
+
+    fn assert_same(a: &Chunk, b: &Chunk) {
+        let (a, b) = (a.to_protocol(), b.to_protocol());
+        assert_eq!((a.x, a.z), (b.x, b.z));
+        assert_eq!(a.biomes, b.biomes);
+        for (a, b) in a.sections.iter().zip(&b.sections) {
+            match (a, b) {
+                (Some(a), Some(b)) => {
+                    assert_eq!(a.blocks, b.blocks);
+                    assert_eq!(a.block_light, b.block_light);
+                    assert_eq!(a.sky_light, b.sky_light);
+                }
+                (None, None) => {}
+                _ => panic!("sections differ"),
+            }
+        }
+    }
+
+    #[test]
+    fn chunk_nbt_round_trip() {
+        for has_sky in [true, false] {
+            let chunk = chunk(has_sky);
+            let tag = chunk_to_nbt(&chunk, None);
+            let read = chunk_from_nbt(&nbt::read(&nbt::write(&tag)).unwrap(), has_sky).unwrap();
+            assert_same(&chunk, &read);
+            assert_eq!(read.get_block(15, 255, 15), Block::new(300, 5));
+        }
+    }
+
+    #[test]
+    fn chunk_to_nbt_keeps_unknown_tags() {
+        let level = Tag::Compound(BTreeMap::from([(
+            "Entities".to_string(),
+            Tag::List(vec![Tag::Int(1)]),
+        )]));
+        let tag = chunk_to_nbt(&chunk(true), Some(level));
+        assert_eq!(tag.get("Entities"), Some(&Tag::List(vec![Tag::Int(1)])));
+        assert_eq!(tag.get("xPos"), Some(&Tag::Int(-3)));
+    }
+
+    #[test]
+    fn region_round_trip() {
+        let directory = temp_path("region");
+        let _ = fs::remove_dir_all(&directory);
+
+        let mut anvil = Anvil::new(&directory);
+        assert!(anvil.load_chunk(-3, 7, true).unwrap().is_none());
+        anvil.save_chunk(&chunk(true)).unwrap();
+        // saving again reuses the sectors of the chunk
+        anvil.save_chunk(&chunk(true)).unwrap();
+
+        let mut anvil = Anvil::new(&directory);
+        let read = anvil.load_chunk(-3, 7, true).unwrap().unwrap();
+        assert_same(&chunk(true), &read);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn rejects_locations_outside_the_file() {
+        let path = temp_path("out-of-bounds.mca");
+        let mut header = vec![0u8; SECTOR * 2];
+        // chunk 0, 0 claims sectors 100 and 101 of a 2 sector file
+        header[..4].copy_from_slice(&((100u32 << 8) | 2).to_be_bytes());
+        fs::write(&path, &header).unwrap();
+
+        let mut region = Region::open(&path).unwrap();
+        let tag = Tag::Compound(BTreeMap::new());
+        assert!(matches!(
+            region.read_chunk(0, 0),
+            Err(AnvilError::OutOfBounds)
+        ));
+        assert!(matches!(
+            region.write_chunk(0, 0, &tag),
+            Err(AnvilError::OutOfBounds)
+        ));
+        // other chunks are still writable
+        region.write_chunk(1, 0, &tag).unwrap();
+        assert_eq!(region.read_chunk(1, 0).unwrap(), Some(tag));
+        fs::remove_file(&path).unwrap();
+    }
+}
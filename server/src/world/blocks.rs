@@ -0,0 +1,512 @@
+use makar_protocol::{BlockPosition, ItemMeta, ItemStack};
+
+use super::Block;
+use crate::inventory::{Tool, ToolKind};
+
+/// What a block is made of, deciding which tools dig it faster.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Material {
+    Rock,
+    Iron,
+    Anvil,
+    Wood,
+    Plants,
+    Vine,
+    Leaves,
+    Gourd,
+    Web,
+    Snow,
+    Other,
+}
+
+fn material(id: u16) -> Material {
+    use Material::*;
+    match id {
+        1
+        | 4
+        | 14..=16
+        | 21..=24
+        | 43
+        | 44
+        | 45
+        | 48
+        | 49
+        | 52
+        | 56
+        | 61
+        | 62
+        | 67
+        | 70
+        | 73
+        | 74
+        | 87
+        | 98
+        | 108
+        | 109
+        | 112..=114
+        | 116
+        | 121
+        | 128..=130
+        | 139
+        | 153
+        | 155
+        | 156
+        | 158
+        | 159
+        | 168
+        | 172
+        | 173
+        | 179..=182 => Rock,
+        41 | 42 | 57 | 71 | 101 | 117 | 118 | 133 | 147 | 148 | 152 | 154 | 167 => Iron,
+        145 => Anvil,
+        5
+        | 17
+        | 25
+        | 47
+        | 53
+        | 54
+        | 58
+        | 63
+        | 64
+        | 68
+        | 72
+        | 84
+        | 85
+        | 96
+        | 99
+        | 100
+        | 107
+        | 125
+        | 126
+        | 134..=136
+        | 143
+        | 146
+        | 151
+        | 162..=164
+        | 176..=178
+        | 183..=197 => Wood,
+        6 | 37..=40 | 59 | 83 | 104 | 105 | 111 | 115 | 127 | 141 | 142 => Plants,
+        31 | 32 | 106 | 175 => Vine,
+        18 | 161 => Leaves,
+        86 | 91 | 103 => Gourd,
+        30 => Web,
+        78 | 80 => Snow,
+        _ => Other,
+    }
+}
+
+/// How long a block takes to dig, `None` for those that can't be.
+pub fn hardness(id: u16) -> Option<f32> {
+    Some(match id {
+        7..=11 | 90 | 119 | 120 | 137 | 166 => return None,
+        49 => 50.0,
+        130 => 22.5,
+        42 | 52 | 57 | 71 | 101 | 116 | 133 | 145 | 152 | 167 | 173 => 5.0,
+        14..=16 | 21 | 22 | 56 | 73 | 74 | 121 | 122 | 129 | 138 | 153 | 154 => 3.0,
+        41 | 64 | 96 | 193..=197 => 3.0,
+        23 | 61 | 62 | 158 => 3.5,
+        54 | 58 | 146 => 2.5,
+        4
+        | 5
+        | 17
+        | 43
+        | 44
+        | 45
+        | 48
+        | 53
+        | 67
+        | 84
+        | 85
+        | 107
+        | 108
+        | 112..=114
+        | 118
+        | 125
+        | 126
+        | 134..=136
+        | 139
+        | 162..=164
+        | 181..=192 => 2.0,
+        1 | 47 | 98 | 109 | 168 => 1.5,
+        159 | 172 => 1.25,
+        63 | 68 | 86 | 91 | 103 | 144 | 176 | 177 => 1.0,
+        24 | 25 | 35 | 128 | 155 | 156 | 179 | 180 => 0.8,
+        97 => 0.75,
+        27 | 28 | 66 | 157 => 0.7,
+        2 | 13 | 19 | 60 | 82 | 110 => 0.6,
+        3 | 12 | 29 | 33 | 34 | 69 | 70 | 72 | 77 | 79 | 88 | 92 | 117 | 143 | 147 | 148 | 170
+        | 174 => 0.5,
+        65 | 81 | 87 => 0.4,
+        20 | 89 | 95 | 102 | 123 | 124 | 160 | 169 => 0.3,
+        18 | 26 | 80 | 99 | 100 | 106 | 127 | 151 | 161 | 178 => 0.2,
+        78 | 171 => 0.1,
+        30 => 4.0,
+        _ => 0.0,
+    })
+}
+
+/// The pickaxe level a block needs to drop anything.
+fn pickaxe_level(id: u16) -> u8 {
+    match id {
+        49 => 3,
+        14 | 41 | 56 | 57 | 73 | 74 | 129 | 133 => 2,
+        15 | 21 | 22 | 42 => 1,
+        _ => 0,
+    }
+}
+
+/// Whether a block drops anything dug without the right tool.
+fn needs_tool(id: u16) -> bool {
+    use Material::*;
+    matches!(material(id), Rock | Iron | Anvil | Web | Snow)
+}
+
+fn can_harvest(id: u16, tool: Option<Tool>) -> bool {
+    if !needs_tool(id) {
+        return true;
+    }
+    let Some(tool) = tool else {
+        return false;
+    };
+    match tool.kind {
+        ToolKind::Pickaxe => {
+            matches!(
+                material(id),
+                Material::Rock | Material::Iron | Material::Anvil
+            ) && tool.level >= pickaxe_level(id)
+        }
+        ToolKind::Shovel => material(id) == Material::Snow,
+        ToolKind::Sword => id == 30,
+        ToolKind::Shears => matches!(id, 30 | 55 | 132),
+        ToolKind::Axe => false,
+    }
+}
+
+/// How much faster than a hand a tool digs a block.
+fn tool_speed(id: u16, tool: Option<Tool>) -> f32 {
+    use Material::*;
+    let Some(tool) = tool else {
+        return 1.0;
+    };
+    let effective = match tool.kind {
+        ToolKind::Pickaxe => {
+            matches!(material(id), Rock | Iron | Anvil)
+                || matches!(id, 27 | 28 | 66 | 79 | 157 | 174)
+        }
+        ToolKind::Shovel => matches!(id, 2 | 3 | 12 | 13 | 60 | 78 | 80 | 82 | 88 | 110),
+        ToolKind::Axe => matches!(material(id), Wood | Plants | Vine | Gourd) || id == 65,
+        ToolKind::Sword => {
+            return match material(id) {
+                Web => 15.0,
+                Plants | Vine | Leaves | Gourd => 1.5,
+                _ => 1.0,
+            }
+        }
+        ToolKind::Shears => {
+            return match id {
+                30 | 18 | 161 => 15.0,
+                35 => 5.0,
+                _ => 1.0,
+            }
+        }
+    };
+    match effective {
+        true => tool.speed,
+        false => 1.0,
+    }
+}
+
+/// How much of a block a player digs every tick, the block breaking once
+/// it adds up to 1. Players dig five times slower in the air and under
+/// water.
+pub fn dig_strength(id: u16, held: Option<&ItemStack>, on_ground: bool, in_water: bool) -> f32 {
+    let Some(hardness) = hardness(id) else {
+        return 0.0;
+    };
+    let tool = held.and_then(Tool::of);
+    let mut speed = tool_speed(id, tool);
+    if !on_ground {
+        speed /= 5.0;
+    }
+    if in_water {
+        speed /= 5.0;
+    }
+    match can_harvest(id, tool) {
+        true => speed / hardness / 30.0,
+        false => speed / hardness / 100.0,
+    }
+}
+
+/// Blocks whose item keeps their metadata.
+fn keeps_meta(id: u16) -> bool {
+    matches!(
+        id,
+        1 | 5 | 12 | 19 | 24 | 35 | 38 | 95 | 98 | 139 | 159 | 160 | 168 | 171 | 179
+    )
+}
+
+/// What a block drops when dug with an item. Drops that vary are the
+/// fewest vanilla gives, and those only dropped by chance are left out.
+pub fn drops(block: Block, held: Option<&ItemStack>) -> Vec<ItemStack> {
+    let tool = held.and_then(Tool::of);
+    if !can_harvest(block.id, tool) {
+        return Vec::new();
+    }
+    let shears = tool.is_some_and(|tool| tool.kind == ToolKind::Shears);
+    let meta = block.meta as i16;
+    let (id, count, damage) = match block.id {
+        1 if meta == 0 => (4, 1, 0),
+        2 | 60 | 110 => (3, 1, 0),
+        3 => (3, 1, if meta == 1 { 1 } else { 0 }),
+        16 => (263, 1, 0),
+        21 => (351, 4, 4),
+        56 => (264, 1, 0),
+        73 | 74 => (331, 4, 0),
+        129 => (388, 1, 0),
+        153 => (406, 1, 0),
+        89 => (348, 2, 0),
+        82 => (337, 4, 0),
+        80 => (332, 4, 0),
+        78 => (332, (meta as u8 & 7) + 1, 0),
+        18 | 161 if shears => (block.id as i16, 1, meta & 3),
+        31 if shears => (31, 1, meta),
+        32 | 106 if shears => (block.id as i16, 1, 0),
+        30 if shears => (30, 1, 0),
+        30 => (287, 1, 0),
+        17 | 162 => (block.id as i16, 1, meta & 3),
+        6 => (6, 1, meta & 7),
+        43 => (44, 2, meta & 7),
+        125 => (126, 2, meta & 7),
+        181 => (182, 2, meta & 7),
+        44 | 126 | 182 => (block.id as i16, 1, meta & 7),
+        55 => (331, 1, 0),
+        59 if meta == 7 => (296, 1, 0),
+        59 => (295, 1, 0),
+        141 => (391, 1, 0),
+        142 => (392, 1, 0),
+        115 => (372, if meta == 3 { 2 } else { 1 }, 0),
+        83 => (338, 1, 0),
+        63 | 68 => (323, 1, 0),
+        93 | 94 => (356, 1, 0),
+        149 | 150 => (404, 1, 0),
+        118 => (380, 1, 0),
+        117 => (379, 1, 0),
+        140 => (390, 1, 0),
+        144 => (397, 1, 0),
+        127 => (351, if meta >> 2 >= 2 { 2 } else { 1 }, 3),
+        132 => (287, 1, 0),
+        75 | 76 => (76, 1, 0),
+        123 | 124 => (123, 1, 0),
+        61 | 62 => (61, 1, 0),
+        178 => (151, 1, 0),
+        155 => (155, 1, if meta >= 2 { 2 } else { meta }),
+        145 => (145, 1, meta >> 2),
+        // doors, beds and tall plants drop from their lower half
+        64 | 71 | 193..=197 | 26 | 175 if meta & 8 != 0 => return Vec::new(),
+        64 => (324, 1, 0),
+        71 => (330, 1, 0),
+        193..=197 => (427 + block.id as i16 - 193, 1, 0),
+        26 => (355, 1, 0),
+        175 if matches!(meta, 2 | 3) => return Vec::new(),
+        175 => (175, 1, meta),
+        0
+        | 7..=11
+        | 18
+        | 20
+        | 31
+        | 32
+        | 34
+        | 36
+        | 51
+        | 52
+        | 79
+        | 90
+        | 92
+        | 95
+        | 97
+        | 99
+        | 100
+        | 102
+        | 104
+        | 105
+        | 106
+        | 119
+        | 120
+        | 160
+        | 161
+        | 166
+        | 174 => return Vec::new(),
+        id if keeps_meta(id) => (id as i16, 1, meta),
+        id => (id as i16, 1, 0),
+    };
+    vec![ItemStack {
+        id,
+        count,
+        damage,
+        meta: ItemMeta::default(),
+    }]
+}
+
+/// Whether placing a block replaces this one, like tall grass.
+pub fn is_replaceable(block: Block) -> bool {
+    match block.id {
+        0 | 8..=11 | 31 | 32 | 51 | 106 => true,
+        // a single layer of snow
+        78 => block.meta == 0,
+        _ => false,
+    }
+}
+
+/// Whether entities collide with a block.
+pub fn collides(block: Block) -> bool {
+    match block.id {
+        // the thinnest snow has no height
+        78 => block.meta != 0,
+        0
+        | 6
+        | 8..=11
+        | 27
+        | 28
+        | 30..=32
+        | 37..=40
+        | 50
+        | 51
+        | 55
+        | 59
+        | 63
+        | 66
+        | 68..=70
+        | 72
+        | 75..=77
+        | 83
+        | 90
+        | 93
+        | 94
+        | 104..=106
+        | 115
+        | 119
+        | 131
+        | 132
+        | 141..=143
+        | 147..=150
+        | 157
+        | 175..=177 => false,
+        _ => true,
+    }
+}
+
+/// Whether a block fills its whole space, things like torches only
+/// sticking to those.
+pub fn is_full_cube(block: Block) -> bool {
+    collides(block)
+        && !matches!(
+            block.id,
+            20 | 26
+                | 44
+                | 53
+                | 54
+                | 60
+                | 64
+                | 65
+                | 67
+                | 71
+                | 78
+                | 81
+                | 85
+                | 88
+                | 92
+                | 95
+                | 96
+                | 101
+                | 102
+                | 107..=109
+                | 111
+                | 113
+                | 114
+                | 116..=118
+                | 120
+                | 122
+                | 126
+                | 127
+                | 128
+                | 130
+                | 134..=136
+                | 139
+                | 140
+                | 144..=146
+                | 151
+                | 154
+                | 156
+                | 160
+                | 163
+                | 164
+                | 167
+                | 171
+                | 178
+                | 180
+                | 182..=197
+        )
+}
+
+/// Whether clicking a block uses it, like a chest or a door, rather than
+/// placing a block against it.
+pub fn is_interactive(id: u16) -> bool {
+    matches!(
+        id,
+        23 | 25
+            | 26
+            | 54
+            | 58
+            | 61
+            | 62
+            | 64
+            | 69
+            | 77
+            | 84
+            | 92..=94
+            | 96
+            | 107
+            | 116..=118
+            | 130
+            | 138
+            | 143
+            | 145
+            | 146
+            | 149
+            | 150
+            | 154
+            | 158
+            | 183..=187
+            | 193..=197
+    )
+}
+
+/// The other half of blocks two blocks high, or two long for beds.
+pub fn other_half(block: Block, position: BlockPosition) -> Option<BlockPosition> {
+    let BlockPosition { x, y, z } = position;
+    let upper = block.meta & 8 != 0;
+    match block.id {
+        64 | 71 | 175 | 193..=197 => Some(BlockPosition {
+            x,
+            y: if upper { y - 1 } else { y + 1 },
+            z,
+        }),
+        26 => {
+            // the foot points to the head
+            let (dx, dz) = [(0, 1), (-1, 0), (0, -1), (1, 0)][block.meta as usize & 3];
+            let sign = if upper { -1 } else { 1 };
+            Some(BlockPosition {
+                x: x + dx * sign,
+                y,
+                z: z + dz * sign,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The block state effects like breaking particles take, the id in the low
+/// 12 bits and the metadata above.
+pub fn effect_state(block: Block) -> i32 {
+    block.id as i32 | (block.meta as i32) << 12
+}
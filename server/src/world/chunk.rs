@@ -0,0 +1,323 @@
+use std::collections::BTreeMap;
+
+use makar_protocol::{ChunkColumn, ChunkSection};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Block {
+    pub id: u16,
+    pub meta: u8,
+}
+
+impl Block {
+    pub const AIR: Block = Block { id: 0, meta: 0 };
+
+    pub fn new(id: u16, meta: u8) -> Self {
+        Self { id, meta }
+    }
+
+    /// The block as sent to 1.8 clients, its id shifted left by 4 and ored
+    /// with its metadata.
+    pub fn state(&self) -> u16 {
+        (self.id << 4) | (self.meta as u16 & 0xF)
+    }
+
+    pub fn from_state(state: u16) -> Self {
+        Self {
+            id: state >> 4,
+            meta: (state & 0xF) as u8,
+        }
+    }
+}
+
+fn index(x: usize, y: usize, z: usize) -> usize {
+    (y << 8) | (z << 4) | x
+}
+
+fn get_nibble(array: &[u8], index: usize) -> u8 {
+    (array[index >> 1] >> ((index & 1) * 4)) & 0xF
+}
+
+fn set_nibble(array: &mut [u8], index: usize, value: u8) {
+    let shift = (index & 1) * 4;
+    array[index >> 1] = (array[index >> 1] & !(0xF << shift)) | ((value & 0xF) << shift);
+}
+
+/// A 16x16x16 cube of blocks along with its light.
+#[derive(Debug, Clone)]
+pub struct Section {
+    blocks: Vec<u16>,
+    block_light: Vec<u8>,
+    sky_light: Option<Vec<u8>>,
+    non_air: u16,
+}
+
+impl Section {
+    pub fn new(has_sky: bool) -> Self {
+        Self {
+            blocks: vec![0; 4096],
+            block_light: vec![0; 2048],
+            sky_light: has_sky.then(|| vec![0xFF; 2048]),
+            non_air: 0,
+        }
+    }
+
+    pub fn from_protocol(section: ChunkSection) -> Self {
+        let non_air = section.blocks.iter().filter(|b| **b >> 4 != 0).count() as u16;
+        Self {
+            blocks: section.blocks,
+            block_light: section.block_light,
+            sky_light: section.sky_light,
+            non_air,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.non_air == 0
+    }
+
+    pub fn get_block(&self, x: usize, y: usize, z: usize) -> Block {
+        Block::from_state(self.blocks[index(x, y, z)])
+    }
+
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: Block) -> Block {
+        let state = &mut self.blocks[index(x, y, z)];
+        let old = Block::from_state(*state);
+        *state = block.state();
+
+        match (old.id, block.id) {
+            (0, 0) => {}
+            (0, _) => self.non_air += 1,
+            (_, 0) => self.non_air -= 1,
+            _ => {}
+        };
+        old
+    }
+
+    pub fn get_block_light(&self, x: usize, y: usize, z: usize) -> u8 {
+        get_nibble(&self.block_light, index(x, y, z))
+    }
+
+    pub fn set_block_light(&mut self, x: usize, y: usize, z: usize, value: u8) {
+        set_nibble(&mut self.block_light, index(x, y, z), value);
+    }
+
+    pub fn get_sky_light(&self, x: usize, y: usize, z: usize) -> u8 {
+        match &self.sky_light {
+            Some(sky_light) => get_nibble(sky_light, index(x, y, z)),
+            None => 0,
+        }
+    }
+
+    pub fn set_sky_light(&mut self, x: usize, y: usize, z: usize, value: u8) {
+        if let Some(sky_light) = &mut self.sky_light {
+            set_nibble(sky_light, index(x, y, z), value);
+        }
+    }
+
+    pub fn to_protocol(&self) -> ChunkSection {
+        ChunkSection {
+            blocks: self.blocks.clone(),
+            block_light: self.block_light.clone(),
+            sky_light: self.sky_light.clone(),
+        }
+    }
+}
+
+/// A block that changed since the last time changes were taken, relative to
+/// its chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockChange {
+    pub x: u8,
+    pub y: u8,
+    pub z: u8,
+    pub block: Block,
+}
+
+/// A column of sixteen sections, 256 blocks high.
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    pub x: i32,
+    pub z: i32,
+    has_sky: bool,
+    sections: Vec<Option<Section>>,
+    biomes: Vec<u8>,
+    /// Whether the chunk changed since it was last saved.
+    dirty: bool,
+    /// Blocks changed since changes were last taken, by index in the column.
+    changes: BTreeMap<usize, Block>,
+}
+
+impl Chunk {
+    pub fn new(x: i32, z: i32, has_sky: bool) -> Self {
+        Self {
+            x,
+            z,
+            has_sky,
+            sections: vec![None; 16],
+            biomes: vec![1; 256],
+            dirty: false,
+            changes: BTreeMap::new(),
+        }
+    }
+
+    pub fn from_protocol(column: ChunkColumn, has_sky: bool) -> Self {
+        let mut chunk = Self::new(column.x, column.z, has_sky);
+        for (y, section) in column.sections.into_iter().enumerate().take(16) {
+            chunk.sections[y] = section.map(Section::from_protocol);
+        }
+        if column.biomes.len() == 256 {
+            chunk.biomes = column.biomes;
+        }
+        chunk
+    }
+
+    pub fn has_sky(&self) -> bool {
+        self.has_sky
+    }
+
+    pub fn section(&self, y: usize) -> Option<&Section> {
+        self.sections.get(y).and_then(Option::as_ref)
+    }
+
+    pub fn set_section(&mut self, y: usize, section: Option<Section>) {
+        self.sections[y] = section;
+        self.dirty = true;
+    }
+
+    pub fn get_block(&self, x: usize, y: usize, z: usize) -> Block {
+        match self.section(y >> 4) {
+            Some(section) => section.get_block(x, y & 0xF, z),
+            None => Block::AIR,
+        }
+    }
+
+    /// Sets a block, returning the one it replaced, or `None` above the
+    /// top of the chunk.
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: Block) -> Option<Block> {
+        let has_sky = self.has_sky;
+        let slot = self.sections.get_mut(y >> 4)?;
+        let section = slot.get_or_insert_with(|| Section::new(has_sky));
+        let old = section.set_block(x, y & 0xF, z, block);
+        if section.is_empty() {
+            *slot = None;
+        }
+
+        if old != block {
+            self.dirty = true;
+            self.changes.insert(index(x, y, z), block);
+        }
+        Some(old)
+    }
+
+    pub fn get_block_light(&self, x: usize, y: usize, z: usize) -> u8 {
+        match self.section(y >> 4) {
+            Some(section) => section.get_block_light(x, y & 0xF, z),
+            None => 0,
+        }
+    }
+
+    pub fn set_block_light(&mut self, x: usize, y: usize, z: usize, value: u8) {
+        if let Some(Some(section)) = self.sections.get_mut(y >> 4) {
+            section.set_block_light(x, y & 0xF, z, value);
+            self.dirty = true;
+        }
+    }
+
+    pub fn get_sky_light(&self, x: usize, y: usize, z: usize) -> u8 {
+        match self.section(y >> 4) {
+            Some(section) => section.get_sky_light(x, y & 0xF, z),
+            None if self.has_sky => 15,
+            None => 0,
+        }
+    }
+
+    pub fn set_sky_light(&mut self, x: usize, y: usize, z: usize, value: u8) {
+        if let Some(Some(section)) = self.sections.get_mut(y >> 4) {
+            section.set_sky_light(x, y & 0xF, z, value);
+            self.dirty = true;
+        }
+    }
+
+    pub fn get_biome(&self, x: usize, z: usize) -> u8 {
+        self.biomes[(z << 4) | x]
+    }
+
+    pub fn set_biome(&mut self, x: usize, z: usize, biome: u8) {
+        self.biomes[(z << 4) | x] = biome;
+        self.dirty = true;
+    }
+
+    pub fn biomes(&self) -> &[u8] {
+        &self.biomes
+    }
+
+    /// Y coordinate of the block above the highest non air block.
+    pub fn height(&self, x: usize, z: usize) -> usize {
+        (0..256)
+            .rev()
+            .find(|y| self.get_block(x, *y, z).id != 0)
+            .map_or(0, |y| y + 1)
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.dirty = false;
+    }
+
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn has_changes(&self) -> bool {
+        !self.changes.is_empty()
+    }
+
+    pub fn take_changes(&mut self) -> Vec<BlockChange> {
+        std::mem::take(&mut self.changes)
+            .into_iter()
+            .map(|(i, block)| BlockChange {
+                x: (i & 0xF) as u8,
+                y: (i >> 8) as u8,
+                z: ((i >> 4) & 0xF) as u8,
+                block,
+            })
+            .collect()
+    }
+
+    pub fn to_protocol(&self) -> ChunkColumn {
+        ChunkColumn {
+            x: self.x,
+            z: self.z,
+            sections: self
+                .sections
+                .iter()
+                .map(|section| section.as_ref().map(Section::to_protocol))
+                .collect(),
+            biomes: self.biomes.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_blocks_above_the_top() {
+        let mut chunk = Chunk::new(0, 0, true);
+        assert_eq!(
+            chunk.set_block(1, 255, 2, Block::new(1, 0)),
+            Some(Block::AIR)
+        );
+        chunk.take_changes();
+
+        assert_eq!(chunk.set_block(1, 256, 2, Block::new(1, 0)), None);
+        chunk.set_block_light(1, 256, 2, 15);
+        chunk.set_sky_light(1, 300, 2, 0);
+        assert_eq!(chunk.get_block(1, 256, 2), Block::AIR);
+        assert!(chunk.take_changes().is_empty());
+    }
+}
@@ -0,0 +1,231 @@
+use std::str::FromStr;
+
+use super::ChunkGenerator;
+use crate::world::{Block, Chunk};
+
+/// The preset of the default superflat world.
+pub const CLASSIC_FLAT: &str = "3;minecraft:bedrock,2*minecraft:dirt,minecraft:grass;1;village";
+
+#[derive(Debug, thiserror::Error)]
+pub enum PresetError {
+    #[error("unknown block {0}")]
+    UnknownBlock(String),
+    #[error("invalid number {0}")]
+    InvalidNumber(String),
+    #[error("layers are higher than the world")]
+    TooHigh,
+}
+
+/// Ids of the blocks superflat presets usually name.
+const BLOCKS: &[(&str, u16)] = &[
+    ("air", 0),
+    ("stone", 1),
+    ("grass", 2),
+    ("dirt", 3),
+    ("cobblestone", 4),
+    ("planks", 5),
+    ("bedrock", 7),
+    ("flowing_water", 8),
+    ("water", 9),
+    ("flowing_lava", 10),
+    ("lava", 11),
+    ("sand", 12),
+    ("gravel", 13),
+    ("gold_ore", 14),
+    ("iron_ore", 15),
+    ("coal_ore", 16),
+    ("log", 17),
+    ("leaves", 18),
+    ("glass", 20),
+    ("sandstone", 24),
+    ("wool", 35),
+    ("obsidian", 49),
+    ("snow_layer", 78),
+    ("ice", 79),
+    ("snow", 80),
+    ("clay", 82),
+    ("netherrack", 87),
+    ("stonebrick", 98),
+    ("mycelium", 110),
+    ("end_stone", 121),
+    ("quartz_block", 155),
+    ("stained_hardened_clay", 159),
+    ("hardened_clay", 172),
+    ("packed_ice", 174),
+];
+
+fn number<T: FromStr>(s: &str) -> Result<T, PresetError> {
+    s.parse()
+        .map_err(|_| PresetError::InvalidNumber(s.to_string()))
+}
+
+/// Parses a block named like `minecraft:stone`, `stone:2` or `1:2`.
+fn parse_block(s: &str) -> Result<Block, PresetError> {
+    let s = s.strip_prefix("minecraft:").unwrap_or(s);
+    let (name, meta) = match s.split_once(':') {
+        Some((name, meta)) => (name, number(meta)?),
+        None => (s, 0),
+    };
+    let id = match name.parse() {
+        Ok(id) => id,
+        Err(_) => BLOCKS
+            .iter()
+            .find(|(block, _)| *block == name)
+            .map(|(_, id)| *id)
+            .ok_or_else(|| PresetError::UnknownBlock(name.to_string()))?,
+    };
+    Ok(Block::new(id, meta))
+}
+
+/// Generates layers of blocks that are the same everywhere.
+pub struct FlatGenerator {
+    /// One block per y coordinate, starting from the bottom of the world.
+    layers: Vec<Block>,
+    biome: u8,
+}
+
+impl FlatGenerator {
+    pub fn new(layers: &[(usize, Block)], biome: u8) -> Result<Self, PresetError> {
+        let layers: Vec<_> = layers
+            .iter()
+            .flat_map(|(count, block)| (0..*count).map(|_| *block))
+            .collect();
+        if layers.len() > 256 {
+            return Err(PresetError::TooHigh);
+        }
+        Ok(Self { layers, biome })
+    }
+}
+
+impl Default for FlatGenerator {
+    fn default() -> Self {
+        CLASSIC_FLAT
+            .parse()
+            .expect("classic preset should be valid")
+    }
+}
+
+impl FromStr for FlatGenerator {
+    type Err = PresetError;
+
+    /// Parses a superflat preset, `version;layers;biome;structures` with
+    /// everything but the layers being optional. Layers are separated by
+    /// commas, counted with `*` since version 3 and with `x` before.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = s.split(';').collect();
+        let (version, layers, biome) = match parts[..] {
+            [layers] => (0, layers, None),
+            [version, layers] => (number(version)?, layers, None),
+            [version, layers, biome, ..] => (number(version)?, layers, Some(biome)),
+            [] => unreachable!("split always yields a part"),
+        };
+        let separator = if version >= 3 { '*' } else { 'x' };
+
+        let layers = layers
+            .split(',')
+            .filter(|layer| !layer.is_empty())
+            .map(|layer| match layer.split_once(separator) {
+                Some((count, block)) => Ok((number(count)?, parse_block(block)?)),
+                None => Ok((1, parse_block(layer)?)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let biome = match biome {
+            Some(biome) => number(biome)?,
+            None => 1,
+        };
+        Self::new(&layers, biome)
+    }
+}
+
+impl ChunkGenerator for FlatGenerator {
+    fn generate(&self, x: i32, z: i32, has_sky: bool) -> Chunk {
+        let mut chunk = Chunk::new(x, z, has_sky);
+        for (y, block) in self.layers.iter().enumerate() {
+            for x in 0..16 {
+                for z in 0..16 {
+                    chunk.set_block(x, y, z, *block);
+                }
+            }
+        }
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk.set_biome(x, z, self.biome);
+            }
+        }
+        chunk.take_changes();
+        chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(generator: &FlatGenerator) -> Vec<Block> {
+        let chunk = generator.generate(3, -2, true);
+        (0..6).map(|y| chunk.get_block(5, y, 9)).collect()
+    }
+
+    #[test]
+    fn parses_presets() {
+        let classic = FlatGenerator::default();
+        assert_eq!(
+            column(&classic),
+            [
+                Block::new(7, 0),
+                Block::new(3, 0),
+                Block::new(3, 0),
+                Block::new(2, 0),
+                Block::AIR,
+                Block::AIR,
+            ]
+        );
+        assert_eq!(classic.generate(0, 0, true).get_biome(15, 15), 1);
+
+        // versions before 3 count layers with x
+        let old: FlatGenerator = "2;3x1:2,minecraft:wool:14;2".parse().unwrap();
+        assert_eq!(
+            column(&old),
+            [
+                Block::new(1, 2),
+                Block::new(1, 2),
+                Block::new(1, 2),
+                Block::new(35, 14),
+                Block::AIR,
+                Block::AIR,
+            ]
+        );
+        assert_eq!(old.generate(0, 0, true).get_biome(0, 0), 2);
+
+        let layers_only: FlatGenerator = "bedrock,,sand".parse().unwrap();
+        assert_eq!(
+            column(&layers_only)[..3],
+            [Block::new(7, 0), Block::new(12, 0), Block::AIR]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_presets() {
+        assert!(matches!(
+            "3;minecraft:unobtainium".parse::<FlatGenerator>(),
+            Err(PresetError::UnknownBlock(block)) if block == "unobtainium"
+        ));
+        assert!(matches!(
+            "3;many*stone".parse::<FlatGenerator>(),
+            Err(PresetError::InvalidNumber(number)) if number == "many"
+        ));
+        assert!(matches!(
+            "3;stone:red".parse::<FlatGenerator>(),
+            Err(PresetError::InvalidNumber(number)) if number == "red"
+        ));
+        assert!(matches!(
+            "3;stone;plains".parse::<FlatGenerator>(),
+            Err(PresetError::InvalidNumber(number)) if number == "plains"
+        ));
+        assert!(matches!(
+            "3;200*stone,57*dirt".parse::<FlatGenerator>(),
+            Err(PresetError::TooHigh)
+        ));
+        assert!("3;200*stone,56*dirt".parse::<FlatGenerator>().is_ok());
+    }
+}
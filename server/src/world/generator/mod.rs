@@ -0,0 +1,24 @@
+mod flat;
+mod noise;
+
+pub use flat::{FlatGenerator, PresetError};
+pub use noise::NoiseGenerator;
+
+use super::Chunk;
+
+/// Creates the chunks of a world that were never saved.
+///
+/// Generators run on worker threads and must be deterministic, a chunk only
+/// depending on its coordinates and the generator's own settings.
+pub trait ChunkGenerator: Send + Sync {
+    fn generate(&self, x: i32, z: i32, has_sky: bool) -> Chunk;
+}
+
+/// Generates empty chunks.
+pub struct VoidGenerator;
+
+impl ChunkGenerator for VoidGenerator {
+    fn generate(&self, x: i32, z: i32, has_sky: bool) -> Chunk {
+        Chunk::new(x, z, has_sky)
+    }
+}
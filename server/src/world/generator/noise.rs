@@ -0,0 +1,313 @@
+use super::ChunkGenerator;
+use crate::world::{Block, Chunk};
+
+const SEA_LEVEL: usize = 62;
+
+const STONE: Block = Block { id: 1, meta: 0 };
+const GRASS: Block = Block { id: 2, meta: 0 };
+const DIRT: Block = Block { id: 3, meta: 0 };
+const BEDROCK: Block = Block { id: 7, meta: 0 };
+const WATER: Block = Block { id: 9, meta: 0 };
+const SAND: Block = Block { id: 12, meta: 0 };
+const GRAVEL: Block = Block { id: 13, meta: 0 };
+const LOG: Block = Block { id: 17, meta: 0 };
+const LEAVES: Block = Block { id: 18, meta: 0 };
+const SANDSTONE: Block = Block { id: 24, meta: 0 };
+
+const OCEAN: u8 = 0;
+const PLAINS: u8 = 1;
+const DESERT: u8 = 2;
+const FOREST: u8 = 4;
+const BEACH: u8 = 16;
+
+/// SplitMix64, good enough to shuffle permutation tables and place trees.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn grad(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = match h {
+        0..=3 => y,
+        12 | 14 => x,
+        _ => z,
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+/// Improved Perlin noise over a permutation table shuffled from a seed.
+pub struct Perlin {
+    permutation: [u8; 512],
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut table: Vec<u8> = (0..=255).collect();
+        let mut state = seed;
+        for i in (1..256).rev() {
+            state = mix(state);
+            table.swap(i, (state % (i as u64 + 1)) as usize);
+        }
+
+        let mut permutation = [0; 512];
+        for i in 0..512 {
+            permutation[i] = table[i & 255];
+        }
+        Self { permutation }
+    }
+
+    /// Noise roughly between -1 and 1.
+    pub fn noise(&self, x: f64, y: f64, z: f64) -> f64 {
+        let p = &self.permutation;
+        let (xi, yi, zi) = (
+            x.floor() as i64 as usize & 255,
+            y.floor() as i64 as usize & 255,
+            z.floor() as i64 as usize & 255,
+        );
+        let (x, y, z) = (x - x.floor(), y - y.floor(), z - z.floor());
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let a = p[xi] as usize + yi;
+        let (aa, ab) = (p[a] as usize + zi, p[a + 1] as usize + zi);
+        let b = p[xi + 1] as usize + yi;
+        let (ba, bb) = (p[b] as usize + zi, p[b + 1] as usize + zi);
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(p[aa], x, y, z), grad(p[ba], x - 1.0, y, z)),
+                lerp(
+                    u,
+                    grad(p[ab], x, y - 1.0, z),
+                    grad(p[bb], x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(p[aa + 1], x, y, z - 1.0),
+                    grad(p[ba + 1], x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    grad(p[ab + 1], x, y - 1.0, z - 1.0),
+                    grad(p[bb + 1], x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+
+    /// Sums octaves of noise, each one twice as detailed and half as strong
+    /// as the previous one.
+    pub fn octaves(&self, x: f64, y: f64, z: f64, octaves: u32) -> f64 {
+        let (mut total, mut amplitude, mut frequency, mut max) = (0.0, 1.0, 1.0, 0.0);
+        for _ in 0..octaves {
+            total += self.noise(x * frequency, y * frequency, z * frequency) * amplitude;
+            max += amplitude;
+            amplitude /= 2.0;
+            frequency *= 2.0;
+        }
+        total / max
+    }
+}
+
+/// Generates hills from a noise heightmap, with oceans, beaches, deserts,
+/// forests, caves and trees.
+pub struct NoiseGenerator {
+    seed: u64,
+    height: Perlin,
+    temperature: Perlin,
+    humidity: Perlin,
+    caves: Perlin,
+}
+
+impl NoiseGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            height: Perlin::new(seed),
+            temperature: Perlin::new(mix(seed ^ 1)),
+            humidity: Perlin::new(mix(seed ^ 2)),
+            caves: Perlin::new(mix(seed ^ 3)),
+        }
+    }
+
+    fn column(&self, x: i32, z: i32) -> (usize, u8) {
+        let (x, z) = (x as f64, z as f64);
+        let height = 64.0 + self.height.octaves(x / 128.0, 0.0, z / 128.0, 5) * 40.0;
+        let height = height.clamp(8.0, 200.0) as usize;
+
+        let temperature = self.temperature.octaves(x / 512.0, 0.0, z / 512.0, 2);
+        let humidity = self.humidity.octaves(x / 512.0, 0.0, z / 512.0, 2);
+        let biome = match height {
+            ..=58 => OCEAN,
+            59..=63 => BEACH,
+            _ if temperature > 0.15 && humidity < 0.0 => DESERT,
+            _ if humidity > 0.1 => FOREST,
+            _ => PLAINS,
+        };
+        (height, biome)
+    }
+
+    fn is_cave(&self, x: i32, y: usize, z: i32) -> bool {
+        let noise = self
+            .caves
+            .octaves(x as f64 / 32.0, y as f64 / 16.0, z as f64 / 32.0, 2);
+        noise.abs() < 0.04
+    }
+
+    /// Plants an oak tree on the grass block below `y` if the chunk is
+    /// wide enough around it.
+    fn tree(chunk: &mut Chunk, x: usize, y: usize, z: usize, trunk: usize) {
+        if !(2..14).contains(&x) || !(2..14).contains(&z) || y + trunk + 2 > 256 {
+            return;
+        }
+
+        let top = y + trunk;
+        for leaves_y in top - 2..=top + 1 {
+            let radius = if leaves_y > top - 1 { 1 } else { 2 };
+            for leaves_x in x - radius..=x + radius {
+                for leaves_z in z - radius..=z + radius {
+                    // the narrow top layers are shaped like a plus
+                    let corner = leaves_x.abs_diff(x) == radius && leaves_z.abs_diff(z) == radius;
+                    if corner && radius == 1 {
+                        continue;
+                    }
+                    if chunk.get_block(leaves_x, leaves_y, leaves_z).id == 0 {
+                        chunk.set_block(leaves_x, leaves_y, leaves_z, LEAVES);
+                    }
+                }
+            }
+        }
+        for y in y..top {
+            chunk.set_block(x, y, z, LOG);
+        }
+        chunk.set_block(x, y - 1, z, DIRT);
+    }
+}
+
+impl ChunkGenerator for NoiseGenerator {
+    fn generate(&self, chunk_x: i32, chunk_z: i32, has_sky: bool) -> Chunk {
+        let mut chunk = Chunk::new(chunk_x, chunk_z, has_sky);
+        let mut trees = Vec::new();
+
+        for x in 0..16 {
+            for z in 0..16 {
+                let (world_x, world_z) = ((chunk_x << 4) + x as i32, (chunk_z << 4) + z as i32);
+                let (height, biome) = self.column(world_x, world_z);
+                chunk.set_biome(x, z, biome);
+
+                let (top, filler) = match biome {
+                    OCEAN => (GRAVEL, GRAVEL),
+                    BEACH | DESERT => (SAND, SANDSTONE),
+                    _ => (GRASS, DIRT),
+                };
+                chunk.set_block(x, 0, z, BEDROCK);
+                for y in 1..height {
+                    let block = match height - y {
+                        1 => top,
+                        2..=4 => filler,
+                        _ => STONE,
+                    };
+                    // caves stay below the surface so they don't flood
+                    if y + 6 < height && y > 4 && self.is_cave(world_x, y, world_z) {
+                        continue;
+                    }
+                    chunk.set_block(x, y, z, block);
+                }
+                for y in height..=SEA_LEVEL {
+                    chunk.set_block(x, y, z, WATER);
+                }
+
+                // trees per thousand columns
+                let density = match biome {
+                    FOREST => 80,
+                    PLAINS => 5,
+                    _ => continue,
+                };
+                let hash = mix(self.seed ^ mix(((world_x as u64) << 32) | world_z as u32 as u64));
+                if hash % 1000 < density {
+                    trees.push((x, height, z, 4 + (hash >> 32) as usize % 3));
+                }
+            }
+        }
+
+        for (x, y, z, trunk) in trees {
+            if chunk.get_block(x, y - 1, z) == GRASS {
+                Self::tree(&mut chunk, x, y, z, trunk);
+            }
+        }
+        chunk.take_changes();
+        chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_same(a: &Chunk, b: &Chunk) {
+        assert_eq!(a.biomes(), b.biomes());
+        for y in 0..256 {
+            for z in 0..16 {
+                for x in 0..16 {
+                    assert_eq!(a.get_block(x, y, z), b.get_block(x, y, z));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generation_is_deterministic() {
+        let (a, b) = (NoiseGenerator::new(42), NoiseGenerator::new(42));
+        for (x, z) in [(0, 0), (-7, 3), (120, -45)] {
+            assert_same(&a.generate(x, z, true), &b.generate(x, z, true));
+            // chunks don't depend on what was generated before
+            assert_same(&a.generate(x, z, true), &a.generate(x, z, true));
+        }
+
+        let other = NoiseGenerator::new(43).generate(0, 0, true);
+        let chunk = a.generate(0, 0, true);
+        let differs = (0..256).any(|y| {
+            (0..16).any(|x| (0..16).any(|z| chunk.get_block(x, y, z) != other.get_block(x, y, z)))
+        });
+        assert!(differs);
+    }
+
+    #[test]
+    fn perlin_stays_in_bounds() {
+        let perlin = Perlin::new(7);
+        let (mut min, mut max) = (f64::MAX, f64::MIN);
+        for i in 0..20_000 {
+            let (x, y, z) = (
+                i as f64 * 0.37,
+                i as f64 * 0.113 - 900.0,
+                (i % 97) as f64 * 0.71,
+            );
+            let noise = perlin.noise(x, y, z);
+            let octaves = perlin.octaves(x, y, z, 5);
+            assert!(noise.abs() <= 1.1, "{noise} at {x}, {y}, {z}");
+            assert!(octaves.abs() <= 1.1, "{octaves} at {x}, {y}, {z}");
+            min = min.min(noise);
+            max = max.max(noise);
+        }
+        // the noise covers most of its range
+        assert!(min < -0.5 && max > 0.5);
+        // and is zero on the lattice
+        assert_eq!(perlin.noise(3.0, -12.0, 40.0), 0.0);
+    }
+}
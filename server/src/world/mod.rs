@@ -0,0 +1,653 @@
+pub mod anvil;
+pub mod blocks;
+mod chunk;
+pub mod generator;
+pub mod nbt;
+pub mod placement;
+mod pool;
+mod weather;
+
+pub use anvil::{Anvil, AnvilError};
+pub use chunk::{Block, BlockChange, Chunk, Section};
+pub use pool::ChunkPool;
+pub use weather::Weather;
+
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    path::Path,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+};
+
+use crate::view::spiral;
+use generator::ChunkGenerator;
+use weather::Sky;
+
+use log::warn;
+
+use makar_protocol::{BlockPosition, BlockRecord, GameStateChange, ProxyBoundPacket};
+
+/// The kind of world clients render, with its sky, fog and light.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    Nether,
+    Overworld,
+    End,
+}
+
+impl Dimension {
+    /// The id of the dimension in the protocol.
+    pub fn id(&self) -> i32 {
+        match self {
+            Dimension::Nether => -1,
+            Dimension::Overworld => 0,
+            Dimension::End => 1,
+        }
+    }
+
+    /// Whether the dimension has a sky, chunks of the others carrying no
+    /// sky light.
+    pub fn has_sky(&self) -> bool {
+        *self == Dimension::Overworld
+    }
+}
+
+/// Chunks around the spawn point kept loaded, so that players can join and
+/// respawn without waiting for them.
+pub(crate) const SPAWN_RADIUS: i32 = 3;
+
+/// Whether a chunk is one of those kept loaded around the spawn point.
+pub(crate) fn in_spawn_area((x, z): (i32, i32)) -> bool {
+    x.abs() <= SPAWN_RADIUS && z.abs() <= SPAWN_RADIUS
+}
+
+/// Chunk coordinates of the column containing a block position.
+pub fn chunk_pos(position: BlockPosition) -> (i32, i32) {
+    (position.x >> 4, position.z >> 4)
+}
+
+/// What a world is told once a job it gave the pool is over.
+enum Done {
+    Loaded(Chunk),
+    Saved((i32, i32), Result<(), AnvilError>),
+}
+
+fn lock(storage: &Mutex<Anvil>) -> MutexGuard<'_, Anvil> {
+    // region files are written whole, so a panicking job can't leave them
+    // half written
+    storage.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn read_chunk(storage: &Mutex<Anvil>, x: i32, z: i32, has_sky: bool) -> Option<Chunk> {
+    lock(storage).load_chunk(x, z, has_sky).unwrap_or_else(|e| {
+        warn!("couldn't load chunk {x}, {z}: {e}");
+        None
+    })
+}
+
+pub struct World {
+    dimension: Dimension,
+    /// Tells clients how to draw the horizon, like `default` or `flat`.
+    level_type: String,
+    /// Ticks since the world was created.
+    age: i64,
+    /// Ticks since the first sunrise, which stays put while frozen.
+    time: i64,
+    time_frozen: bool,
+    /// Whether the time was set since players were last told.
+    time_changed: bool,
+    sky: Sky,
+    chunks: HashMap<(i32, i32), Chunk>,
+    /// Where chunks are loaded from and saved to, if anywhere, by the jobs
+    /// of the pool.
+    storage: Option<Arc<Mutex<Anvil>>>,
+    generator: Arc<dyn ChunkGenerator>,
+    /// Workers shared by the worlds of a game, chunks being loaded and saved
+    /// on the calling thread until the world is added to one.
+    pool: Option<Arc<ChunkPool>>,
+    /// Where jobs say they're over, handled by [`World::poll_generated`].
+    done: Sender<Done>,
+    completed: Receiver<Done>,
+    /// Chunks being read or generated and not loaded yet.
+    loading: HashSet<(i32, i32)>,
+    /// Chunks being saved, which aren't saved again or evicted until that's
+    /// over.
+    saving: HashSet<(i32, i32)>,
+}
+
+impl World {
+    pub fn new(dimension: Dimension, generator: Arc<dyn ChunkGenerator>) -> Self {
+        let (done, completed) = mpsc::channel();
+        Self {
+            dimension,
+            level_type: "default".to_string(),
+            age: 0,
+            time: 0,
+            time_frozen: false,
+            time_changed: false,
+            sky: Sky::default(),
+            chunks: HashMap::new(),
+            storage: None,
+            generator,
+            pool: None,
+            done,
+            completed,
+            loading: HashSet::new(),
+            saving: HashSet::new(),
+        }
+    }
+
+    /// Opens a world saved in the Anvil format, its region files being read
+    /// as chunks get loaded and chunks that were never saved being generated.
+    pub fn open(
+        directory: &Path,
+        dimension: Dimension,
+        generator: Arc<dyn ChunkGenerator>,
+    ) -> Self {
+        Self {
+            storage: Some(Arc::new(Mutex::new(Anvil::new(directory.join("region"))))),
+            ..Self::new(dimension, generator)
+        }
+    }
+
+    pub fn has_sky(&self) -> bool {
+        self.dimension.has_sky()
+    }
+
+    /// Loads and saves chunks on the workers of a game rather than the
+    /// calling thread.
+    pub(crate) fn set_pool(&mut self, pool: Arc<ChunkPool>) {
+        self.pool = Some(pool);
+    }
+
+    pub fn with_level_type(mut self, level_type: impl Into<String>) -> Self {
+        self.level_type = level_type.into();
+        self
+    }
+
+    pub fn dimension(&self) -> Dimension {
+        self.dimension
+    }
+
+    pub fn level_type(&self) -> &str {
+        &self.level_type
+    }
+
+    /// Freezes the time at a time of day, like lobbies always showing noon.
+    pub fn with_frozen_time(mut self, time: i64) -> Self {
+        self.time = time;
+        self.time_frozen = true;
+        self
+    }
+
+    pub fn age(&self) -> i64 {
+        self.age
+    }
+
+    pub fn time(&self) -> i64 {
+        self.time
+    }
+
+    /// Sets the time, players being told right away rather than on the next
+    /// time update.
+    pub fn set_time(&mut self, time: i64) {
+        self.time = time;
+        self.time_changed = true;
+    }
+
+    pub fn is_time_frozen(&self) -> bool {
+        self.time_frozen
+    }
+
+    pub fn freeze_time(&mut self, frozen: bool) {
+        self.time_frozen = frozen;
+        self.time_changed = true;
+    }
+
+    pub fn weather(&self) -> Weather {
+        self.sky.weather
+    }
+
+    /// Changes the weather, which fades in and out over a few seconds.
+    pub fn set_weather(&mut self, weather: Weather) {
+        self.sky.weather = weather;
+    }
+
+    /// Advances the time by a tick, returning whether players should be
+    /// told about it on top of the regular time updates.
+    pub(crate) fn advance_time(&mut self) -> bool {
+        self.age += 1;
+        if !self.time_frozen {
+            self.time += 1;
+        }
+        std::mem::take(&mut self.time_changed)
+    }
+
+    /// Fades the weather by a tick, returning what players in the world
+    /// should be told.
+    pub(crate) fn update_weather(&mut self) -> Vec<GameStateChange> {
+        self.sky.update()
+    }
+
+    /// The weather shown to players arriving in the world.
+    pub(crate) fn current_weather(&self) -> Vec<GameStateChange> {
+        self.sky.current()
+    }
+
+    /// The packet telling a client the time, which it keeps advancing on its
+    /// own unless the time is frozen.
+    pub fn time_packet(&self, player: u128) -> ProxyBoundPacket {
+        // clients stop the day from going by when given a negative time
+        let time_of_day = match self.time_frozen {
+            true => -self.time.max(1),
+            false => self.time,
+        };
+        ProxyBoundPacket::TimeUpdate {
+            player,
+            world_age: self.age,
+            time_of_day,
+        }
+    }
+
+    pub fn chunk(&self, x: i32, z: i32) -> Option<&Chunk> {
+        self.chunks.get(&(x, z))
+    }
+
+    pub fn chunk_mut(&mut self, x: i32, z: i32) -> Option<&mut Chunk> {
+        self.chunks.get_mut(&(x, z))
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = &Chunk> {
+        self.chunks.values()
+    }
+
+    /// Returns the chunk at the given coordinates, reading it from the
+    /// world's region files if it isn't loaded, or generating it on the
+    /// calling thread if it was never saved. This blocks, and is meant for
+    /// setting worlds up before they're hosted.
+    pub fn load_chunk(&mut self, x: i32, z: i32) -> &mut Chunk {
+        if !self.chunks.contains_key(&(x, z)) {
+            let has_sky = self.has_sky();
+            let chunk = match self
+                .storage
+                .as_ref()
+                .and_then(|storage| read_chunk(storage, x, z, has_sky))
+            {
+                Some(chunk) => chunk,
+                None => self.generated(self.generator.generate(x, z, has_sky)),
+            };
+            self.loading.remove(&(x, z));
+            self.chunks.insert((x, z), chunk);
+        }
+        self.chunks
+            .get_mut(&(x, z))
+            .expect("chunk should be loaded")
+    }
+
+    /// Generated chunks are saved like modified ones, while worlds that
+    /// aren't saved anywhere can generate them again and only need to keep
+    /// the chunks modified afterwards.
+    fn generated(&self, mut chunk: Chunk) -> Chunk {
+        if self.storage.is_none() {
+            chunk.mark_saved();
+        }
+        chunk
+    }
+
+    fn execute(&self, job: impl FnOnce() + Send + 'static) {
+        match &self.pool {
+            Some(pool) => pool.execute(job),
+            None => job(),
+        }
+    }
+
+    /// Makes sure a chunk gets loaded without waiting for it to be read or
+    /// generated, returning whether it is loaded already. Chunks queued
+    /// this way are loaded by [`World::poll_generated`].
+    pub fn request_chunk(&mut self, x: i32, z: i32) -> bool {
+        if self.chunks.contains_key(&(x, z)) {
+            return true;
+        }
+        if !self.loading.insert((x, z)) {
+            return false;
+        }
+        let (storage, generator) = (self.storage.clone(), self.generator.clone());
+        let (done, has_sky) = (self.done.clone(), self.has_sky());
+        self.execute(move || {
+            let chunk = storage
+                .and_then(|storage| read_chunk(&storage, x, z, has_sky))
+                .unwrap_or_else(|| generator.generate(x, z, has_sky));
+            let _ = done.send(Done::Loaded(chunk));
+        });
+        false
+    }
+
+    /// Loads a chunk coming out of the pool, returning whether it was still
+    /// missing.
+    fn insert_loaded(&mut self, chunk: Chunk) -> bool {
+        let key = (chunk.x, chunk.z);
+        // chunks loaded in the meantime are kept as they are
+        if !self.loading.remove(&key) {
+            return false;
+        }
+        let chunk = self.generated(chunk);
+        match self.chunks.entry(key) {
+            Entry::Vacant(entry) => {
+                entry.insert(chunk);
+                true
+            }
+            Entry::Occupied(_) => false,
+        }
+    }
+
+    /// Handles a job being over, returning the chunk it loaded.
+    fn complete(&mut self, done: Done) -> Option<(i32, i32)> {
+        match done {
+            Done::Loaded(chunk) => {
+                let key = (chunk.x, chunk.z);
+                self.insert_loaded(chunk).then_some(key)
+            }
+            Done::Saved(key, result) => {
+                self.saving.remove(&key);
+                if let Err(e) = result {
+                    warn!("couldn't save chunk {}, {}: {e}", key.0, key.1);
+                    // the chunk stays loaded until a later save works out
+                    if let Some(chunk) = self.chunks.get_mut(&key) {
+                        chunk.mark_dirty();
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    /// Loads the chunks read or generated since the last call, returning
+    /// their coordinates, and records the saves that are over.
+    pub fn poll_generated(&mut self) -> Vec<(i32, i32)> {
+        let completed: Vec<_> = self.completed.try_iter().collect();
+        completed
+            .into_iter()
+            .filter_map(|done| self.complete(done))
+            .collect()
+    }
+
+    /// Makes sure the chunks around the spawn point get loaded, returning
+    /// whether they all are.
+    pub fn request_spawn_area(&mut self) -> bool {
+        spiral((0, 0), SPAWN_RADIUS)
+            .into_iter()
+            .fold(true, |loaded, (x, z)| self.request_chunk(x, z) && loaded)
+    }
+
+    /// Where players spawn, on top of the center of the chunk at the origin,
+    /// once the chunks around it are loaded.
+    pub fn spawn_point(&mut self) -> Option<BlockPosition> {
+        if !self.request_spawn_area() {
+            return None;
+        }
+        let (x, z) = (8, 8);
+        let chunk = self.chunk(0, 0)?;
+        Some(BlockPosition {
+            x,
+            y: chunk.height(x as usize, z as usize) as i32,
+            z,
+        })
+    }
+
+    /// Queues the chunks modified since they were last saved to be written
+    /// to the world's region files, returning how many were queued.
+    pub fn save(&mut self) -> usize {
+        let dirty: Vec<_> = self
+            .chunks
+            .iter()
+            .filter(|(key, chunk)| chunk.is_dirty() && !self.saving.contains(key))
+            .map(|(key, _)| *key)
+            .collect();
+        dirty
+            .into_iter()
+            .filter(|key| self.save_chunk(*key))
+            .count()
+    }
+
+    /// Saves a copy of a chunk in the background, returning whether it was
+    /// queued. A chunk is only saved again once its last save is over, so
+    /// that saves land in order.
+    fn save_chunk(&mut self, key: (i32, i32)) -> bool {
+        let (Some(storage), Some(chunk)) = (self.storage.clone(), self.chunks.get_mut(&key)) else {
+            return false;
+        };
+        if !self.saving.insert(key) {
+            return false;
+        }
+        chunk.mark_saved();
+        let (chunk, done) = (chunk.clone(), self.done.clone());
+        self.execute(move || {
+            let result = lock(&storage).save_chunk(&chunk);
+            let _ = done.send(Done::Saved(key, result));
+        });
+        true
+    }
+
+    /// Blocks until the chunks being saved are written, so that nothing is
+    /// lost when the world or its pool are dropped.
+    pub fn flush(&mut self) {
+        while !self.saving.is_empty() {
+            match self.completed.recv() {
+                Ok(done) => {
+                    self.complete(done);
+                }
+                Err(_) => break,
+            }
+        }
+    }
+
+    pub fn insert_chunk(&mut self, chunk: Chunk) -> Option<Chunk> {
+        self.chunks.insert((chunk.x, chunk.z), chunk)
+    }
+
+    pub fn remove_chunk(&mut self, x: i32, z: i32) -> Option<Chunk> {
+        self.chunks.remove(&(x, z))
+    }
+
+    /// Removes the chunks outside of the spawn area that `seen` rejects,
+    /// returning how many were removed. Modified chunks are saved and
+    /// removed by a later call once that worked out, or kept when the world
+    /// isn't saved anywhere, so that changes aren't lost.
+    pub fn evict(&mut self, seen: impl Fn((i32, i32)) -> bool) -> usize {
+        let unseen: Vec<_> = self
+            .chunks
+            .keys()
+            .copied()
+            .filter(|chunk| !in_spawn_area(*chunk) && !seen(*chunk))
+            .collect();
+        let mut evicted = 0;
+        for key in unseen {
+            if self.saving.contains(&key) {
+                continue;
+            }
+            if self.chunks[&key].is_dirty() {
+                self.save_chunk(key);
+                continue;
+            }
+            self.chunks.remove(&key);
+            evicted += 1;
+        }
+        evicted
+    }
+
+    fn locate(&self, position: BlockPosition) -> Option<(&Chunk, usize, usize, usize)> {
+        if !(0..256).contains(&position.y) {
+            return None;
+        }
+        let (chunk_x, chunk_z) = chunk_pos(position);
+        self.chunk(chunk_x, chunk_z).map(|chunk| {
+            (
+                chunk,
+                (position.x & 0xF) as usize,
+                position.y as usize,
+                (position.z & 0xF) as usize,
+            )
+        })
+    }
+
+    fn locate_mut(&mut self, position: BlockPosition) -> Option<(&mut Chunk, usize, usize, usize)> {
+        if !(0..256).contains(&position.y) {
+            return None;
+        }
+        let (chunk_x, chunk_z) = chunk_pos(position);
+        self.chunk_mut(chunk_x, chunk_z).map(|chunk| {
+            (
+                chunk,
+                (position.x & 0xF) as usize,
+                position.y as usize,
+                (position.z & 0xF) as usize,
+            )
+        })
+    }
+
+    /// Returns the block at a position, air if its chunk isn't loaded.
+    pub fn get_block(&self, position: BlockPosition) -> Block {
+        match self.locate(position) {
+            Some((chunk, x, y, z)) => chunk.get_block(x, y, z),
+            None => Block::AIR,
+        }
+    }
+
+    /// Sets a block, returning the one it replaced or `None` if its chunk
+    /// isn't loaded.
+    pub fn set_block(&mut self, position: BlockPosition, block: Block) -> Option<Block> {
+        self.locate_mut(position)
+            .and_then(|(chunk, x, y, z)| chunk.set_block(x, y, z, block))
+    }
+
+    pub fn get_block_light(&self, position: BlockPosition) -> u8 {
+        match self.locate(position) {
+            Some((chunk, x, y, z)) => chunk.get_block_light(x, y, z),
+            None => 0,
+        }
+    }
+
+    pub fn set_block_light(&mut self, position: BlockPosition, value: u8) {
+        if let Some((chunk, x, y, z)) = self.locate_mut(position) {
+            chunk.set_block_light(x, y, z, value);
+        }
+    }
+
+    pub fn get_sky_light(&self, position: BlockPosition) -> u8 {
+        match self.locate(position) {
+            Some((chunk, x, y, z)) => chunk.get_sky_light(x, y, z),
+            None if self.has_sky() => 15,
+            None => 0,
+        }
+    }
+
+    pub fn set_sky_light(&mut self, position: BlockPosition, value: u8) {
+        if let Some((chunk, x, y, z)) = self.locate_mut(position) {
+            chunk.set_sky_light(x, y, z, value);
+        }
+    }
+
+    /// Drains the block changes of every chunk, grouped by chunk.
+    pub fn take_block_changes(&mut self) -> Vec<((i32, i32), Vec<BlockChange>)> {
+        self.chunks
+            .values_mut()
+            .filter(|chunk| chunk.has_changes())
+            .map(|chunk| ((chunk.x, chunk.z), chunk.take_changes()))
+            .collect()
+    }
+}
+
+/// Builds the packet sending a chunk's block changes to a player, using a
+/// single block change when possible.
+pub fn block_changes_packet(
+    player: u128,
+    (chunk_x, chunk_z): (i32, i32),
+    changes: &[BlockChange],
+) -> ProxyBoundPacket {
+    match changes {
+        [change] => ProxyBoundPacket::BlockChange {
+            player,
+            position: BlockPosition {
+                x: (chunk_x << 4) | change.x as i32,
+                y: change.y as i32,
+                z: (chunk_z << 4) | change.z as i32,
+            },
+            block: change.block.state(),
+        },
+        changes => ProxyBoundPacket::MultiBlockChange {
+            player,
+            chunk_x,
+            chunk_z,
+            records: changes
+                .iter()
+                .map(|change| BlockRecord {
+                    x: change.x,
+                    y: change.y,
+                    z: change.z,
+                    block: change.block.state(),
+                })
+                .collect(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, process};
+
+    use super::*;
+    use generator::FlatGenerator;
+
+    fn world() -> World {
+        World::new(Dimension::Overworld, Arc::new(FlatGenerator::default()))
+    }
+
+    fn modify(world: &mut World, x: i32, z: i32) {
+        let position = BlockPosition {
+            x: x * 16,
+            y: 100,
+            z: z * 16,
+        };
+        world.set_block(position, Block::new(1, 0));
+    }
+
+    #[test]
+    fn evicts_unseen_chunks() {
+        let mut world = world();
+        for (x, z) in [(0, 0), (10, 0), (11, 0)] {
+            world.load_chunk(x, z);
+        }
+        assert_eq!(world.evict(|chunk| chunk == (11, 0)), 1);
+        assert!(world.chunk(0, 0).is_some());
+        assert!(world.chunk(10, 0).is_none());
+        assert!(world.chunk(11, 0).is_some());
+    }
+
+    #[test]
+    fn keeps_modified_chunks_without_storage() {
+        let mut world = world();
+        world.load_chunk(10, 0);
+        modify(&mut world, 10, 0);
+        assert_eq!(world.evict(|_| false), 0);
+        assert!(world.chunk(10, 0).is_some_and(Chunk::is_dirty));
+    }
+
+    #[test]
+    fn saves_modified_chunks_before_evicting_them() {
+        let directory = std::env::temp_dir().join(format!("makar-{}-evict", process::id()));
+        let generator = Arc::new(FlatGenerator::default());
+        let mut world = World::open(&directory, Dimension::Overworld, generator.clone());
+        world.load_chunk(10, 0);
+        modify(&mut world, 10, 0);
+        assert_eq!(world.evict(|_| false), 0);
+        world.flush();
+        assert_eq!(world.evict(|_| false), 1);
+
+        let mut reopened = World::open(&directory, Dimension::Overworld, generator);
+        let block = reopened.load_chunk(10, 0).get_block(0, 100, 0);
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(block, Block::new(1, 0));
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+
+use bytes::{Buf, BufMut};
+
+#[derive(Debug, thiserror::Error)]
+pub enum NbtError {
+    #[error("NBT incomplete")]
+    Incomplete,
+    #[error("unknown tag type {0}")]
+    UnknownTag(u8),
+    #[error("root tag isn't a compound")]
+    NotCompound,
+    #[error("invalid string")]
+    InvalidString,
+}
+
+/// A named binary tag, in the format used by 1.8 worlds.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<u8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(BTreeMap<String, Tag>),
+    IntArray(Vec<i32>),
+}
+
+impl Tag {
+    fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => 1,
+            Tag::Short(_) => 2,
+            Tag::Int(_) => 3,
+            Tag::Long(_) => 4,
+            Tag::Float(_) => 5,
+            Tag::Double(_) => 6,
+            Tag::ByteArray(_) => 7,
+            Tag::String(_) => 8,
+            Tag::List(_) => 9,
+            Tag::Compound(_) => 10,
+            Tag::IntArray(_) => 11,
+        }
+    }
+
+    /// Looks a tag up by name if this is a compound.
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(tags) => tags.get(name),
+            _ => None,
+        }
+    }
+
+    /// Reads a number, whatever its width.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Tag::Byte(v) => Some(*v as i64),
+            Tag::Short(v) => Some(*v as i64),
+            Tag::Int(v) => Some(*v as i64),
+            Tag::Long(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Tag::ByteArray(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+fn read_string(buf: &mut &[u8]) -> Result<String, NbtError> {
+    if buf.remaining() < 2 {
+        return Err(NbtError::Incomplete);
+    }
+    let len = buf.get_u16() as usize;
+    if buf.remaining() < len {
+        return Err(NbtError::Incomplete);
+    }
+    let string = String::from_utf8(buf[..len].to_vec()).map_err(|_| NbtError::InvalidString)?;
+    buf.advance(len);
+    Ok(string)
+}
+
+fn write_string(buf: &mut Vec<u8>, string: &str) {
+    buf.put_u16(string.len() as u16);
+    buf.put_slice(string.as_bytes());
+}
+
+/// Reads an array length, making sure the buffer holds that many elements.
+fn read_len(buf: &mut &[u8], element_size: usize) -> Result<usize, NbtError> {
+    if buf.remaining() < 4 {
+        return Err(NbtError::Incomplete);
+    }
+    let len = buf.get_i32().max(0) as usize;
+    if buf.remaining() < len * element_size {
+        return Err(NbtError::Incomplete);
+    }
+    Ok(len)
+}
+
+fn read_payload(buf: &mut &[u8], id: u8) -> Result<Tag, NbtError> {
+    let size = match id {
+        1 => 1,
+        2 => 2,
+        3 | 5 => 4,
+        4 | 6 => 8,
+        _ => 0,
+    };
+    if buf.remaining() < size {
+        return Err(NbtError::Incomplete);
+    }
+
+    Ok(match id {
+        1 => Tag::Byte(buf.get_i8()),
+        2 => Tag::Short(buf.get_i16()),
+        3 => Tag::Int(buf.get_i32()),
+        4 => Tag::Long(buf.get_i64()),
+        5 => Tag::Float(buf.get_f32()),
+        6 => Tag::Double(buf.get_f64()),
+        7 => {
+            let len = read_len(buf, 1)?;
+            let bytes = buf[..len].to_vec();
+            buf.advance(len);
+            Tag::ByteArray(bytes)
+        }
+        8 => Tag::String(read_string(buf)?),
+        9 => {
+            if buf.remaining() < 1 {
+                return Err(NbtError::Incomplete);
+            }
+            let element = buf.get_u8();
+            let len = read_len(buf, 0)?;
+            let mut list = Vec::with_capacity(len.min(1024));
+            for _ in 0..len {
+                list.push(read_payload(buf, element)?);
+            }
+            Tag::List(list)
+        }
+        10 => {
+            let mut tags = BTreeMap::new();
+            loop {
+                if buf.remaining() < 1 {
+                    return Err(NbtError::Incomplete);
+                }
+                let id = buf.get_u8();
+                if id == 0 {
+                    break;
+                }
+                let name = read_string(buf)?;
+                tags.insert(name, read_payload(buf, id)?);
+            }
+            Tag::Compound(tags)
+        }
+        11 => {
+            let len = read_len(buf, 4)?;
+            Tag::IntArray((0..len).map(|_| buf.get_i32()).collect())
+        }
+        id => return Err(NbtError::UnknownTag(id)),
+    })
+}
+
+fn write_payload(buf: &mut Vec<u8>, tag: &Tag) {
+    match tag {
+        Tag::Byte(v) => buf.put_i8(*v),
+        Tag::Short(v) => buf.put_i16(*v),
+        Tag::Int(v) => buf.put_i32(*v),
+        Tag::Long(v) => buf.put_i64(*v),
+        Tag::Float(v) => buf.put_f32(*v),
+        Tag::Double(v) => buf.put_f64(*v),
+        Tag::ByteArray(v) => {
+            buf.put_i32(v.len() as i32);
+            buf.put_slice(v);
+        }
+        Tag::String(v) => write_string(buf, v),
+        Tag::List(v) => {
+            // empty lists are written with the end tag as their element type
+            buf.put_u8(v.first().map_or(0, Tag::id));
+            buf.put_i32(v.len() as i32);
+            for tag in v {
+                write_payload(buf, tag);
+            }
+        }
+        Tag::Compound(v) => {
+            for (name, tag) in v {
+                buf.put_u8(tag.id());
+                write_string(buf, name);
+                write_payload(buf, tag);
+            }
+            buf.put_u8(0);
+        }
+        Tag::IntArray(v) => {
+            buf.put_i32(v.len() as i32);
+            for i in v {
+                buf.put_i32(*i);
+            }
+        }
+    }
+}
+
+/// Reads the unnamed root compound of an NBT document.
+pub fn read(mut buf: &[u8]) -> Result<Tag, NbtError> {
+    if buf.remaining() < 1 {
+        return Err(NbtError::Incomplete);
+    }
+    if buf.get_u8() != 10 {
+        return Err(NbtError::NotCompound);
+    }
+    read_string(&mut buf)?;
+    read_payload(&mut buf, 10)
+}
+
+/// Writes a root compound with an empty name.
+pub fn write(tag: &Tag) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.put_u8(tag.id());
+    write_string(&mut buf, "");
+    write_payload(&mut buf, tag);
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_every_tag() {
+        let tag = Tag::Compound(BTreeMap::from([
+            ("byte".to_string(), Tag::Byte(-1)),
+            ("short".to_string(), Tag::Short(-300)),
+            ("int".to_string(), Tag::Int(i32::MIN)),
+            ("long".to_string(), Tag::Long(i64::MAX)),
+            ("float".to_string(), Tag::Float(0.5)),
+            ("double".to_string(), Tag::Double(-2.25)),
+            ("bytes".to_string(), Tag::ByteArray(vec![0, 1, 255])),
+            ("string".to_string(), Tag::String("héllo".to_string())),
+            (
+                "list".to_string(),
+                Tag::List(vec![Tag::Short(1), Tag::Short(2)]),
+            ),
+            ("empty".to_string(), Tag::List(Vec::new())),
+            (
+                "compound".to_string(),
+                Tag::Compound(BTreeMap::from([("nested".to_string(), Tag::Int(7))])),
+            ),
+            ("ints".to_string(), Tag::IntArray(vec![-1, 0, 1])),
+        ]));
+        assert_eq!(read(&write(&tag)).unwrap(), tag);
+    }
+
+    #[test]
+    fn writes_an_unnamed_root() {
+        let tag = Tag::Compound(BTreeMap::from([("a".to_string(), Tag::Byte(1))]));
+        assert_eq!(write(&tag), [10, 0, 0, 1, 0, 1, b'a', 1, 0]);
+    }
+
+    #[test]
+    fn rejects_invalid_documents() {
+        assert!(matches!(read(&[]), Err(NbtError::Incomplete)));
+        assert!(matches!(read(&[1, 0, 0, 1]), Err(NbtError::NotCompound)));
+        assert!(matches!(
+            read(&[10, 0, 0, 1, 0, 1]),
+            Err(NbtError::Incomplete)
+        ));
+        assert!(matches!(
+            read(&[10, 0, 0, 12, 0, 0]),
+            Err(NbtError::UnknownTag(12))
+        ));
+    }
+}
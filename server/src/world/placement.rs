@@ -0,0 +1,326 @@
+use makar_protocol::{BlockFace, BlockPosition, ItemStack};
+
+use super::Block;
+
+/// The position next to a block, on one of its faces.
+pub fn neighbour(position: BlockPosition, face: BlockFace) -> BlockPosition {
+    let BlockPosition { x, y, z } = position;
+    let (dx, dy, dz) = match face {
+        BlockFace::Bottom => (0, -1, 0),
+        BlockFace::Top => (0, 1, 0),
+        BlockFace::North => (0, 0, -1),
+        BlockFace::South => (0, 0, 1),
+        BlockFace::West => (-1, 0, 0),
+        BlockFace::East => (1, 0, 0),
+    };
+    BlockPosition {
+        x: x + dx,
+        y: y + dy,
+        z: z + dz,
+    }
+}
+
+fn opposite(face: BlockFace) -> BlockFace {
+    match face {
+        BlockFace::Bottom => BlockFace::Top,
+        BlockFace::Top => BlockFace::Bottom,
+        BlockFace::North => BlockFace::South,
+        BlockFace::South => BlockFace::North,
+        BlockFace::West => BlockFace::East,
+        BlockFace::East => BlockFace::West,
+    }
+}
+
+/// The direction a player looks in, ignoring their pitch.
+fn facing(yaw: f32) -> BlockFace {
+    match (yaw * 4.0 / 360.0 + 0.5).floor() as i32 & 3 {
+        0 => BlockFace::South,
+        1 => BlockFace::West,
+        2 => BlockFace::North,
+        _ => BlockFace::East,
+    }
+}
+
+/// The metadata of blocks facing a direction, as the protocol numbers
+/// faces.
+fn index(face: BlockFace) -> u8 {
+    face as u8
+}
+
+/// The metadata of blocks only facing horizontally, from south clockwise.
+fn horizontal(face: BlockFace) -> u8 {
+    match face {
+        BlockFace::South => 0,
+        BlockFace::West => 1,
+        BlockFace::North => 2,
+        _ => 3,
+    }
+}
+
+fn is_horizontal(face: BlockFace) -> bool {
+    !matches!(face, BlockFace::Bottom | BlockFace::Top)
+}
+
+/// The slab doubled blocks of the same kind make.
+pub fn double_slab(id: u16) -> Option<u16> {
+    match id {
+        44 => Some(43),
+        126 => Some(125),
+        182 => Some(181),
+        _ => None,
+    }
+}
+
+/// The block a slab and a slab of the same kind already there merge into,
+/// whichever half each is.
+pub fn merge_slabs(existing: Block, placed: Block) -> Option<Block> {
+    let double = double_slab(placed.id)?;
+    let same = existing.id == placed.id && existing.meta & 7 == placed.meta & 7;
+    same.then_some(Block::new(double, placed.meta & 7))
+}
+
+/// A block placed by a player, with the upper half of doors and tall
+/// plants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub block: Block,
+    pub above: Option<Block>,
+    /// Whether the block hangs on the block it's placed against.
+    pub attached: bool,
+}
+
+/// The block an item places against a face of another block, turned the
+/// way the player looks and depending on where the face was clicked, in
+/// sixteenths of a block.
+pub fn placement(
+    item: &ItemStack,
+    face: BlockFace,
+    cursor_y: u8,
+    yaw: f32,
+    pitch: f32,
+) -> Option<Placement> {
+    let damage = (item.damage & 0xF) as u8;
+    let looking = facing(yaw);
+    let upper_half = face == BlockFace::Bottom || (is_horizontal(face) && cursor_y > 8);
+    let axis = match face {
+        BlockFace::Bottom | BlockFace::Top => 0,
+        BlockFace::West | BlockFace::East => 4,
+        BlockFace::North | BlockFace::South => 8,
+    };
+    let placed = |id: u16, meta: u8| {
+        Some(Placement {
+            block: Block::new(id, meta),
+            above: None,
+            attached: false,
+        })
+    };
+    let attached = |id: u16, meta: u8| {
+        Some(Placement {
+            block: Block::new(id, meta),
+            above: None,
+            attached: true,
+        })
+    };
+
+    let id = match item.id {
+        id @ 1..=255 => id as u16,
+        // items placing blocks
+        323 => {
+            return match face {
+                BlockFace::Bottom => None,
+                BlockFace::Top => {
+                    let rotation = ((yaw + 180.0) * 16.0 / 360.0 + 0.5).floor() as i32 & 15;
+                    placed(63, rotation as u8)
+                }
+                face => attached(68, index(face)),
+            }
+        }
+        324 | 330 | 427..=431 if face == BlockFace::Top => {
+            let id = match item.id {
+                324 => 64,
+                330 => 71,
+                id => (id - 427 + 193) as u16,
+            };
+            // the lower half tells where the door faces, the upper its hinge
+            return Some(Placement {
+                block: Block::new(id, (horizontal(looking) + 1) & 3),
+                above: Some(Block::new(id, 8)),
+                attached: false,
+            });
+        }
+        331 => 55,
+        338 => 83,
+        354 => 92,
+        356 => 93,
+        379 => 117,
+        380 => 118,
+        390 => 140,
+        404 => 149,
+        295 => 59,
+        361 => 104,
+        362 => 105,
+        372 => 115,
+        391 => 141,
+        392 => 142,
+        _ => return None,
+    };
+
+    match id {
+        // not held as blocks
+        7..=11
+        | 26
+        | 34
+        | 36
+        | 43
+        | 51
+        | 52
+        | 55
+        | 59
+        | 60
+        | 62
+        | 63
+        | 64
+        | 68
+        | 71
+        | 74
+        | 75
+        | 90
+        | 92..=94
+        | 104
+        | 105
+        | 115
+        | 117
+        | 118
+        | 119
+        | 124
+        | 125
+        | 127
+        | 132
+        | 137
+        | 140..=142
+        | 144
+        | 149
+        | 150
+        | 166
+        | 178
+        | 181
+        | 193..=197
+            if item.id < 256 =>
+        {
+            None
+        }
+        53 | 67 | 108 | 109 | 114 | 128 | 134..=136 | 156 | 163 | 164 | 180 => {
+            // east, west, south then north
+            let facing = 5 - index(looking);
+            placed(id, facing | if upper_half { 4 } else { 0 })
+        }
+        44 | 126 | 182 => placed(id, (damage & 7) | if upper_half { 8 } else { 0 }),
+        17 | 162 => placed(id, (damage & 3) | axis),
+        170 => placed(id, axis),
+        155 if damage == 2 => placed(
+            id,
+            match axis {
+                0 => 2,
+                4 => 3,
+                _ => 4,
+            },
+        ),
+        50 | 76 => match face {
+            BlockFace::Bottom => None,
+            BlockFace::Top => attached(id, 5),
+            BlockFace::East => attached(id, 1),
+            BlockFace::West => attached(id, 2),
+            BlockFace::South => attached(id, 3),
+            BlockFace::North => attached(id, 4),
+        },
+        65 | 131 if !is_horizontal(face) => None,
+        65 => attached(id, index(face)),
+        131 => attached(id, horizontal(face)),
+        77 | 143 => attached(
+            id,
+            match face {
+                BlockFace::Bottom => 0,
+                BlockFace::East => 1,
+                BlockFace::West => 2,
+                BlockFace::South => 3,
+                BlockFace::North => 4,
+                BlockFace::Top => 5,
+            },
+        ),
+        69 => {
+            let along_x = matches!(looking, BlockFace::West | BlockFace::East);
+            attached(
+                id,
+                match face {
+                    BlockFace::Bottom if along_x => 0,
+                    BlockFace::Bottom => 7,
+                    BlockFace::East => 1,
+                    BlockFace::West => 2,
+                    BlockFace::South => 3,
+                    BlockFace::North => 4,
+                    BlockFace::Top if along_x => 6,
+                    BlockFace::Top => 5,
+                },
+            )
+        }
+        106 if !is_horizontal(face) => None,
+        // the side of its block the vine hangs on
+        106 => attached(
+            id,
+            match opposite(face) {
+                BlockFace::South => 1,
+                BlockFace::West => 2,
+                BlockFace::North => 4,
+                _ => 8,
+            },
+        ),
+        96 | 167 if is_horizontal(face) => {
+            let facing = match face {
+                BlockFace::North => 0,
+                BlockFace::South => 1,
+                BlockFace::West => 2,
+                _ => 3,
+            };
+            attached(id, facing | if cursor_y > 8 { 8 } else { 0 })
+        }
+        96 | 167 => None,
+        // facing the player
+        54 | 61 | 130 | 146 => placed(id, index(opposite(looking))),
+        86 | 91 | 93 | 120 | 149 => placed(id, horizontal(opposite(looking))),
+        23 | 29 | 33 | 158 => {
+            let facing = match pitch {
+                pitch if pitch > 45.0 => BlockFace::Top,
+                pitch if pitch < -45.0 => BlockFace::Bottom,
+                _ => opposite(looking),
+            };
+            placed(id, index(facing))
+        }
+        154 => {
+            let facing = match opposite(face) {
+                BlockFace::Top => BlockFace::Bottom,
+                facing => facing,
+            };
+            placed(id, index(facing))
+        }
+        107 | 183..=187 => placed(id, horizontal(looking)),
+        145 => {
+            let facing = match looking {
+                BlockFace::South => BlockFace::West,
+                BlockFace::West => BlockFace::North,
+                BlockFace::North => BlockFace::East,
+                _ => BlockFace::South,
+            };
+            placed(id, horizontal(facing) | (damage & 3) << 2)
+        }
+        // players' leaves never decay
+        18 | 161 => placed(id, (damage & 3) | 4),
+        175 => Some(Placement {
+            block: Block::new(id, damage & 7),
+            above: Some(Block::new(id, 8)),
+            attached: false,
+        }),
+        1 | 3 | 5 | 6 | 12 | 19 | 24 | 31 | 35 | 38 | 95 | 97 | 98 | 139 | 155 | 159 | 160
+        | 168 | 171 | 179 => placed(id, damage),
+        id => placed(id, 0),
+    }
+}
@@ -0,0 +1,69 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Worker threads generating chunks in the background for every world of a
+/// game, so that the tick never waits on them.
+pub struct ChunkPool {
+    jobs: Option<Sender<Job>>,
+    queue: Arc<Mutex<Receiver<Job>>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl ChunkPool {
+    pub fn new(threads: usize) -> Self {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let queue = Arc::new(Mutex::new(queue));
+
+        let workers = (0..threads.max(1))
+            .map(|i| {
+                let queue = queue.clone();
+                thread::Builder::new()
+                    .name(format!("chunk-worker-{i}"))
+                    .spawn(move || loop {
+                        // the lock is released before running the job
+                        let job = queue.lock().map(|queue| queue.recv());
+                        match job {
+                            Ok(Ok(job)) => job(),
+                            _ => break,
+                        }
+                    })
+                    .expect("chunk worker should spawn")
+            })
+            .collect();
+
+        Self {
+            jobs: Some(jobs),
+            queue,
+            workers,
+        }
+    }
+
+    /// Queues a job for the next idle worker.
+    pub fn execute(&self, job: impl FnOnce() + Send + 'static) {
+        if let Some(jobs) = &self.jobs {
+            let _ = jobs.send(Box::new(job));
+        }
+    }
+}
+
+impl Drop for ChunkPool {
+    fn drop(&mut self) {
+        // closing the queue first so that idle workers waiting on it give the
+        // lock back, then dropping pending jobs so workers stop once they
+        // finish their current one
+        self.jobs.take();
+        if let Ok(queue) = self.queue.lock() {
+            queue.try_iter().for_each(drop);
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use makar_protocol::GameStateChange;
+
+/// How much rain and thunder darken the sky by each tick, fading in and out
+/// over 5 seconds.
+const FADE: f32 = 0.01;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Weather {
+    #[default]
+    Clear,
+    Rain,
+    /// Rain under an even darker sky.
+    Thunder,
+}
+
+/// The weather of a world, with how far the rain and thunder faded in.
+#[derive(Debug, Default)]
+pub(crate) struct Sky {
+    pub(crate) weather: Weather,
+    rain: f32,
+    thunder: f32,
+    /// Whether clients were told it's raining.
+    raining: bool,
+}
+
+impl Sky {
+    /// Fades the rain and thunder towards the weather by a tick, returning
+    /// what players in the world should be told.
+    pub(crate) fn update(&mut self) -> Vec<GameStateChange> {
+        let mut changes = Vec::new();
+        let raining = self.weather != Weather::Clear;
+        if raining != self.raining {
+            self.raining = raining;
+            changes.push(match raining {
+                true => GameStateChange::BeginRaining,
+                false => GameStateChange::EndRaining,
+            });
+        }
+
+        let rain = fade(self.rain, raining);
+        if rain != self.rain {
+            self.rain = rain;
+            changes.push(GameStateChange::RainLevel(rain));
+        }
+        let thunder = fade(self.thunder, self.weather == Weather::Thunder);
+        if thunder != self.thunder {
+            self.thunder = thunder;
+            changes.push(GameStateChange::ThunderLevel(thunder));
+        }
+        changes
+    }
+
+    /// What players arriving in the world should be told.
+    pub(crate) fn current(&self) -> Vec<GameStateChange> {
+        let mut changes = Vec::new();
+        if self.raining {
+            changes.push(GameStateChange::BeginRaining);
+        }
+        if self.rain > 0.0 {
+            changes.push(GameStateChange::RainLevel(self.rain));
+        }
+        if self.thunder > 0.0 {
+            changes.push(GameStateChange::ThunderLevel(self.thunder));
+        }
+        changes
+    }
+}
+
+fn fade(level: f32, towards_full: bool) -> f32 {
+    match towards_full {
+        true => (level + FADE).min(1.0),
+        false => (level - FADE).max(0.0),
+    }
+}